@@ -0,0 +1,216 @@
+use serde::{Deserialize, Serialize};
+
+use crate::audio::pipeline::DspStage;
+
+/// Постоянная времени измерения уровня (средний квадрат до attack/release),
+/// чтобы огибающая следила за RMS, а не за пиками каждого периода
+const LEVEL_WINDOW_MS: f32 = 20.0;
+/// Ниже этого уровня (dBFS) вход считается паузой: усиление не меняется,
+/// чтобы не поднимать шум между фразами
+const HOLD_BELOW_DBFS: f32 = -55.0;
+/// Минимальное усиление (громкий вход ослабляется не больше чем вдвое)
+const MIN_GAIN: f32 = 0.5;
+
+/// Параметры автоматической регулировки усиления (стадия `agc`).
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AgcParams {
+    /// Целевой уровень речи (RMS, dBFS)
+    pub target_dbfs: f32,
+    /// За сколько миллисекунд уровень догоняет рост громкости (усиление падает)
+    pub attack_ms: f32,
+    /// За сколько миллисекунд уровень следует за спадом громкости (усиление растёт)
+    pub release_ms: f32,
+    /// Максимальное усиление тихого входа, dB
+    pub max_gain_db: f32,
+}
+
+impl Default for AgcParams {
+    fn default() -> Self {
+        Self {
+            // ~0.12 RMS: оптимальный уровень для Whisper
+            target_dbfs: -18.0,
+            attack_ms: 10.0,
+            release_ms: 500.0,
+            max_gain_db: 20.0,
+        }
+    }
+}
+
+impl AgcParams {
+    pub fn validate(&self) -> Result<(), String> {
+        if !(-40.0..=-3.0).contains(&self.target_dbfs) {
+            return Err("AGC target must be between -40 and -3 dBFS".to_string());
+        }
+        if !(1.0..=1000.0).contains(&self.attack_ms) {
+            return Err("AGC attack must be between 1 and 1000 ms".to_string());
+        }
+        if !(10.0..=10_000.0).contains(&self.release_ms) {
+            return Err("AGC release must be between 10 and 10000 ms".to_string());
+        }
+        if !(0.0..=40.0).contains(&self.max_gain_db) {
+            return Err("AGC max gain must be between 0 and 40 dB".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// Коэффициент однополюсного сглаживания с постоянной времени `ms`.
+fn smoothing(sample_rate: u32, ms: f32) -> f32 {
+    let samples = (sample_rate as f32 * ms / 1000.0).max(1.0);
+    1.0 - (-1.0 / samples).exp()
+}
+
+/// Потоковая АРУ с attack/release.
+/// 
+/// Уровень входа (средний квадрат за ~`LEVEL_WINDOW_MS`) сглаживается огибающей:
+/// быстро вверх (`attack_ms`), медленно вниз (`release_ms`). Усиление в каждый
+/// момент приводит огибающую к `target_dbfs`, поэтому тихие и громкие части
+/// одного дубля выравниваются, а кашель влияет только на ближайшие сотни миллисекунд.
+/// В паузах усиление замирает. До первой речи огибающая стоит на целевом уровне
+/// (усиление 1.0): иначе начало первой фразы шло бы с максимальным усилением,
+/// пока огибающая не догонит вход.
+/// 
+/// Пики, которые проскакивают до реакции огибающей, ловит стадия `limiter`.
+/// Работает блоками любого размера без задержки (годится и для потокового распознавания).
+pub struct Agc {
+    sample_rate: u32,
+    params: AgcParams,
+    level_coef: f32,
+    attack_coef: f32,
+    release_coef: f32,
+    target: f32,
+    max_gain: f32,
+    hold_below: f32,
+    /// Средний квадрат входа за короткое окно
+    mean_square: f32,
+    /// Огибающая уровня (средний квадрат) с attack/release
+    envelope: f32,
+    gain: f32,
+}
+
+impl Agc {
+    /// Параметры:
+    /// * `sample_rate` - частота сигнала
+    /// * `params` - параметры стадии из профиля обработки
+    pub fn new(sample_rate: u32, params: &AgcParams) -> Self {
+        Self {
+            sample_rate,
+            params: *params,
+            level_coef: smoothing(sample_rate, LEVEL_WINDOW_MS),
+            attack_coef: smoothing(sample_rate, params.attack_ms),
+            release_coef: smoothing(sample_rate, params.release_ms),
+            target: 10f32.powf(params.target_dbfs / 20.0),
+            max_gain: 10f32.powf(params.max_gain_db / 20.0).max(MIN_GAIN),
+            hold_below: 10f32.powf(HOLD_BELOW_DBFS / 10.0),
+            mean_square: 0.0,
+            envelope: 10f32.powf(params.target_dbfs / 10.0),
+            gain: 1.0,
+        }
+    }
+
+    /// Обновляет огибающую по сэмплу и возвращает усиление.
+    fn level(&mut self, sample: f32) -> f32 {
+        self.mean_square += self.level_coef * (sample * sample - self.mean_square);
+        if self.mean_square < self.hold_below {
+            return self.gain;
+        }
+
+        let coef = if self.mean_square > self.envelope { self.attack_coef } else { self.release_coef };
+        self.envelope += coef * (self.mean_square - self.envelope);
+        self.gain = (self.target / self.envelope.sqrt()).clamp(MIN_GAIN, self.max_gain);
+        self.gain
+    }
+}
+
+impl DspStage for Agc {
+    fn process(&mut self, input: &[f32], output: &mut Vec<f32>) {
+        output.reserve(input.len());
+        for &sample in input {
+            let gain = self.level(sample);
+            output.push(sample * gain);
+        }
+    }
+
+    fn reset(&mut self) {
+        *self = Self::new(self.sample_rate, &self.params);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RATE: u32 = 16000;
+
+    fn tone(seconds: f32, amplitude: f32) -> Vec<f32> {
+        (0..(RATE as f32 * seconds) as usize)
+            .map(|n| amplitude * (2.0 * std::f32::consts::PI * 300.0 * n as f32 / RATE as f32).sin())
+            .collect()
+    }
+
+    fn rms_dbfs(samples: &[f32]) -> f32 {
+        10.0 * (samples.iter().map(|s| s * s).sum::<f32>() / samples.len() as f32).log10()
+    }
+
+    fn run(agc: &mut Agc, input: &[f32]) -> Vec<f32> {
+        let mut output = Vec::new();
+        agc.process(input, &mut output);
+        output
+    }
+
+    #[test]
+    fn test_quiet_and_loud_parts_reach_target() {
+        // Тихая фраза (-36 dBFS) после громкой (-15 dBFS) в одном дубле
+        let mut input = tone(4.0, 0.25);
+        input.extend(tone(4.0, 0.022));
+        let output = run(&mut Agc::new(RATE, &AgcParams::default()), &input);
+        assert_eq!(output.len(), input.len());
+
+        let second = RATE as usize;
+        let loud = rms_dbfs(&output[3 * second..4 * second]);
+        let quiet = rms_dbfs(&output[7 * second..8 * second]);
+        assert!((loud + 18.0).abs() < 1.0, "loud part at {} dBFS", loud);
+        assert!((quiet + 18.0).abs() < 1.0, "quiet part at {} dBFS", quiet);
+    }
+
+    #[test]
+    fn test_silence_is_not_amplified() {
+        let mut input = tone(1.0, 0.1);
+        input.extend(vec![0.0005; RATE as usize]);
+        let mut agc = Agc::new(RATE, &AgcParams::default());
+        let output = run(&mut agc, &input);
+
+        // После паузы усиление не выросло до максимума
+        assert!(agc.gain < 3.0, "gain {}", agc.gain);
+        assert!(output[RATE as usize + 100..].iter().all(|s| s.abs() < 0.002));
+    }
+
+    #[test]
+    fn test_speech_onset_is_not_boosted() {
+        // Фраза сразу после тишины: до реакции огибающей усиление не выше 1.0
+        for amplitude in [0.3, 0.6] {
+            let mut input = vec![0.0; RATE as usize / 2];
+            input.extend(tone(1.0, amplitude));
+            let output = run(&mut Agc::new(RATE, &AgcParams::default()), &input);
+
+            let onset = &output[RATE as usize / 2..RATE as usize / 2 + RATE as usize / 50];
+            let peak = onset.iter().fold(0.0f32, |peak, s| peak.max(s.abs()));
+            assert!(peak <= amplitude * 1.01, "amplitude {}: onset peak {}", amplitude, peak);
+        }
+    }
+
+    #[test]
+    fn test_chunked_processing_matches_whole_signal() {
+        let mut input = tone(0.5, 0.3);
+        input.extend(tone(0.5, 0.01));
+        let whole = run(&mut Agc::new(RATE, &AgcParams::default()), &input);
+
+        let mut agc = Agc::new(RATE, &AgcParams::default());
+        let mut chunked = Vec::new();
+        for chunk in input.chunks(100) {
+            agc.process(chunk, &mut chunked);
+        }
+        assert_eq!(chunked, whole);
+    }
+}
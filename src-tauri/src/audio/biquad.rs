@@ -0,0 +1,250 @@
+use std::f64::consts::PI;
+
+use serde::{Deserialize, Serialize};
+
+use crate::audio::pipeline::DspStage;
+
+/// Параметры фильтра высоких частот (стадия `high_pass`).
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HighPassParams {
+    /// Частота среза, Гц: ниже неё гул, стук по столу и вибрации, речи там нет
+    pub cutoff_hz: f32,
+    /// Добротность (0.707 - Баттерворт, без подъёма у среза)
+    pub q: f32,
+}
+
+impl Default for HighPassParams {
+    fn default() -> Self {
+        Self {
+            cutoff_hz: 80.0,
+            q: std::f32::consts::FRAC_1_SQRT_2,
+        }
+    }
+}
+
+impl HighPassParams {
+    pub fn validate(&self) -> Result<(), String> {
+        if !(20.0..=300.0).contains(&self.cutoff_hz) {
+            return Err("High-pass cutoff must be between 20 and 300 Hz".to_string());
+        }
+        if !(0.3..=2.0).contains(&self.q) {
+            return Err("High-pass Q must be between 0.3 and 2".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// Параметры удаления постоянной составляющей (стадия `dc_block`).
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DcBlockParams {
+    /// Частота среза, Гц (чем ниже, тем дольше фильтр устанавливается)
+    pub cutoff_hz: f32,
+}
+
+impl Default for DcBlockParams {
+    fn default() -> Self {
+        Self { cutoff_hz: 10.0 }
+    }
+}
+
+impl DcBlockParams {
+    pub fn validate(&self) -> Result<(), String> {
+        if !(1.0..=40.0).contains(&self.cutoff_hz) {
+            return Err("DC blocker cutoff must be between 1 and 40 Hz".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// Биквадратный фильтр (второго порядка), транспонированная вторая прямая форма.
+/// 
+/// Коэффициенты по формулам Audio EQ Cookbook (R. Bristow-Johnson),
+/// нормированы на a0. Состояние в f64: при низкой частоте среза и 48 кГц
+/// полюса близки к единичной окружности, и f32 копит ошибку.
+/// 
+/// Работает блоками любого размера без задержки.
+pub struct Biquad {
+    b0: f64,
+    b1: f64,
+    b2: f64,
+    a1: f64,
+    a2: f64,
+    z1: f64,
+    z2: f64,
+}
+
+impl Biquad {
+    /// Фильтр по коэффициентам передаточной функции
+    /// H(z) = (b0 + b1 z^-1 + b2 z^-2) / (a0 + a1 z^-1 + a2 z^-2).
+    pub fn new(b: [f64; 3], a: [f64; 3]) -> Self {
+        Self {
+            b0: b[0] / a[0],
+            b1: b[1] / a[0],
+            b2: b[2] / a[0],
+            a1: a[1] / a[0],
+            a2: a[2] / a[0],
+            z1: 0.0,
+            z2: 0.0,
+        }
+    }
+
+    /// Фильтр высоких частот.
+    /// 
+    /// Параметры:
+    /// * `sample_rate` - частота сигнала
+    /// * `params` - частота среза и добротность
+    pub fn high_pass(sample_rate: u32, params: &HighPassParams) -> Self {
+        let w0 = 2.0 * PI * params.cutoff_hz as f64 / sample_rate.max(1) as f64;
+        let alpha = w0.sin() / (2.0 * params.q as f64);
+        let cos = w0.cos();
+        Self::new(
+            [(1.0 + cos) / 2.0, -(1.0 + cos), (1.0 + cos) / 2.0],
+            [1.0 + alpha, -2.0 * cos, 1.0 - alpha],
+        )
+    }
+
+    /// Нормированные коэффициенты [b0, b1, b2, a1, a2].
+    #[cfg(test)]
+    pub fn coefficients(&self) -> [f64; 5] {
+        [self.b0, self.b1, self.b2, self.a1, self.a2]
+    }
+
+    /// Фильтрует один сэмпл.
+    pub fn filter(&mut self, sample: f32) -> f32 {
+        let x = sample as f64;
+        let y = self.b0 * x + self.z1;
+        self.z1 = self.b1 * x - self.a1 * y + self.z2;
+        self.z2 = self.b2 * x - self.a2 * y;
+        y as f32
+    }
+}
+
+impl DspStage for Biquad {
+    fn process(&mut self, input: &[f32], output: &mut Vec<f32>) {
+        output.reserve(input.len());
+        for &sample in input {
+            let filtered = self.filter(sample);
+            output.push(filtered);
+        }
+    }
+
+    fn reset(&mut self) {
+        self.z1 = 0.0;
+        self.z2 = 0.0;
+    }
+}
+
+/// Удаление постоянной составляющей: y[n] = x[n] - x[n-1] + R * y[n-1].
+/// 
+/// Однополюсный фильтр высоких частот с нулём на 0 Гц. Убирает смещение
+/// дешёвых микрофонов, почти не трогая всё выше `cutoff_hz`.
+pub struct DcBlocker {
+    pole: f64,
+    previous_input: f64,
+    previous_output: f64,
+}
+
+impl DcBlocker {
+    /// Параметры:
+    /// * `sample_rate` - частота сигнала
+    /// * `params` - частота среза
+    pub fn new(sample_rate: u32, params: &DcBlockParams) -> Self {
+        Self {
+            pole: (-2.0 * PI * params.cutoff_hz as f64 / sample_rate.max(1) as f64).exp(),
+            previous_input: 0.0,
+            previous_output: 0.0,
+        }
+    }
+}
+
+impl DspStage for DcBlocker {
+    fn process(&mut self, input: &[f32], output: &mut Vec<f32>) {
+        output.reserve(input.len());
+        for &sample in input {
+            let x = sample as f64;
+            let y = x - self.previous_input + self.pole * self.previous_output;
+            self.previous_input = x;
+            self.previous_output = y;
+            output.push(y as f32);
+        }
+    }
+
+    fn reset(&mut self) {
+        self.previous_input = 0.0;
+        self.previous_output = 0.0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RATE: u32 = 48000;
+
+    fn sine(frequency: f32, seconds: f32) -> Vec<f32> {
+        (0..(RATE as f32 * seconds) as usize)
+            .map(|n| 0.5 * (2.0 * std::f32::consts::PI * frequency * n as f32 / RATE as f32).sin())
+            .collect()
+    }
+
+    fn rms(samples: &[f32]) -> f32 {
+        (samples.iter().map(|s| s * s).sum::<f32>() / samples.len() as f32).sqrt()
+    }
+
+    /// Усиление стадии на частоте `frequency`, dB (по установившейся второй секунде).
+    fn gain_db(stage: &mut dyn DspStage, frequency: f32) -> f32 {
+        let input = sine(frequency, 2.0);
+        let mut output = Vec::new();
+        for chunk in input.chunks(1000) {
+            stage.process(chunk, &mut output);
+        }
+        let settled = RATE as usize..;
+        20.0 * (rms(&output[settled.clone()]) / rms(&input[settled])).log10()
+    }
+
+    #[test]
+    fn test_high_pass_frequency_response() {
+        let response = |frequency| gain_db(&mut Biquad::high_pass(RATE, &HighPassParams::default()), frequency);
+
+        // Второй порядок: -12 dB на октаву ниже среза
+        assert!(response(20.0) < -22.0, "20 Hz: {} dB", response(20.0));
+        assert!(response(40.0) < -11.0, "40 Hz: {} dB", response(40.0));
+        // Баттерворт: -3 dB на частоте среза
+        assert!((response(80.0) + 3.0).abs() < 0.2, "80 Hz: {} dB", response(80.0));
+        // Полоса речи не тронута
+        for frequency in [300.0, 1000.0, 4000.0] {
+            assert!(response(frequency).abs() < 0.1, "{} Hz: {} dB", frequency, response(frequency));
+        }
+    }
+
+    #[test]
+    fn test_high_pass_at_recognition_rate() {
+        // Коэффициенты зависят от частоты дискретизации: срез остаётся на 80 Hz
+        let rate = 16000;
+        let input: Vec<f32> = (0..2 * rate)
+            .map(|n| 0.5 * (2.0 * std::f32::consts::PI * 80.0 * n as f32 / rate as f32).sin())
+            .collect();
+        let mut output = Vec::new();
+        Biquad::high_pass(rate as u32, &HighPassParams::default()).process(&input, &mut output);
+        let gain = 20.0 * (rms(&output[rate..]) / rms(&input[rate..])).log10();
+        assert!((gain + 3.0).abs() < 0.2, "80 Hz: {} dB", gain);
+    }
+
+    #[test]
+    fn test_dc_blocker_removes_offset() {
+        // Смещение 0.2 поверх речевого тона
+        let input: Vec<f32> = sine(200.0, 2.0).iter().map(|s| s + 0.2).collect();
+        let mut output = Vec::new();
+        DcBlocker::new(RATE, &DcBlockParams::default()).process(&input, &mut output);
+
+        let settled = &output[RATE as usize..];
+        let mean = settled.iter().sum::<f32>() / settled.len() as f32;
+        assert!(mean.abs() < 1e-3, "mean {}", mean);
+        let gain = 20.0 * (rms(settled) / rms(&sine(200.0, 1.0))).log10();
+        assert!(gain.abs() < 0.1, "200 Hz: {} dB", gain);
+
+        assert!(gain_db(&mut DcBlocker::new(RATE, &DcBlockParams::default()), 1000.0).abs() < 0.01);
+    }
+}
@@ -1,242 +1,585 @@
-use std::{sync::{Arc, Mutex}, time::Instant};
+use std::sync::{atomic::{AtomicBool, AtomicU64, Ordering}, Arc, Mutex};
+use std::time::Duration;
 
-use cpal::{SampleFormat, SupportedStreamConfig, traits::{DeviceTrait, HostTrait, StreamTrait}};
-use tauri::State;
+use cpal::{FromSample, Sample, SampleFormat, SampleRate, SizedSample, StreamConfig, StreamError, SupportedStreamConfig, traits::{DeviceTrait, HostTrait, StreamTrait}};
+use rtrb::{Producer, RingBuffer};
+use serde::Serialize;
 
+use crate::audio::devices::{find_input_device, negotiate_config, supported_config_ranges, DeviceConfig, StreamPreferences};
+use crate::audio::source::{source_from_id, AudioSource, SourceConfig, SourceGuard};
 use crate::types::AudioCapture;
 
+/// Ёмкость кольцевого буфера между callback и потоком-потребителем (секунды звука).
+/// Потребитель забирает данные каждые ~10 мс, запас нужен на случай его задержки.
+const RING_BUFFER_SECONDS: usize = 2;
+/// Пауза источника без устройства, когда в кольцевом буфере нет места
+const SINK_FULL_WAIT: Duration = Duration::from_millis(1);
+/// Части текста ошибок backend, которые означают переполнение буфера драйвера (xrun)
+const OVERRUN_ERROR_HINTS: [&str; 4] = ["overrun", "xrun", "broken pipe", "epipe"];
+/// Части текста ошибок backend, которые означают, что устройства больше нет
+/// (ALSA ENODEV, WASAPI AUDCLNT_E_DEVICE_INVALIDATED, CoreAudio)
+const DEVICE_LOST_ERROR_HINTS: [&str; 4] = ["no such device", "enodev", "device_invalidated", "disconnected"];
+
+/// Вид ошибки stream во время захвата.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StreamErrorKind {
+    /// Устройство отключено - stream не восстановить на том же устройстве
+    DeviceLost,
+    /// Сбой backend (драйвера, звукового сервера) - обычно проходит после переоткрытия stream
+    Backend,
+    /// Переполнение буфера драйвера: часть сэмплов потеряна, stream продолжает работать
+    BufferOverrun,
+}
+
+/// Классифицированная ошибка stream, полученная из error callback.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CaptureError {
+    pub kind: StreamErrorKind,
+    pub message: String,
+}
+
+/// Ошибка stream, которую error callback оставляет потоку сессии.
+/// 
+/// Хранится последняя ошибка; переполнение не затирает более серьёзный сбой backend.
+pub type StreamErrorSlot = Arc<Mutex<Option<CaptureError>>>;
+
+/// Забирает ошибку stream, оставленную error callback.
+pub fn take_stream_error(slot: &StreamErrorSlot) -> Option<CaptureError> {
+    slot.lock().ok()?.take()
+}
+
+/// Классифицирует ошибку cpal.
+/// 
+/// cpal различает только отключение устройства и ошибки backend, поэтому
+/// переполнение и пропажа устройства внутри backend определяются по тексту ошибки.
+pub fn classify_stream_error(err: &StreamError) -> CaptureError {
+    let message = err.to_string();
+    let kind = match err {
+        StreamError::DeviceNotAvailable => StreamErrorKind::DeviceLost,
+        StreamError::BackendSpecific { .. } => {
+            let text = message.to_lowercase();
+            if DEVICE_LOST_ERROR_HINTS.iter().any(|hint| text.contains(hint)) {
+                StreamErrorKind::DeviceLost
+            } else if OVERRUN_ERROR_HINTS.iter().any(|hint| text.contains(hint)) {
+                StreamErrorKind::BufferOverrun
+            } else {
+                StreamErrorKind::Backend
+            }
+        }
+    };
+    CaptureError { kind, message }
+}
+
+/// Открытый поток захвата: источник и потребительская сторона кольцевого буфера.
+pub struct CaptureStream {
+    /// Работающий источник; drop останавливает его
+    pub stream: SourceGuard,
+    pub consumer: rtrb::Consumer<f32>,
+    pub sample_rate: u32,
+    pub channels: u16,
+    pub sample_format: SampleFormat,
+    /// Имя устройства (источника), на котором открыт stream
+    pub device_name: String,
+    /// Stream записывает звук системы, а не микрофон
+    pub is_loopback: bool,
+    /// ID, по которому stream можно открыть заново (после сбоя backend)
+    pub source_id: String,
+}
+
 /// Инициализирует и запускает захват аудио с указанного устройства.
 /// 
+/// Кроме устройств ввода принимает ID loopback источников (см. `source_from_id`).
+/// 
+/// Параметры:
+/// * `state_arc` - глобальное состояние AudioCapture с флагами записи
+/// * `device_id` - ID аудиоустройства (`InputDevice::id`); имя тоже принимается,
+///   ненайденное устройство ищется по похожему имени
+/// * `preferences` - желаемые частота, количество каналов и формат
+pub fn start_audio_capture_with_stream(
+    state_arc: Arc<Mutex<AudioCapture>>,
+    device_id: String,
+    preferences: &StreamPreferences,
+) -> Result<CaptureStream, String> {
+    log::debug!("Starting audio capture on device: {}", device_id);
+    let stream = open_source(state_arc, source_from_id(&device_id)?, preferences)?;
+    Ok(CaptureStream { source_id: device_id, ..stream })
+}
+
+/// Открывает stream в потоке, который будет им владеть (cpal stream не Send).
+pub type StreamOpener = Box<dyn FnOnce(Arc<Mutex<AudioCapture>>) -> Result<CaptureStream, String> + Send>;
+
+/// Открывает stream на устройстве (см. `start_audio_capture_with_stream`).
+pub fn device_opener(device_id: String, preferences: StreamPreferences) -> StreamOpener {
+    Box::new(move |state_arc| start_audio_capture_with_stream(state_arc, device_id, &preferences))
+}
+
+/// Открывает источник звука и связывает его с кольцевым буфером.
+/// 
 /// Реализует лучшие практики для высокого качества распознавания:
 /// - Захватывает аудио в монo (1 канал) - оптимально для речи
 /// - Поддерживает 48000 Hz (выше 44100, улучшает детализацию)
 /// - Использует F32 (без потерь) для сохранения максимального качества
 /// - Автоматически конвертирует I16/U16 в F32 с правильной нормализацией
 /// - Предотвращает многократное перекодирование (one-pass conversion)
-/// - Автоматический стоп при достижении 30 секунд максимальной длительности
 /// 
-/// Stream создаётся для одного из трёх форматов: F32, I16, U16 (конвертируются в f32).
-/// Callback проверяет is_recording перед добавлением в буфер.
+/// Источник пишет interleaved f32 сэмплы в SPSC кольцевой буфер через `SampleSink`.
+/// Если буфер переполнен, лишние сэмплы отбрасываются и учитываются в
+/// `AudioCapture::overrun_samples`. Сэмплы забирает поток RecordingSession.
 /// 
-/// Параметры:
-/// * `state` - глобальное состояние AudioCapture с буфером и флагами
-/// * `device_name` - имя аудиоустройства для захвата (из cpal::input_devices)
-pub fn start_audio_capture_with_stream(
-    state: State<'_, Arc<Mutex<AudioCapture>>>,
-    device_name: String,
-) -> Result<cpal::Stream, String> {
-    log::debug!("Starting audio capture on device: {}", device_name);
-    // Clone Arc to move into audio callback for duration checks
-    let state_arc = state.inner().clone();
-    let mut capture = state_arc.lock().unwrap();
-    if *capture.is_recording.lock().unwrap() {
-        return Err("Already recording".to_string());
-    }
-
-    let host = cpal::default_host();
-    let device = host.input_devices()
-        .map_err(|e| format!("Device error: {}", e))?
-        .find(|d| d.name().unwrap_or_default() == device_name)
-        .ok_or_else(|| "Device not found".to_string())?;
-
-    let config: SupportedStreamConfig = device.default_input_config()
-        .map_err(|e| format!("Config error: {}", e))?
-        .into();
-    let sample_format = config.sample_format();
-
-    let buffer = capture.buffer.clone();
-    *capture.is_recording.lock().unwrap() = true;
-    capture.start_time = Some(Instant::now());
-    capture.sample_rate = config.sample_rate().0;
-    capture.channels = config.channels() as u16;
-    
-    // Очищаем буфер перед новой записью
-    buffer.lock().unwrap().clear();
-
-    let is_recording = Arc::clone(&capture.is_recording);
-    // Keep sample_rate for buffer trimming and duration check
-    let sample_rate = capture.sample_rate;
-    let channels = config.channels() as usize;
-    // Max recording seconds (auto-stop)
-    const MAX_RECORD_SECONDS: usize = 30;
-    let max_samples = sample_rate as usize * channels * MAX_RECORD_SECONDS;
-    
-    // Флаг для логирования авто-стопа только один раз
-    let auto_stop_logged = Arc::new(Mutex::new(false));
-    
+/// Если устройство отключилось, источник выставляет `AudioCapture::device_lost`;
+/// если источник закончился (конец файла), - `AudioCapture::source_ended`.
+/// Остальные ошибки stream попадают в `AudioCapture::stream_error`.
+pub fn open_source(
+    state_arc: Arc<Mutex<AudioCapture>>,
+    mut source: Box<dyn AudioSource>,
+    preferences: &StreamPreferences,
+) -> Result<CaptureStream, String> {
+    let mut capture = state_arc.lock().map_err(|_| "Failed to lock audio state".to_string())?;
+    if capture.is_capturing.load(Ordering::Acquire) {
+        return Err("Audio stream is already open".to_string());
+    }
+
+    let config = source.configure(preferences)?;
+    let sample_rate = config.sample_rate;
+    let channels = config.channels.max(1);
+
+    capture.sample_rate = sample_rate;
+    capture.channels = channels;
+    capture.device_lost.store(false, Ordering::Release);
+    capture.source_ended.store(false, Ordering::Release);
+    take_stream_error(&capture.stream_error);
+
+    let (producer, consumer) = RingBuffer::<f32>::new(sample_rate as usize * channels as usize * RING_BUFFER_SECONDS);
+
     log::info!(
         "Audio capture config: sample_rate={}Hz, channels={}, format={:?}",
         sample_rate,
         channels,
-        sample_format,
+        config.sample_format,
     );
-    log::info!(
-        "Quality settings: {} Hz (выше 44.1kHz для деталей), {} ({} лучше стерео), {} формат (без потерь)",
+    let sink = SampleSink {
+        producer,
+        channels: channels as usize,
+        is_capturing: Arc::clone(&capture.is_capturing),
+        overruns: Arc::clone(&capture.overrun_samples),
+        device_lost: Arc::clone(&capture.device_lost),
+        source_ended: Arc::clone(&capture.source_ended),
+        stream_error: Arc::clone(&capture.stream_error),
+    };
+
+    // Флаг выставляется до запуска: источник без устройства начинает писать сразу
+    capture.is_capturing.store(true, Ordering::Release);
+    capture.active_device = Some(config.name.clone());
+
+    let stream = match source.start(sink) {
+        Ok(stream) => stream,
+        Err(e) => {
+            capture.is_capturing.store(false, Ordering::Release);
+            capture.active_device = None;
+            return Err(e);
+        }
+    };
+
+    Ok(CaptureStream {
+        stream,
+        consumer,
         sample_rate,
-        if channels == 1 { "Mono" } else { "Stereo" },
-        if channels == 1 { "оптимально для речи" } else { "потребует синхронизации" },
-        match sample_format {
-            SampleFormat::F32 => "F32 (без потерь)",
-            SampleFormat::I16 => "I16 (16-bit, буду конвертировать в F32 без потерь)",
-            SampleFormat::U16 => "U16 (буду конвертировать в F32 без потерь)",
-            _ => "Неизвестный формат (будет отклонён)",
+        channels,
+        sample_format: config.sample_format,
+        source_id: config.name.clone(),
+        device_name: config.name,
+        is_loopback: config.is_loopback,
+    })
+}
+
+/// Запись в кольцевой буфер со стороны источника.
+/// 
+/// Держит атомарные флаги, которые источник разделяет с потоком сессии.
+pub struct SampleSink {
+    producer: Producer<f32>,
+    channels: usize,
+    is_capturing: Arc<AtomicBool>,
+    overruns: Arc<AtomicU64>,
+    device_lost: Arc<AtomicBool>,
+    source_ended: Arc<AtomicBool>,
+    stream_error: StreamErrorSlot,
+}
+
+impl SampleSink {
+    /// Пишет сэмплы из real-time callback: без блокировок и аллокаций,
+    /// не поместившиеся сэмплы отбрасываются (см. `push_samples`).
+    pub fn push<I>(&mut self, samples: I)
+    where
+        I: ExactSizeIterator<Item = f32>,
+    {
+        if !self.is_capturing.load(Ordering::Acquire) {
+            return; // stop processing if capture flag cleared
         }
-    );
+        push_samples(&mut self.producer, samples, self.channels, &self.overruns);
+    }
 
-    let stream = match sample_format {
-        SampleFormat::F32 => {
-            let is_recording = Arc::clone(&is_recording);
-            let state_for_cb = state_arc.clone();
-            let auto_stop_logged = auto_stop_logged.clone();
-            device.build_input_stream(
-                &config.into(),
-                move |data: &[f32], _| {
-                    if !*is_recording.lock().unwrap() {
-                        return; // stop processing if recording flag cleared
-                    }
-                    
-                    let mut buf = buffer.lock().unwrap();
-                    
-                    // Check if we're about to exceed max duration
-                    if buf.len() + data.len() > max_samples {
-                        // Stop recording - only process up to max_samples
-                        let remaining = max_samples.saturating_sub(buf.len());
-                        if remaining > 0 {
-                            buf.extend_from_slice(&data[..remaining]);
-                        }
-                        drop(buf);
-                        
-                        // Mark recording stopped
-                        *is_recording.lock().unwrap() = false;
-                        if let Ok(mut s) = state_for_cb.lock() {
-                            s.start_time = None;
-                        }
-                        
-                        // Log only once
-                        let mut logged = auto_stop_logged.lock().unwrap();
-                        if !*logged {
-                            log::info!("Max recording duration reached ({}s), auto-stopping. Buffer size: {} samples", MAX_RECORD_SECONDS, max_samples);
-                            *logged = true;
-                        }
-                        return;
-                    }
-                    
-                    buf.extend_from_slice(data);
-                },
-                move |err| {
-                    log::error!("Stream error: {}", err);
-                },
-                None,
-            ).map_err(|e| format!("Stream creation failed: {}", e))?
+    /// Пишет сэмплы из обычного потока: ждёт места в буфере, ничего не теряя.
+    /// 
+    /// Возвращает false, если выставлен `stop` (источник останавливают).
+    /// Пока stream закрывается (`is_capturing` снят), сэмплы отбрасываются, как в callback.
+    pub fn push_blocking(&mut self, samples: &[f32], stop: &AtomicBool) -> bool {
+        let mut rest = samples;
+        while !rest.is_empty() {
+            if stop.load(Ordering::Acquire) {
+                return false;
+            }
+            if !self.is_capturing.load(Ordering::Acquire) {
+                return true;
+            }
+            let mut writable = self.producer.slots().min(rest.len());
+            writable -= writable % self.channels;
+            if writable == 0 {
+                std::thread::sleep(SINK_FULL_WAIT);
+                continue;
+            }
+            if let Ok(chunk) = self.producer.write_chunk_uninit(writable) {
+                chunk.fill_from_iter(rest[..writable].iter().copied());
+            }
+            rest = &rest[writable..];
         }
-        SampleFormat::I16 => {
-            let is_recording = Arc::clone(&is_recording);
-            let state_for_cb = state_arc.clone();
-            let auto_stop_logged = auto_stop_logged.clone();
-            device.build_input_stream(
-                &config.into(),
-                move |data: &[i16], _| {
-                    if !*is_recording.lock().unwrap() {
-                        return;
-                    }
-                    
-                    let mut buf = buffer.lock().unwrap();
-                    
-                    // Конвертация I16 -> F32 БЕЗ ПОТЕРЬ (одноразовая конвертация)
-                    // Правильная нормализация: [-32768, 32767] -> [-1.0, 1.0]
-                    // Это соответствует рекомендации избегать многократного перекодирования
-                    let converted: Vec<f32> = data
-                        .iter()
-                        .map(|&sample| {
-                            if sample < 0 {
-                                sample as f32 / 32768.0
-                            } else {
-                                sample as f32 / 32767.0
-                            }
-                        })
-                        .collect();
-                    
-                    if buf.len() + converted.len() > max_samples {
-                        let remaining = max_samples.saturating_sub(buf.len());
-                        if remaining > 0 {
-                            buf.extend_from_slice(&converted[..remaining]);
-                        }
-                        drop(buf);
-                        
-                        *is_recording.lock().unwrap() = false;
-                        if let Ok(mut s) = state_for_cb.lock() {
-                            s.start_time = None;
-                        }
-                        
-                        let mut logged = auto_stop_logged.lock().unwrap();
-                        if !*logged {
-                            log::info!("Максимальная длительность записи {}s достигнута, авто-стоп", MAX_RECORD_SECONDS);
-                            *logged = true;
-                        }
-                        return;
-                    }
-                    
-                    buf.extend(converted);
-                },
-                move |err| {
-                    log::error!("Stream error: {}", err);
-                },
-                None,
-            ).map_err(|e| format!("Stream creation failed: {}", e))?
+        true
+    }
+
+    /// Error callback stream, который сообщает об ошибках потоку сессии.
+    fn error_callback(&self) -> impl FnMut(StreamError) + Send + 'static {
+        stream_error_callback(Arc::clone(&self.device_lost), Arc::clone(&self.stream_error))
+    }
+
+    /// Сообщает, что источник закончился и новых сэмплов не будет.
+    pub fn finish(&self) {
+        self.source_ended.store(true, Ordering::Release);
+    }
+}
+
+/// Источник звука - устройство ввода cpal.
+/// 
+/// Stream создаётся для любого формата cpal (I8..I64, U8..U64, F32, F64),
+/// сэмплы конвертируются в f32 одним обобщённым callback.
+/// Callback работает в real-time потоке, поэтому не берёт мьютексов и не аллоцирует.
+/// 
+/// В режиме `output` stream записи открывается на устройстве вывода
+/// (WASAPI loopback, см. loopback.rs).
+pub struct CpalSource {
+    query: String,
+    output: bool,
+    opened: Option<(cpal::Device, SupportedStreamConfig)>,
+}
+
+impl CpalSource {
+    /// `query` - ID аудиоустройства (`InputDevice::id`) или имя
+    pub fn new(query: impl Into<String>) -> Self {
+        Self { query: query.into(), output: false, opened: None }
+    }
+
+    /// Запись того, что играет устройство вывода с именем `name`.
+    #[cfg(windows)]
+    pub fn output(name: impl Into<String>) -> Self {
+        Self { query: name.into(), output: true, opened: None }
+    }
+}
+
+impl AudioSource for CpalSource {
+    /// Конфигурация выбирается из поддерживаемых устройством как ближайшая
+    /// к `preferences` (см. `negotiate_config`); без предпочтений используется
+    /// конфигурация устройства по умолчанию.
+    fn configure(&mut self, preferences: &StreamPreferences) -> Result<SourceConfig, String> {
+        if self.output {
+            return self.configure_output();
         }
-        SampleFormat::U16 => {
-            let is_recording = Arc::clone(&is_recording);
-            let state_for_cb = state_arc.clone();
-            let auto_stop_logged = auto_stop_logged.clone();
-            device.build_input_stream(
-                &config.into(),
-                move |data: &[u16], _| {
-                    if !*is_recording.lock().unwrap() {
-                        return;
-                    }
-                    
-                    let mut buf = buffer.lock().unwrap();
-                    
-                    // Конвертация U16 -> F32 БЕЗ ПОТЕРЬ (одноразовая конвертация)
-                    // Нормализация: [0, 65535] -> [-1.0, 1.0]
-                    let converted: Vec<f32> = data
-                        .iter()
-                        .map(|&sample| (sample as f32 - 32768.0) / 32768.0)
-                        .collect();
-                    
-                    if buf.len() + converted.len() > max_samples {
-                        let remaining = max_samples.saturating_sub(buf.len());
-                        if remaining > 0 {
-                            buf.extend_from_slice(&converted[..remaining]);
-                        }
-                        drop(buf);
-                        
-                        *is_recording.lock().unwrap() = false;
-                        if let Ok(mut s) = state_for_cb.lock() {
-                            s.start_time = None;
-                        }
-                        
-                        let mut logged = auto_stop_logged.lock().unwrap();
-                        if !*logged {
-                            log::info!("Максимальная длительность записи {}s достигнута, авто-стоп", MAX_RECORD_SECONDS);
-                            *logged = true;
-                        }
-                        return;
-                    }
-                    
-                    buf.extend(converted);
-                },
-                move |err| {
-                    log::error!("Stream error: {}", err);
-                },
-                None,
-            ).map_err(|e| format!("Stream creation failed: {}", e))?
+
+        let (device, info) = find_input_device(&self.query)?;
+        let config = choose_stream_config(&device, info.default_config.as_ref(), preferences)?;
+        let source_config = SourceConfig {
+            sample_rate: config.sample_rate().0,
+            channels: config.channels(),
+            sample_format: config.sample_format(),
+            is_loopback: info.is_loopback,
+            name: info.name,
+        };
+        self.opened = Some((device, config));
+        Ok(source_config)
+    }
+
+    fn start(self: Box<Self>, sink: SampleSink) -> Result<SourceGuard, String> {
+        let (device, config) = self.opened.ok_or_else(|| "Audio device is not configured".to_string())?;
+        let sample_format = config.sample_format();
+        let stream_config: StreamConfig = config.into();
+        let stream = match sample_format {
+            SampleFormat::I8 => build_input_stream::<i8>(&device, &stream_config, sink),
+            SampleFormat::I16 => build_input_stream::<i16>(&device, &stream_config, sink),
+            SampleFormat::I32 => build_input_stream::<i32>(&device, &stream_config, sink),
+            SampleFormat::I64 => build_input_stream::<i64>(&device, &stream_config, sink),
+            SampleFormat::U8 => build_input_stream::<u8>(&device, &stream_config, sink),
+            SampleFormat::U16 => build_input_stream::<u16>(&device, &stream_config, sink),
+            SampleFormat::U32 => build_input_stream::<u32>(&device, &stream_config, sink),
+            SampleFormat::U64 => build_input_stream::<u64>(&device, &stream_config, sink),
+            SampleFormat::F32 => build_input_stream::<f32>(&device, &stream_config, sink),
+            SampleFormat::F64 => build_input_stream::<f64>(&device, &stream_config, sink),
+            _ => Err(format!("Unsupported sample format: {:?}", sample_format)),
+        }?;
+
+        stream.play().map_err(|e| format!("Stream play error: {}", e))?;
+        Ok(Box::new(stream))
+    }
+}
+
+impl CpalSource {
+    /// Открывает устройство вывода в его конфигурации по умолчанию:
+    /// loopback stream пишет микс в формате устройства.
+    fn configure_output(&mut self) -> Result<SourceConfig, String> {
+        let host = cpal::default_host();
+        let device = host
+            .output_devices()
+            .map_err(|e| format!("Failed to retrieve output devices: {}", e))?
+            .find(|d| d.name().is_ok_and(|name| name == self.query))
+            .ok_or_else(|| format!("Output device not found: {}", self.query))?;
+        let config = device.default_output_config().map_err(|e| format!("Config error: {}", e))?;
+
+        let source_config = SourceConfig {
+            sample_rate: config.sample_rate().0,
+            channels: config.channels(),
+            sample_format: config.sample_format(),
+            name: self.query.clone(),
+            is_loopback: true,
+        };
+        self.opened = Some((device, config));
+        Ok(source_config)
+    }
+}
+
+/// Форматы, для которых есть ветка в `CpalSource::start`.
+fn is_supported_format(format: SampleFormat) -> bool {
+    matches!(
+        format,
+        SampleFormat::I8 | SampleFormat::I16 | SampleFormat::I32 | SampleFormat::I64
+            | SampleFormat::U8 | SampleFormat::U16 | SampleFormat::U32 | SampleFormat::U64
+            | SampleFormat::F32 | SampleFormat::F64
+    )
+}
+
+/// Создаёт входной stream для сэмплов типа `T`.
+/// 
+/// Один код для всех форматов cpal: сэмплы конвертируются в f32 на лету
+/// (`sample_to_f32`), без промежуточного Vec, и пишутся в кольцевой буфер.
+fn build_input_stream<T>(
+    device: &cpal::Device,
+    config: &StreamConfig,
+    mut sink: SampleSink,
+) -> Result<cpal::Stream, String>
+where
+    T: SizedSample,
+    f32: FromSample<T>,
+{
+    let error_callback = sink.error_callback();
+
+    device.build_input_stream(
+        config,
+        move |data: &[T], _| sink.push(data.iter().map(|&s| sample_to_f32(s))),
+        error_callback,
+        None,
+    ).map_err(|e| format!("Stream creation failed: {}", e))
+}
+
+/// Выбирает конфигурацию stream, ближайшую к предпочтениям.
+/// 
+/// Рассматриваются только форматы, которые умеет конвертировать callback.
+/// Если устройство не перечисляет конфигурации, используется конфигурация по умолчанию.
+fn choose_stream_config(
+    device: &cpal::Device,
+    default: Option<&DeviceConfig>,
+    preferences: &StreamPreferences,
+) -> Result<SupportedStreamConfig, String> {
+    let default_config = || device.default_input_config().map_err(|e| format!("Config error: {}", e));
+
+    let (ranges, infos): (Vec<_>, Vec<_>) = supported_config_ranges(device)?
+        .into_iter()
+        .filter(|(range, _)| is_supported_format(range.sample_format()))
+        .unzip();
+
+    let default = match default {
+        Some(default) => default.clone(),
+        None => {
+            let config = default_config()?;
+            DeviceConfig {
+                sample_rate: config.sample_rate().0,
+                channels: config.channels(),
+                sample_format: format!("{:?}", config.sample_format()),
+            }
         }
-        _ => {
-            return Err(format!("Unsupported sample format: {:?}", sample_format));
+    };
+
+    match negotiate_config(&infos, preferences, &default) {
+        Some((index, rate)) => Ok(ranges[index].with_sample_rate(SampleRate(rate))),
+        None => default_config(),
+    }
+}
+
+/// Error callback stream: отключение устройства отмечается флагом `device_lost`,
+/// остальные ошибки оставляются в `stream_error` для потока сессии.
+/// 
+/// Вызывается из потока backend, а не из audio callback, поэтому мьютекс здесь допустим.
+fn stream_error_callback(device_lost: Arc<AtomicBool>, stream_error: StreamErrorSlot) -> impl FnMut(StreamError) + Send + 'static {
+    move |err| {
+        let error = classify_stream_error(&err);
+        match error.kind {
+            StreamErrorKind::DeviceLost => {
+                log::error!("Stream error, device lost: {}", error.message);
+                device_lost.store(true, Ordering::Release);
+            }
+            StreamErrorKind::Backend => {
+                log::error!("Stream error: {}", error.message);
+                if let Ok(mut slot) = stream_error.lock() {
+                    *slot = Some(error);
+                }
+            }
+            StreamErrorKind::BufferOverrun => {
+                log::warn!("Stream buffer overrun: {}", error.message);
+                if let Ok(mut slot) = stream_error.lock() {
+                    slot.get_or_insert(error);
+                }
+            }
         }
+    }
+}
+
+/// Записывает сэмплы в кольцевой буфер без блокировок и аллокаций.
+/// 
+/// Вызывается из real-time callback. Сэмплы, которые не поместились в буфер,
+/// отбрасываются и прибавляются к счётчику переполнений. Пишутся только целые
+/// фреймы, чтобы после переполнения каналы не перепутались.
+/// 
+/// Параметры:
+/// * `producer` - производитель SPSC кольцевого буфера
+/// * `samples` - итератор сконвертированных в f32 сэмплов
+/// * `channels` - количество каналов (размер фрейма)
+/// * `overruns` - счётчик потерянных сэмплов
+fn push_samples<I>(producer: &mut Producer<f32>, samples: I, channels: usize, overruns: &AtomicU64)
+where
+    I: ExactSizeIterator<Item = f32>,
+{
+    let total = samples.len();
+    let mut writable = producer.slots().min(total);
+    writable -= writable % channels.max(1);
+    let written = match producer.write_chunk_uninit(writable) {
+        Ok(chunk) => chunk.fill_from_iter(samples),
+        Err(_) => 0,
     };
 
-    stream.play().map_err(|e| format!("Stream play error: {}", e))?;
-    Ok(stream)
+    if written < total {
+        overruns.fetch_add((total - written) as u64, Ordering::Relaxed);
+    }
+}
+
+/// Конвертация сэмпла любого формата cpal в f32 в диапазоне [-1.0, 1.0].
+/// 
+/// Целые со знаком делятся на 2^(bits-1), беззнаковые сначала сдвигаются
+/// к нулю (середина диапазона = тишина), F64 сужается до f32.
+fn sample_to_f32<T>(sample: T) -> f32
+where
+    T: Sample,
+    f32: FromSample<T>,
+{
+    sample.to_sample::<f32>()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(actual: f32, expected: f32) {
+        assert!((actual - expected).abs() < 1e-4, "expected {}, got {}", expected, actual);
+    }
+
+    #[test]
+    fn test_signed_formats_normalization() {
+        assert_close(sample_to_f32(i8::MIN), -1.0);
+        assert_close(sample_to_f32(0i8), 0.0);
+        assert_close(sample_to_f32(i8::MAX), 127.0 / 128.0);
+
+        assert_close(sample_to_f32(i16::MIN), -1.0);
+        assert_close(sample_to_f32(0i16), 0.0);
+        assert_close(sample_to_f32(i16::MAX), 1.0);
+        assert_close(sample_to_f32(-16384i16), -0.5);
+
+        assert_close(sample_to_f32(i32::MIN), -1.0);
+        assert_close(sample_to_f32(0i32), 0.0);
+        assert_close(sample_to_f32(i32::MAX), 1.0);
+
+        assert_close(sample_to_f32(i64::MIN), -1.0);
+        assert_close(sample_to_f32(0i64), 0.0);
+        assert_close(sample_to_f32(i64::MAX), 1.0);
+    }
+
+    #[test]
+    fn test_unsigned_formats_normalization() {
+        assert_close(sample_to_f32(0u8), -1.0);
+        assert_close(sample_to_f32(128u8), 0.0);
+        assert_close(sample_to_f32(u8::MAX), 127.0 / 128.0);
+
+        assert_close(sample_to_f32(0u16), -1.0);
+        assert_close(sample_to_f32(32768u16), 0.0);
+        assert_close(sample_to_f32(u16::MAX), 1.0);
+
+        assert_close(sample_to_f32(0u32), -1.0);
+        assert_close(sample_to_f32(1u32 << 31), 0.0);
+        assert_close(sample_to_f32(u32::MAX), 1.0);
+
+        assert_close(sample_to_f32(0u64), -1.0);
+        assert_close(sample_to_f32(1u64 << 63), 0.0);
+        assert_close(sample_to_f32(u64::MAX), 1.0);
+    }
+
+    #[test]
+    fn test_float_formats_normalization() {
+        assert_close(sample_to_f32(-1.0f32), -1.0);
+        assert_close(sample_to_f32(0.25f32), 0.25);
+        assert_close(sample_to_f32(-1.0f64), -1.0);
+        assert_close(sample_to_f32(0.5f64), 0.5);
+        assert_close(sample_to_f32(1.0f64), 1.0);
+    }
+
+    #[test]
+    fn test_push_samples_keeps_whole_frames_on_overrun() {
+        let (mut producer, mut consumer) = RingBuffer::<f32>::new(5);
+        let overruns = AtomicU64::new(0);
+        push_samples(&mut producer, [0.1f32; 6].into_iter(), 2, &overruns);
+
+        assert_eq!(consumer.slots(), 4);
+        assert_eq!(overruns.load(Ordering::Relaxed), 2);
+        assert_eq!(consumer.pop(), Ok(0.1));
+    }
+
+    #[test]
+    fn test_stream_errors_are_classified() {
+        let backend = |description: &str| StreamError::BackendSpecific {
+            err: cpal::BackendSpecificError { description: description.to_string() },
+        };
+
+        assert_eq!(classify_stream_error(&StreamError::DeviceNotAvailable).kind, StreamErrorKind::DeviceLost);
+        assert_eq!(classify_stream_error(&backend("ALSA function 'snd_pcm_readi' failed with error 'ENODEV: No such device'")).kind, StreamErrorKind::DeviceLost);
+        assert_eq!(classify_stream_error(&backend("EPIPE: Broken pipe")).kind, StreamErrorKind::BufferOverrun);
+        assert_eq!(classify_stream_error(&backend("ALSA function 'snd_pcm_poll' failed with error 'EIO: I/O error'")).kind, StreamErrorKind::Backend);
+    }
+
+    #[test]
+    fn test_overrun_does_not_hide_backend_error() {
+        let device_lost = Arc::new(AtomicBool::new(false));
+        let slot = StreamErrorSlot::default();
+        let mut callback = stream_error_callback(device_lost.clone(), slot.clone());
+
+        callback(StreamError::BackendSpecific { err: cpal::BackendSpecificError { description: "EIO".to_string() } });
+        callback(StreamError::BackendSpecific { err: cpal::BackendSpecificError { description: "xrun".to_string() } });
+        assert_eq!(take_stream_error(&slot).map(|e| e.kind), Some(StreamErrorKind::Backend));
+        assert_eq!(take_stream_error(&slot), None);
+
+        callback(StreamError::DeviceNotAvailable);
+        assert!(device_lost.load(Ordering::Acquire));
+        assert_eq!(take_stream_error(&slot), None);
+    }
 }
@@ -0,0 +1,349 @@
+use std::sync::Arc;
+
+use realfft::num_complex::Complex;
+use realfft::{ComplexToReal, RealFftPlanner, RealToComplex};
+use serde::{Deserialize, Serialize};
+
+use crate::audio::pipeline::DspStage;
+
+/// Длительность кадра анализа (округляется вверх до степени двойки в сэмплах)
+const FRAME_SECONDS: f32 = 0.02;
+/// Во сколько раз оценка шума вычитается с запасом: меньше остаточного шума
+/// ценой небольшой потери тихих согласных
+const OVERSUBTRACTION: f32 = 2.5;
+/// Мощность кадра перед сравнением с шумом усредняется по соседним частотам
+/// (± столько бинов) и по времени (доля предыдущего кадра): у одиночного бина
+/// шума разброс мощности слишком велик, и выбросы выше порога остаются "музыкальным шумом"
+const SMOOTHING_BINS: usize = 1;
+const POWER_SMOOTHING: f32 = 0.5;
+/// Шум оценивается по самой тихой доле кадров окна обучения,
+/// чтобы начатая сразу речь не попала в профиль шума
+const QUIET_FRAMES_FRACTION: f32 = 0.5;
+
+/// Параметры спектрального вычитания шума (стадия `denoise`).
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DenoiseParams {
+    /// По скольким миллисекундам начала дубля (pre-roll, пауза перед речью)
+    /// учится профиль шума
+    pub profile_ms: u32,
+    /// Максимальное ослабление шума, dB
+    pub reduction_db: f32,
+}
+
+impl Default for DenoiseParams {
+    fn default() -> Self {
+        Self {
+            profile_ms: 300,
+            reduction_db: 20.0,
+        }
+    }
+}
+
+impl DenoiseParams {
+    pub fn validate(&self) -> Result<(), String> {
+        if !(50..=2000).contains(&self.profile_ms) {
+            return Err("Noise profile must be between 50 and 2000 ms".to_string());
+        }
+        if !(3.0..=40.0).contains(&self.reduction_db) {
+            return Err("Noise reduction must be between 3 and 40 dB".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// Подавление стационарного шума (вентилятор, гул, шипение) спектральным вычитанием.
+/// 
+/// Сигнал режется на кадры ~20 мс с перекрытием 50% (окно sqrt-Hann при анализе
+/// и синтезе, в сумме окна дают единицу). Профиль шума - средняя мощность по
+/// частотам - выучивается по первым `DenoiseParams::profile_ms` дубля: это pre-roll или пауза
+/// перед речью. Из мощности каждого кадра вычитается профиль, умноженный на
+/// `OVERSUBTRACTION`, ослабление ограничено снизу `reduction_db`.
+/// 
+/// Работает потоково: пока профиль не выучен, вход копится, дальше выход
+/// отстаёт на один кадр. После `flush` выход выровнен со входом и той же длины,
+/// результат не зависит от того, как вход нарезан.
+pub struct SpectralDenoiser {
+    sample_rate: u32,
+    params: DenoiseParams,
+    frame: usize,
+    hop: usize,
+    forward: Arc<dyn RealToComplex<f32>>,
+    inverse: Arc<dyn ComplexToReal<f32>>,
+    window: Vec<f32>,
+    /// Сколько сэмплов входа нужно для профиля шума
+    profile_samples: usize,
+    /// Средняя мощность шума по частотам (None - ещё не выучена)
+    noise: Option<Vec<f32>>,
+    /// Минимальное усиление (ослабление не глубже `reduction_db`)
+    floor: f32,
+    /// Вход, который ещё понадобится кадрам (в начале - `hop` нулей слева)
+    pending: Vec<f32>,
+    /// Сумма перекрывающихся кадров синтеза
+    overlap: Vec<f32>,
+    /// Сглаженная мощность текущего кадра
+    power: Vec<f32>,
+    smoothed: Vec<f32>,
+    time: Vec<f32>,
+    spectrum: Vec<Complex<f32>>,
+    /// Ещё не выданные нули выравнивания в начале выхода
+    skip: usize,
+    consumed: u64,
+    produced: u64,
+}
+
+impl SpectralDenoiser {
+    /// Параметры:
+    /// * `sample_rate` - частота дубля
+    /// * `params` - параметры стадии из профиля обработки
+    pub fn new(sample_rate: u32, params: &DenoiseParams) -> Self {
+        let frame = ((sample_rate as f32 * FRAME_SECONDS) as usize).next_power_of_two().max(64);
+        let hop = frame / 2;
+        let mut planner = RealFftPlanner::<f32>::new();
+        let forward = planner.plan_fft_forward(frame);
+        let inverse = planner.plan_fft_inverse(frame);
+        let spectrum = forward.make_output_vec();
+
+        // Периодическое окно sqrt-Hann: анализ * синтез = Hann, с шагом frame/2 сумма равна 1
+        let window = (0..frame)
+            .map(|n| (std::f32::consts::PI * n as f32 / frame as f32).sin())
+            .collect();
+        let profile_samples = (sample_rate as u64 * params.profile_ms as u64 / 1000) as usize;
+
+        Self {
+            sample_rate,
+            params: *params,
+            frame,
+            hop,
+            forward,
+            inverse,
+            window,
+            profile_samples: profile_samples.max(frame),
+            noise: None,
+            floor: 10f32.powf(-params.reduction_db.abs() / 20.0),
+            pending: vec![0.0; hop],
+            overlap: vec![0.0; frame],
+            power: vec![0.0; spectrum.len()],
+            smoothed: vec![0.0; spectrum.len()],
+            time: vec![0.0; frame],
+            spectrum,
+            skip: hop,
+            consumed: 0,
+            produced: 0,
+        }
+    }
+
+    /// Считает профиль шума по тихой части кадров накопленного начала дубля.
+    fn learn_noise(&mut self) {
+        let end = (self.hop + self.profile_samples).min(self.pending.len());
+        let mut frames: Vec<(f32, Vec<f32>)> = Vec::new();
+        // Нули выравнивания в профиль не входят
+        let mut start = self.hop;
+        while start + self.frame <= end {
+            self.analyze(start);
+            let power: Vec<f32> = self.spectrum.iter().map(|c| c.norm_sqr()).collect();
+            frames.push((power.iter().sum(), power));
+            start += self.hop;
+        }
+
+        frames.sort_by(|a, b| a.0.total_cmp(&b.0));
+        let quiet = ((frames.len() as f32 * QUIET_FRAMES_FRACTION).ceil() as usize).max(1);
+        let mut noise = vec![0.0; self.spectrum.len()];
+        for (_, power) in frames.iter().take(quiet) {
+            noise.iter_mut().zip(power).for_each(|(n, p)| *n += p);
+        }
+        let count = frames.len().min(quiet).max(1) as f32;
+        noise.iter_mut().for_each(|n| *n /= count);
+
+        log::debug!("Noise profile learned from {} of {} frames", frames.len().min(quiet), frames.len());
+        self.noise = Some(noise);
+    }
+
+    /// Оконное преобразование кадра `pending[start..start + frame]` в `spectrum`.
+    fn analyze(&mut self, start: usize) {
+        let samples = &self.pending[start..start + self.frame];
+        self.time.iter_mut()
+            .zip(samples.iter().zip(&self.window))
+            .for_each(|(t, (x, w))| *t = x * w);
+        if let Err(e) = self.forward.process(&mut self.time, &mut self.spectrum) {
+            log::error!("Denoiser FFT failed: {}", e);
+        }
+    }
+
+    /// Обрабатывает все полные кадры и выдаёт сэмплы, сумма кадров для которых готова
+    /// (не больше `limit` сэмплов всего).
+    fn drain(&mut self, output: &mut Vec<f32>, limit: u64) {
+        let mut start = 0;
+        while start + self.frame <= self.pending.len() && self.produced < limit {
+            self.analyze(start);
+            self.suppress();
+            self.spectrum[0].im = 0.0;
+            if let Some(last) = self.spectrum.last_mut() {
+                last.im = 0.0;
+            }
+            if let Err(e) = self.inverse.process(&mut self.spectrum, &mut self.time) {
+                log::error!("Denoiser inverse FFT failed: {}", e);
+            }
+
+            let scale = 1.0 / self.frame as f32;
+            self.overlap.iter_mut()
+                .zip(self.time.iter().zip(&self.window))
+                .for_each(|(o, (t, w))| *o += t * w * scale);
+
+            // Первые hop сэмплов суммы больше не изменятся
+            let ready = &self.overlap[self.skip..self.hop];
+            let take = ready.len().min((limit - self.produced) as usize);
+            output.extend_from_slice(&ready[..take]);
+            self.produced += take as u64;
+            self.skip = 0;
+
+            self.overlap.copy_within(self.hop.., 0);
+            let tail = self.frame - self.hop;
+            self.overlap[tail..].fill(0.0);
+            start += self.hop;
+        }
+        self.pending.drain(..start);
+    }
+
+    /// Вычитает профиль шума из мощности кадра в `spectrum`.
+    fn suppress(&mut self) {
+        let Some(noise) = &self.noise else { return };
+        let bins = self.spectrum.len();
+        for (k, smoothed) in self.smoothed.iter_mut().enumerate() {
+            let neighbours = k.saturating_sub(SMOOTHING_BINS)..(k + SMOOTHING_BINS + 1).min(bins);
+            let count = neighbours.len() as f32;
+            *smoothed = self.spectrum[neighbours].iter().map(|c| c.norm_sqr()).sum::<f32>() / count;
+        }
+
+        let bins = self.spectrum.iter_mut().zip(self.power.iter_mut().zip(&self.smoothed));
+        for (noise, (bin, (power, smoothed))) in noise.iter().zip(bins) {
+            *power = POWER_SMOOTHING * *power + (1.0 - POWER_SMOOTHING) * smoothed;
+            let gain = if *noise > 0.0 {
+                (1.0 - OVERSUBTRACTION * noise / power.max(f32::MIN_POSITIVE)).max(0.0).sqrt()
+            } else {
+                1.0
+            };
+            *bin *= gain.max(self.floor);
+        }
+    }
+}
+
+impl DspStage for SpectralDenoiser {
+    fn process(&mut self, input: &[f32], output: &mut Vec<f32>) {
+        self.consumed += input.len() as u64;
+        self.pending.extend_from_slice(input);
+        if self.noise.is_none() {
+            if self.pending.len() < self.hop + self.profile_samples {
+                return;
+            }
+            self.learn_noise();
+        }
+        self.drain(output, u64::MAX);
+    }
+
+    /// Дописывает хвост, которому не хватало входа до полного кадра.
+    /// Короткий дубль, не набравший окна обучения, учится по тому, что есть.
+    fn flush(&mut self, output: &mut Vec<f32>) {
+        if self.noise.is_none() {
+            self.learn_noise();
+        }
+        self.pending.extend(std::iter::repeat_n(0.0, self.frame));
+        self.drain(output, self.consumed);
+    }
+
+    /// Профиль шума учится заново: он часть потока, а не анализа.
+    fn reset(&mut self) {
+        *self = Self::new(self.sample_rate, &self.params);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RATE: u32 = 16000;
+    const PARAMS: DenoiseParams = DenoiseParams { profile_ms: 300, reduction_db: 30.0 };
+
+    /// Детерминированный белый шум (LCG), амплитуда до `amplitude`.
+    fn noise(len: usize, amplitude: f32) -> Vec<f32> {
+        let mut state = 0x2545_f491u32;
+        (0..len)
+            .map(|_| {
+                state = state.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+                amplitude * ((state >> 8) as f32 / (1u32 << 23) as f32 - 1.0)
+            })
+            .collect()
+    }
+
+    fn tone(len: usize, freq: f32, amplitude: f32) -> Vec<f32> {
+        (0..len)
+            .map(|n| amplitude * (2.0 * std::f32::consts::PI * freq * n as f32 / RATE as f32).sin())
+            .collect()
+    }
+
+    fn rms(samples: &[f32]) -> f32 {
+        (samples.iter().map(|s| s * s).sum::<f32>() / samples.len() as f32).sqrt()
+    }
+
+    fn denoise(input: &[f32]) -> Vec<f32> {
+        let mut denoiser = SpectralDenoiser::new(RATE, &PARAMS);
+        let mut output = Vec::new();
+        denoiser.process(input, &mut output);
+        denoiser.flush(&mut output);
+        output
+    }
+
+    #[test]
+    fn test_steady_noise_is_suppressed_and_tone_kept() {
+        // 0.5 с только шума (профиль), затем тон на том же шуме
+        let len = RATE as usize * 2;
+        let background = noise(len, 0.05);
+        let signal = tone(len, 440.0, 0.3);
+        let input: Vec<f32> = background.iter().enumerate()
+            .map(|(n, b)| if n < RATE as usize / 2 { *b } else { b + signal[n] })
+            .collect();
+
+        let output = denoise(&input);
+        assert_eq!(output.len(), input.len());
+
+        let quiet = RATE as usize / 8..RATE as usize / 2 - 1000;
+        let reduction = 20.0 * (rms(&output[quiet.clone()]) / rms(&input[quiet])).log10();
+        assert!(reduction < -15.0, "noise reduced by only {} dB", reduction);
+
+        let speech = RATE as usize..RATE as usize + 8000;
+        let tone_gain = 20.0 * (rms(&output[speech.clone()]) / rms(&signal[speech])).log10();
+        assert!(tone_gain.abs() < 1.0, "tone level changed by {} dB", tone_gain);
+    }
+
+    #[test]
+    fn test_without_noise_signal_passes_unchanged() {
+        // Профиль из цифровой тишины: вычитать нечего, кадры складываются обратно точно
+        let mut input = vec![0.0; RATE as usize / 2];
+        input.extend(tone(RATE as usize, 1000.0, 0.5));
+
+        let output = denoise(&input);
+        assert_eq!(output.len(), input.len());
+        for (n, (a, b)) in output.iter().zip(&input).enumerate() {
+            assert!((a - b).abs() < 1e-4, "sample {}: {} vs {}", n, a, b);
+        }
+    }
+
+    #[test]
+    fn test_chunked_processing_matches_whole_signal() {
+        let mut input = noise(RATE as usize, 0.05);
+        input.iter_mut().zip(tone(RATE as usize, 300.0, 0.2)).skip(6000).for_each(|(x, t)| *x += t);
+        let whole = denoise(&input);
+
+        let mut denoiser = SpectralDenoiser::new(RATE, &PARAMS);
+        let mut chunked = Vec::new();
+        for chunk in input.chunks(333) {
+            denoiser.process(chunk, &mut chunked);
+        }
+        denoiser.flush(&mut chunked);
+
+        assert_eq!(chunked.len(), whole.len());
+        for (a, b) in chunked.iter().zip(&whole) {
+            assert!((a - b).abs() < 1e-6);
+        }
+    }
+}
@@ -0,0 +1,521 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::Ordering;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use cpal::{default_host, traits::{DeviceTrait, HostTrait}};
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter};
+
+use crate::audio::loopback::{find_loopback_device, is_loopback_id, is_loopback_name, list_loopback_devices};
+use crate::types::AudioCapture;
+
+/// Как часто watcher опрашивает список устройств
+const WATCH_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Стандартные частоты, которые перечисляются в возможностях устройства
+const STANDARD_SAMPLE_RATES: [u32; 10] = [8000, 11025, 16000, 22050, 32000, 44100, 48000, 88200, 96000, 192000];
+
+/// Минимальная похожесть имён (доля общих слов), при которой
+/// сохранённое устройство считается найденным
+const FUZZY_MATCH_THRESHOLD: f32 = 0.5;
+
+/// Конфигурация устройства по умолчанию.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DeviceConfig {
+    pub sample_rate: u32,
+    pub channels: u16,
+    pub sample_format: String,
+}
+
+/// Диапазон конфигураций, поддерживаемых устройством.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ConfigRange {
+    pub channels: u16,
+    pub min_sample_rate: u32,
+    pub max_sample_rate: u32,
+    pub sample_format: String,
+}
+
+/// Возможности входного устройства (команда `get_device_capabilities`).
+#[derive(Debug, Clone, Serialize)]
+pub struct DeviceCapabilities {
+    pub device: InputDevice,
+    /// Стандартные частоты, попадающие в поддерживаемые диапазоны, и границы диапазонов
+    pub sample_rates: Vec<u32>,
+    pub channels: Vec<u16>,
+    pub sample_formats: Vec<String>,
+    pub configs: Vec<ConfigRange>,
+}
+
+/// Желаемые параметры stream. Незаданные значения берутся из конфигурации
+/// устройства по умолчанию.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct StreamPreferences {
+    pub sample_rate: Option<u32>,
+    pub channels: Option<u16>,
+    /// Формат сэмплов как в `DeviceConfig::sample_format` (F32, I16, ...)
+    pub sample_format: Option<String>,
+}
+
+/// Аудио входное устройство.
+/// 
+/// `id` строится из аудио API и имени устройства и стабилен между запусками,
+/// пока имя устройства уникально. Одинаковые имена (два одинаковых USB
+/// микрофона на WASAPI или CoreAudio) различаются номером в порядке
+/// перечисления (`wasapi:USB Mic#1`), и такие ID не стабильны: после
+/// переподключения устройства могут поменяться ими. cpal не отдаёт ID
+/// конечной точки WASAPI; на ALSA имена PCM содержат ID карты и не повторяются.
+/// 
+/// Источники звука системы (monitor / loopback) помечены `is_loopback`,
+/// их ID начинается с `loopback:` (см. loopback.rs).
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct InputDevice {
+    pub id: String,
+    pub name: String,
+    pub host_api: String,
+    pub default_config: Option<DeviceConfig>,
+    pub is_default: bool,
+    /// Устройство записывает то, что играет система, а не микрофон
+    pub is_loopback: bool,
+}
+
+/// Строит идентификатор устройства.
+/// 
+/// Номер `occurrence` - позиция в порядке перечисления среди устройств
+/// с таким же именем, поэтому ID с суффиксом `#n` не стабилен (см. `InputDevice`).
+/// 
+/// Параметры:
+/// * `host_api` - имя аудио API (cpal host)
+/// * `name` - имя устройства
+/// * `occurrence` - номер среди устройств с таким же именем (0 для первого)
+fn device_id(host_api: &str, name: &str, occurrence: usize) -> String {
+    if occurrence == 0 {
+        format!("{}:{}", host_api.to_lowercase(), name)
+    } else {
+        format!("{}:{}#{}", host_api.to_lowercase(), name, occurrence)
+    }
+}
+
+/// Перечисляет входные устройства вместе с их описанием.
+/// 
+/// Устройства, которые не могут предоставить имя, пропускаются.
+fn enumerate_input_devices() -> Result<Vec<(cpal::Device, InputDevice)>, String> {
+    let host = default_host();
+    let host_api = host.id().name().to_string();
+    log::debug!("Using host: {:?}", host.id());
+
+    let default_name = host.default_input_device().and_then(|d| d.name().ok());
+    let mut seen: HashMap<String, usize> = HashMap::new();
+
+    Ok(host.input_devices()
+        .map_err(|e| format!("Failed to retrieve input devices: {}", e))?
+        .filter_map(|device| {
+            let name = device.name().ok()?;
+            let occurrence = seen.entry(name.clone()).or_insert(0);
+            let id = device_id(&host_api, &name, *occurrence);
+            *occurrence += 1;
+
+            let default_config = device.default_input_config().ok().map(|config| DeviceConfig {
+                sample_rate: config.sample_rate().0,
+                channels: config.channels(),
+                sample_format: format!("{:?}", config.sample_format()),
+            });
+
+            let info = InputDevice {
+                id,
+                is_default: default_name.as_deref() == Some(name.as_str()),
+                is_loopback: is_loopback_name(&name),
+                name,
+                host_api: host_api.clone(),
+                default_config,
+            };
+            Some((device, info))
+        })
+        .collect())
+}
+
+/// Возвращает список всех доступных аудио входных устройств.
+/// 
+/// После устройств ввода идут источники звука системы (`list_loopback_devices`).
+pub fn list_input_devices() -> Result<Vec<InputDevice>, String> {
+    let mut devices: Vec<InputDevice> = enumerate_input_devices()?.into_iter().map(|(_, info)| info).collect();
+    devices.extend(list_loopback_devices());
+    Ok(devices)
+}
+
+/// Имя системного входного устройства по умолчанию.
+pub fn default_input_device_name() -> Option<String> {
+    default_host().default_input_device()?.name().ok()
+}
+
+/// Находит входное устройство по ID или имени.
+/// 
+/// Порядок поиска: точное совпадение ID, точное совпадение имени, затем
+/// нечёткое сравнение имён - так сохранённый выбор переживает перезагрузку,
+/// после которой ALSA/WASAPI могут немного поменять имя устройства.
+/// 
+/// Параметры:
+/// * `query` - ID устройства (или имя, для совместимости)
+pub fn find_input_device(query: &str) -> Result<(cpal::Device, InputDevice), String> {
+    let mut devices = enumerate_input_devices()?;
+    let infos: Vec<InputDevice> = devices.iter().map(|(_, info)| info.clone()).collect();
+
+    let index = match_device(&infos, query).ok_or_else(|| "Device not found".to_string())?;
+    let found = devices.swap_remove(index);
+    if found.1.id != query && found.1.name != query {
+        log::warn!("Device {:?} not found, using closest match {:?}", query, found.1.id);
+    }
+    Ok(found)
+}
+
+/// Перечисляет поддерживаемые устройством конфигурации.
+/// 
+/// Возвращает пары из диапазона cpal (нужен для открытия stream) и его описания.
+pub fn supported_config_ranges(
+    device: &cpal::Device,
+) -> Result<Vec<(cpal::SupportedStreamConfigRange, ConfigRange)>, String> {
+    Ok(device
+        .supported_input_configs()
+        .map_err(|e| format!("Failed to query supported configs: {}", e))?
+        .map(|range| {
+            let info = ConfigRange {
+                channels: range.channels(),
+                min_sample_rate: range.min_sample_rate().0,
+                max_sample_rate: range.max_sample_rate().0,
+                sample_format: format!("{:?}", range.sample_format()),
+            };
+            (range, info)
+        })
+        .collect())
+}
+
+/// Возвращает поддерживаемые частоты, количества каналов и форматы устройства.
+/// 
+/// Loopback источник открывается только в своей конфигурации по умолчанию.
+/// 
+/// Параметры:
+/// * `query` - ID устройства (или имя)
+pub fn device_capabilities(query: &str) -> Result<DeviceCapabilities, String> {
+    if is_loopback_id(query) {
+        let device = find_loopback_device(query).ok_or_else(|| "Device not found".to_string())?;
+        let config = device.default_config.clone().ok_or_else(|| "Loopback source has no configuration".to_string())?;
+        return Ok(DeviceCapabilities {
+            sample_rates: vec![config.sample_rate],
+            channels: vec![config.channels],
+            sample_formats: vec![config.sample_format.clone()],
+            configs: vec![ConfigRange {
+                channels: config.channels,
+                min_sample_rate: config.sample_rate,
+                max_sample_rate: config.sample_rate,
+                sample_format: config.sample_format,
+            }],
+            device,
+        });
+    }
+
+    let (device, info) = find_input_device(query)?;
+    let configs: Vec<ConfigRange> = supported_config_ranges(&device)?
+        .into_iter()
+        .map(|(_, range)| range)
+        .collect();
+
+    let mut sample_rates: Vec<u32> = configs
+        .iter()
+        .flat_map(|c| {
+            STANDARD_SAMPLE_RATES
+                .iter()
+                .copied()
+                .filter(|rate| (c.min_sample_rate..=c.max_sample_rate).contains(rate))
+                .chain([c.min_sample_rate, c.max_sample_rate])
+        })
+        .collect();
+    sample_rates.sort_unstable();
+    sample_rates.dedup();
+
+    let mut channels: Vec<u16> = configs.iter().map(|c| c.channels).collect();
+    channels.sort_unstable();
+    channels.dedup();
+
+    let mut sample_formats: Vec<String> = Vec::new();
+    for config in &configs {
+        if !sample_formats.contains(&config.sample_format) {
+            sample_formats.push(config.sample_format.clone());
+        }
+    }
+
+    Ok(DeviceCapabilities { device: info, sample_rates, channels, sample_formats, configs })
+}
+
+/// Выбирает конфигурацию, ближайшую к желаемой.
+/// 
+/// Сначала сравнивается частота (ресэмплинг дороже всего), затем количество
+/// каналов, затем формат. При равенстве предпочитается более высокая частота
+/// и F32. Незаданные предпочтения берутся из `default`.
+/// 
+/// Возвращает индекс диапазона в `ranges` и выбранную частоту.
+pub fn negotiate_config(
+    ranges: &[ConfigRange],
+    preferences: &StreamPreferences,
+    default: &DeviceConfig,
+) -> Option<(usize, u32)> {
+    let target_rate = preferences.sample_rate.unwrap_or(default.sample_rate);
+    let target_channels = preferences.channels.unwrap_or(default.channels);
+    let target_format = preferences.sample_format.as_deref().unwrap_or(&default.sample_format);
+
+    ranges
+        .iter()
+        .enumerate()
+        .map(|(index, range)| {
+            let rate = target_rate.clamp(range.min_sample_rate, range.max_sample_rate);
+            let format_penalty = if range.sample_format.eq_ignore_ascii_case(target_format) {
+                0
+            } else if range.sample_format == "F32" {
+                1
+            } else {
+                2
+            };
+            let key = (
+                rate.abs_diff(target_rate),
+                range.channels.abs_diff(target_channels),
+                format_penalty,
+                u32::MAX - rate,
+            );
+            (index, rate, key)
+        })
+        .min_by_key(|(_, _, key)| *key)
+        .map(|(index, rate, _)| (index, rate))
+}
+
+/// Выбирает устройство из списка по ID, имени или похожему имени.
+fn match_device(devices: &[InputDevice], query: &str) -> Option<usize> {
+    if let Some(index) = devices.iter().position(|d| d.id == query) {
+        return Some(index);
+    }
+    if let Some(index) = devices.iter().position(|d| d.name == query) {
+        return Some(index);
+    }
+
+    // ID содержит имя после префикса API, а номер дубликата не важен для похожести
+    let query_name = query.split_once(':').map_or(query, |(_, name)| name);
+    let query_name = query_name.rsplit_once('#').map_or(query_name, |(name, _)| name);
+
+    devices
+        .iter()
+        .enumerate()
+        .map(|(index, d)| (index, name_similarity(&d.name, query_name)))
+        .filter(|(_, score)| *score >= FUZZY_MATCH_THRESHOLD)
+        .max_by(|a, b| a.1.total_cmp(&b.1))
+        .map(|(index, _)| index)
+}
+
+/// Похожесть имён устройств: доля общих слов (коэффициент Жаккара).
+/// 
+/// Числа и служебные части ALSA имён (hw, card, dev) не учитываются:
+/// именно они меняются между загрузками.
+fn name_similarity(a: &str, b: &str) -> f32 {
+    fn words(name: &str) -> HashSet<String> {
+        name.split(|c: char| !c.is_alphanumeric())
+            .map(|w| w.to_lowercase())
+            .filter(|w| !w.is_empty() && !w.chars().all(|c| c.is_ascii_digit()))
+            .filter(|w| !matches!(w.as_str(), "hw" | "plughw" | "card" | "dev"))
+            .collect()
+    }
+
+    let (a, b) = (words(a), words(b));
+    let union = a.union(&b).count();
+    if union == 0 {
+        return 0.0;
+    }
+    a.intersection(&b).count() as f32 / union as f32
+}
+
+/// Имена всех устройств хоста (входных и выходных) и loopback источников.
+/// 
+/// Устройства не открываются. На Linux имена берутся из подсказок ALSA
+/// (`HintIter`) - это те же имена, что у устройств cpal, но `Host::devices()`
+/// открывает каждый PCM, и занятое записью устройство выпало бы из списка.
+/// WASAPI и CoreAudio перечисляют устройства без открытия.
+fn present_device_names() -> Result<HashSet<String>, String> {
+    #[cfg(target_os = "linux")]
+    let mut names: HashSet<String> = alsa::device_name::HintIter::new_str(None, "pcm")
+        .map_err(|e| format!("Failed to retrieve device hints: {}", e))?
+        .filter_map(|hint| hint.name)
+        .collect();
+    #[cfg(not(target_os = "linux"))]
+    let mut names: HashSet<String> = default_host()
+        .devices()
+        .map_err(|e| format!("Failed to retrieve devices: {}", e))?
+        .filter_map(|device| device.name().ok())
+        .collect();
+    names.extend(list_loopback_devices().into_iter().map(|device| device.name));
+    Ok(names)
+}
+
+/// Совпадают ли списки устройств по ID и именам.
+/// 
+/// Конфигурация по умолчанию не сравнивается: она меняется, пока устройство
+/// занято, и это не подключение или отключение.
+fn same_devices(a: &[InputDevice], b: &[InputDevice]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).all(|(a, b)| a.id == b.id && a.name == b.name)
+}
+
+/// Запускает фоновый поток, следящий за подключением и отключением устройств.
+/// 
+/// Раз в `WATCH_INTERVAL` сравнивает имена устройств без их открытия
+/// (`present_device_names`); полный список входных устройств строится только
+/// после изменения и эмитится в 'devices-changed', если в нём поменялись ID
+/// или имена. Если во время записи активное устройство пропало из системы,
+/// выставляет `AudioCapture::device_lost` - поток сессии остановит stream,
+/// сохранит записанное и переключится на устройство по умолчанию
+/// (см. session.rs). Этот же флаг выставляет error callback stream при
+/// `StreamError::DeviceNotAvailable`.
+/// 
+/// Параметры:
+/// * `capture` - глобальное состояние AudioCapture
+/// * `app` - AppHandle для отправки событий во frontend
+pub fn spawn_device_watcher(capture: Arc<Mutex<AudioCapture>>, app: AppHandle) {
+    let spawned = std::thread::Builder::new()
+        .name("device-watcher".to_string())
+        .spawn(move || {
+            let mut known = list_input_devices().unwrap_or_default();
+            let mut present = present_device_names().unwrap_or_default();
+
+            loop {
+                std::thread::sleep(WATCH_INTERVAL);
+
+                let names = match present_device_names() {
+                    Ok(names) => names,
+                    Err(e) => {
+                        log::warn!("Device watcher: {}", e);
+                        continue;
+                    }
+                };
+                check_active_device(&capture, &names);
+                if names == present {
+                    continue;
+                }
+                present = names;
+
+                match list_input_devices() {
+                    Ok(devices) if !same_devices(&devices, &known) => {
+                        log::info!("Input devices changed: {} -> {} devices", known.len(), devices.len());
+                        if let Err(e) = app.emit("devices-changed", &devices) {
+                            log::error!("Failed to emit devices-changed event: {}", e);
+                        }
+                        known = devices;
+                    }
+                    Ok(_) => {}
+                    Err(e) => log::warn!("Device watcher: {}", e),
+                }
+            }
+        });
+
+    if let Err(e) = spawned {
+        log::error!("Failed to spawn device watcher thread: {}", e);
+    }
+}
+
+/// Выставляет флаг потери устройства, если устройство открытого stream
+/// (запись или pre-roll) пропало из `present` - имён подключённых устройств.
+fn check_active_device(capture: &Arc<Mutex<AudioCapture>>, present: &HashSet<String>) {
+    let Ok(cap) = capture.lock() else { return };
+    if !cap.is_capturing.load(Ordering::Acquire) {
+        return;
+    }
+    if let Some(active) = cap.active_device.as_ref().filter(|name| !present.contains(*name)) {
+        log::warn!("Active input device disconnected: {}", active);
+        cap.device_lost.store(true, Ordering::Release);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn device(id: &str, name: &str) -> InputDevice {
+        InputDevice {
+            id: id.to_string(),
+            name: name.to_string(),
+            host_api: "ALSA".to_string(),
+            default_config: None,
+            is_default: false,
+            is_loopback: false,
+        }
+    }
+
+    fn range(channels: u16, min: u32, max: u32, format: &str) -> ConfigRange {
+        ConfigRange {
+            channels,
+            min_sample_rate: min,
+            max_sample_rate: max,
+            sample_format: format.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_negotiate_defaults_to_device_config() {
+        let ranges = vec![range(1, 8000, 48000, "I16"), range(2, 8000, 48000, "I16")];
+        let default = DeviceConfig { sample_rate: 44100, channels: 2, sample_format: "I16".to_string() };
+        assert_eq!(negotiate_config(&ranges, &StreamPreferences::default(), &default), Some((1, 44100)));
+    }
+
+    #[test]
+    fn test_negotiate_closest_to_preferences() {
+        let ranges = vec![
+            range(2, 44100, 44100, "I16"),
+            range(2, 48000, 48000, "I16"),
+            range(1, 48000, 48000, "I16"),
+            range(1, 48000, 48000, "F32"),
+        ];
+        let default = DeviceConfig { sample_rate: 44100, channels: 2, sample_format: "I16".to_string() };
+        let prefs = StreamPreferences { sample_rate: Some(48000), channels: Some(1), sample_format: Some("f32".to_string()) };
+        assert_eq!(negotiate_config(&ranges, &prefs, &default), Some((3, 48000)));
+
+        // Частота вне диапазонов - берётся ближайшая граница
+        let prefs = StreamPreferences { sample_rate: Some(16000), channels: Some(1), sample_format: None };
+        assert_eq!(negotiate_config(&ranges, &prefs, &default), Some((0, 44100)));
+
+        assert_eq!(negotiate_config(&[], &prefs, &default), None);
+    }
+
+    #[test]
+    fn test_device_id_distinguishes_duplicates() {
+        assert_eq!(device_id("ALSA", "USB Mic", 0), "alsa:USB Mic");
+        assert_eq!(device_id("ALSA", "USB Mic", 1), "alsa:USB Mic#1");
+    }
+
+    #[test]
+    fn test_device_changes_ignore_default_config() {
+        let known = vec![device("alsa:USB Mic", "USB Mic")];
+        let mut busy = known.clone();
+        busy[0].default_config = Some(DeviceConfig { sample_rate: 48000, channels: 1, sample_format: "I16".to_string() });
+        assert!(same_devices(&known, &busy));
+        assert!(!same_devices(&known, &[device("alsa:Headset", "Headset")]));
+        assert!(!same_devices(&known, &[]));
+    }
+
+    #[test]
+    fn test_match_device_by_id_then_name() {
+        let devices = vec![
+            device("alsa:USB Mic", "USB Mic"),
+            device("alsa:USB Mic#1", "USB Mic"),
+        ];
+        assert_eq!(match_device(&devices, "alsa:USB Mic#1"), Some(1));
+        assert_eq!(match_device(&devices, "USB Mic"), Some(0));
+    }
+
+    #[test]
+    fn test_match_device_fuzzy_after_rename() {
+        let devices = vec![
+            device("alsa:default", "default"),
+            device("alsa:hw:CARD=Microphone,DEV=0", "hw:CARD=Microphone,DEV=0"),
+            device("alsa:Blue Yeti, USB Audio", "Blue Yeti, USB Audio"),
+        ];
+        // Карта получила другой номер после перезагрузки
+        assert_eq!(match_device(&devices, "alsa:hw:CARD=Microphone,DEV=1"), Some(1));
+        assert_eq!(match_device(&devices, "alsa:Blue Yeti USB Audio 2"), Some(2));
+        assert_eq!(match_device(&devices, "alsa:Scarlett 2i2"), None);
+    }
+}
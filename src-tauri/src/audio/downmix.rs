@@ -0,0 +1,162 @@
+use serde::{Deserialize, Serialize};
+
+/// Как сводить многоканальный вход в моно.
+/// 
+/// Запись сводится в моно один раз, при чтении из кольцевого буфера, поэтому
+/// обработка, WAV файлы и распознавание работают с одним каналом.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(tag = "mode", rename_all = "snake_case")]
+pub enum ChannelStrategy {
+    /// Среднее всех каналов
+    #[default]
+    Average,
+    /// Только канал с номером `index` (с нуля)
+    Channel { index: u16 },
+    /// Самый громкий канал в каждом блоке
+    Loudest,
+}
+
+/// Сводит interleaved сэмплы в моно по выбранной стратегии.
+/// 
+/// Блоки из кольцевого буфера могут разрезать фрейм (на границе кольца),
+/// поэтому неполный фрейм сохраняется до следующего блока.
+pub struct Downmixer {
+    strategy: ChannelStrategy,
+    channels: usize,
+    pending: Vec<f32>,
+    output: Vec<f32>,
+}
+
+impl Downmixer {
+    /// Параметры:
+    /// * `strategy` - стратегия сведения
+    /// * `channels` - количество каналов входного потока
+    /// 
+    /// Если выбранного канала на устройстве нет, используется среднее всех каналов.
+    pub fn new(strategy: ChannelStrategy, channels: u16) -> Self {
+        let strategy = match strategy {
+            ChannelStrategy::Channel { index } if index >= channels => {
+                log::warn!(
+                    "Channel {} is not available on a {}-channel device, averaging all channels",
+                    index, channels
+                );
+                ChannelStrategy::Average
+            }
+            other => other,
+        };
+
+        Self {
+            strategy,
+            channels: channels.max(1) as usize,
+            pending: Vec::new(),
+            output: Vec::new(),
+        }
+    }
+
+    /// Сводит блок interleaved сэмплов и возвращает моно сэмплы.
+    /// 
+    /// Возвращает по одному сэмплу на каждый полный фрейм блока
+    /// (с учётом остатка предыдущего блока).
+    pub fn process(&mut self, interleaved: &[f32]) -> &[f32] {
+        self.output.clear();
+        if self.channels == 1 {
+            self.output.extend_from_slice(interleaved);
+            return &self.output;
+        }
+
+        let mut input = interleaved;
+        if !self.pending.is_empty() {
+            let missing = (self.channels - self.pending.len()).min(input.len());
+            self.pending.extend_from_slice(&input[..missing]);
+            input = &input[missing..];
+            if self.pending.len() < self.channels {
+                return &self.output;
+            }
+            let frame = std::mem::take(&mut self.pending);
+            self.mix(&frame);
+        }
+
+        let whole = input.len() - input.len() % self.channels;
+        self.mix(&input[..whole]);
+        self.pending.extend_from_slice(&input[whole..]);
+        &self.output
+    }
+
+    /// Сводит полные фреймы и дописывает результат в `output`.
+    fn mix(&mut self, frames: &[f32]) {
+        let channels = self.channels;
+        match self.strategy {
+            ChannelStrategy::Average => {
+                let scale = 1.0 / channels as f32;
+                self.output.extend(frames.chunks_exact(channels).map(|f| f.iter().sum::<f32>() * scale));
+            }
+            ChannelStrategy::Channel { index } => {
+                self.output.extend(frames.chunks_exact(channels).map(|f| f[index as usize]));
+            }
+            ChannelStrategy::Loudest => {
+                let loudest = loudest_channel(frames, channels);
+                self.output.extend(frames.chunks_exact(channels).map(|f| f[loudest]));
+            }
+        }
+    }
+}
+
+/// Номер канала с наибольшей энергией в блоке.
+fn loudest_channel(frames: &[f32], channels: usize) -> usize {
+    let mut energy = vec![0.0f32; channels];
+    for frame in frames.chunks_exact(channels) {
+        for (e, &s) in energy.iter_mut().zip(frame) {
+            *e += s * s;
+        }
+    }
+    energy
+        .iter()
+        .enumerate()
+        .fold((0, f32::MIN), |best, (i, &e)| if e > best.1 { (i, e) } else { best })
+        .0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_average_mixes_all_channels() {
+        let mut mixer = Downmixer::new(ChannelStrategy::Average, 2);
+        assert_eq!(mixer.process(&[0.2, 0.4, -0.5, 0.5]), &[0.3, 0.0]);
+    }
+
+    #[test]
+    fn test_channel_picks_right_channel() {
+        let mut mixer = Downmixer::new(ChannelStrategy::Channel { index: 1 }, 2);
+        assert_eq!(mixer.process(&[0.0, 0.7, 0.0, -0.3]), &[0.7, -0.3]);
+    }
+
+    #[test]
+    fn test_missing_channel_falls_back_to_average() {
+        let mut mixer = Downmixer::new(ChannelStrategy::Channel { index: 3 }, 2);
+        assert_eq!(mixer.process(&[0.2, 0.4]), &[0.3]);
+    }
+
+    #[test]
+    fn test_loudest_channel_per_block() {
+        let mut mixer = Downmixer::new(ChannelStrategy::Loudest, 2);
+        assert_eq!(mixer.process(&[0.01, 0.5, 0.0, -0.6]), &[0.5, -0.6]);
+        assert_eq!(mixer.process(&[0.8, 0.1, -0.7, 0.0]), &[0.8, -0.7]);
+    }
+
+    #[test]
+    fn test_split_frame_is_carried_over() {
+        let mut mixer = Downmixer::new(ChannelStrategy::Channel { index: 1 }, 2);
+        assert_eq!(mixer.process(&[0.1, 0.2, 0.3]), &[0.2]);
+        assert_eq!(mixer.process(&[0.4, 0.5, 0.6]), &[0.4, 0.6]);
+    }
+
+    #[test]
+    fn test_strategy_serialization() {
+        let json = serde_json::to_string(&ChannelStrategy::Channel { index: 1 }).unwrap();
+        assert_eq!(json, r#"{"mode":"channel","index":1}"#);
+        let parsed: ChannelStrategy = serde_json::from_str(r#"{"mode":"loudest"}"#).unwrap();
+        assert_eq!(parsed, ChannelStrategy::Loudest);
+    }
+}
@@ -0,0 +1,108 @@
+use serde::{Deserialize, Serialize};
+
+use crate::audio::pipeline::DspStage;
+
+/// Параметры мягкого noise gate (стадия `noise_gate`).
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GateParams {
+    /// Порог шума относительно RMS всего дубля
+    pub threshold_ratio: f32,
+    /// Порог не выше этой амплитуды (громкая запись не режет тихие согласные)
+    pub max_threshold: f32,
+    /// Множитель для сэмплов ниже порога (0.2 = оставить 20%)
+    pub factor: f32,
+}
+
+impl Default for GateParams {
+    fn default() -> Self {
+        Self {
+            threshold_ratio: 0.15,
+            max_threshold: 0.01,
+            factor: 0.2,
+        }
+    }
+}
+
+impl GateParams {
+    pub fn validate(&self) -> Result<(), String> {
+        if !(0.0..=1.0).contains(&self.threshold_ratio) {
+            return Err("Noise gate threshold must be between 0 and 1 of input RMS".to_string());
+        }
+        if !(0.0..=0.5).contains(&self.max_threshold) {
+            return Err("Noise gate max threshold must be between 0 and 0.5".to_string());
+        }
+        if !(0.0..=1.0).contains(&self.factor) {
+            return Err("Noise gate factor must be between 0 and 1".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// Мягкий noise gate.
+/// 
+/// Сэмплы с амплитудой меньше порога не полностью обнуляются,
+/// а снижаются с множителем `factor` (обычно 0.2) для сохранения деталей.
+/// Порог считается по RMS всего дубля в предварительном проходе.
+pub struct SoftGate {
+    params: GateParams,
+    sum_squares: f64,
+    count: usize,
+}
+
+impl SoftGate {
+    pub fn new(params: &GateParams) -> Self {
+        Self {
+            params: *params,
+            sum_squares: 0.0,
+            count: 0,
+        }
+    }
+
+    /// Порог шума по RMS проанализированного сигнала.
+    fn threshold(&self) -> f32 {
+        let rms_input = if self.count == 0 { 0.0 } else { (self.sum_squares / self.count as f64).sqrt() as f32 };
+        // Noise threshold = 15% от входного RMS
+        // (мягкий noise gate для сохранения деталей речи)
+        (rms_input * self.params.threshold_ratio).min(self.params.max_threshold)
+    }
+}
+
+impl DspStage for SoftGate {
+    fn process(&mut self, input: &[f32], output: &mut Vec<f32>) {
+        let noise_threshold = self.threshold();
+        output.extend(input.iter().map(|&sample| {
+            // Мягкий noise gate: применяем мягкий множитель для очень тихих сэмплов
+            // Это сохраняет детали речи, включая тихие согласные и фрикативы
+            if sample.abs() < noise_threshold {
+                sample * self.params.factor  // Оставляем % (обычно 20%) от тихих сэмплов
+            } else {
+                sample
+            }
+        }));
+    }
+
+    fn needs_analysis(&self) -> bool {
+        true
+    }
+
+    fn analyze(&mut self, input: &[f32]) {
+        let chunk_rms = calculate_rms(input) as f64;
+        self.sum_squares += chunk_rms * chunk_rms * input.len() as f64;
+        self.count += input.len();
+    }
+
+    fn reset(&mut self) {}
+}
+
+/// Вычисляет RMS (Root Mean Square) для массива сэмплов.
+/// 
+/// Возвращает 0.0 для пустого массива.
+/// 
+/// Параметры:
+/// * `data` - срез аудиосэмплов
+fn calculate_rms(data: &[f32]) -> f32 {
+    if data.is_empty() { return 0.0; }
+    let sum_squares: f32 = data.iter().map(|&x| x * x).sum();
+    (sum_squares / data.len() as f32).sqrt()
+}
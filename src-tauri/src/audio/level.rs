@@ -0,0 +1,134 @@
+use serde::Serialize;
+
+/// Длительность блока, по которому считается уровень
+const LEVEL_BLOCK_MS: u32 = 50;
+/// Сэмпл с амплитудой не меньше порога считается клиппингом
+pub const CLIP_THRESHOLD: f32 = 0.999;
+/// Нижняя граница уровня в dBFS (вместо -inf для тишины)
+pub const MIN_DBFS: f32 = -120.0;
+
+/// Payload события 'audio-level'.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct LevelReport {
+    /// RMS блока (линейный, 0.0..1.0)
+    pub rms: f32,
+    /// Пиковая амплитуда блока (линейная, 0.0..1.0)
+    pub peak: f32,
+    /// RMS блока в dBFS
+    pub rms_dbfs: f32,
+    /// Пик блока в dBFS
+    pub peak_dbfs: f32,
+    /// В блоке есть сэмплы на границе диапазона
+    pub clipping: bool,
+    /// RMS ниже порога шума (`AudioSettings::noise_floor_dbfs`) - микрофон молчит
+    pub below_noise_floor: bool,
+}
+
+/// Измеритель уровня входного сигнала.
+/// 
+/// Копит моно сэмплы записи и по каждому полному блоку ~50 мс
+/// возвращает RMS, пик и признак клиппинга.
+pub struct LevelMeter {
+    block_len: usize,
+    noise_floor_dbfs: f32,
+    sum_squares: f64,
+    peak: f32,
+    count: usize,
+}
+
+impl LevelMeter {
+    /// Параметры:
+    /// * `sample_rate` - частота дискретизации моно сигнала
+    /// * `noise_floor_dbfs` - порог, ниже которого сигнал считается тишиной
+    pub fn new(sample_rate: u32, noise_floor_dbfs: f32) -> Self {
+        Self {
+            block_len: (sample_rate as usize * LEVEL_BLOCK_MS as usize / 1000).max(1),
+            noise_floor_dbfs,
+            sum_squares: 0.0,
+            peak: 0.0,
+            count: 0,
+        }
+    }
+
+    /// Добавляет сэмплы и возвращает уровень последнего завершённого блока.
+    /// 
+    /// Если в `samples` завершилось несколько блоков, возвращается только
+    /// последний - событие всё равно отправляется не чаще раза за блок.
+    pub fn push(&mut self, samples: &[f32]) -> Option<LevelReport> {
+        let mut report = None;
+        for &s in samples {
+            self.sum_squares += (s as f64) * (s as f64);
+            self.peak = self.peak.max(s.abs());
+            self.count += 1;
+            if self.count == self.block_len {
+                report = Some(self.finish_block());
+            }
+        }
+        report
+    }
+
+    fn finish_block(&mut self) -> LevelReport {
+        let rms = (self.sum_squares / self.count as f64).sqrt() as f32;
+        let peak = self.peak;
+        self.sum_squares = 0.0;
+        self.peak = 0.0;
+        self.count = 0;
+
+        let rms_dbfs = to_dbfs(rms);
+        LevelReport {
+            rms,
+            peak,
+            rms_dbfs,
+            peak_dbfs: to_dbfs(peak),
+            clipping: peak >= CLIP_THRESHOLD,
+            below_noise_floor: rms_dbfs < self.noise_floor_dbfs,
+        }
+    }
+}
+
+/// Переводит линейную амплитуду в dBFS.
+pub fn to_dbfs(value: f32) -> f32 {
+    if value <= 0.0 {
+        return MIN_DBFS;
+    }
+    (20.0 * value.log10()).max(MIN_DBFS)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_report_per_block() {
+        let mut meter = LevelMeter::new(1000, -60.0);
+        assert!(meter.push(&[0.5; 49]).is_none());
+
+        let report = meter.push(&[0.5]).unwrap();
+        assert!((report.rms - 0.5).abs() < 1e-6);
+        assert!((report.rms_dbfs + 6.02).abs() < 0.01);
+        assert!(!report.clipping);
+        assert!(!report.below_noise_floor);
+    }
+
+    #[test]
+    fn test_clipping_and_silence() {
+        let mut meter = LevelMeter::new(1000, -60.0);
+        let mut block = vec![0.0f32; 50];
+        block[10] = -1.0;
+        let report = meter.push(&block).unwrap();
+        assert!(report.clipping);
+        assert_eq!(report.peak, 1.0);
+
+        let report = meter.push(&[0.0001; 50]).unwrap();
+        assert!(report.below_noise_floor);
+        assert!(!report.clipping);
+    }
+
+    #[test]
+    fn test_silence_has_finite_level() {
+        let mut meter = LevelMeter::new(1000, -60.0);
+        let report = meter.push(&[0.0; 50]).unwrap();
+        assert_eq!(report.rms_dbfs, MIN_DBFS);
+        assert!(report.below_noise_floor);
+    }
+}
@@ -0,0 +1,170 @@
+use std::collections::VecDeque;
+
+use serde::{Deserialize, Serialize};
+
+use crate::audio::pipeline::DspStage;
+
+/// Параметры limiter (стадия `limiter`).
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LimiterParams {
+    /// Максимальная амплитуда выхода (предотвращение клиппинга)
+    pub threshold: f32,
+    /// Насколько limiter заглядывает вперёд, мс: за это время усиление плавно
+    /// опускается до пика, а не срезает его
+    pub lookahead_ms: f32,
+}
+
+impl Default for LimiterParams {
+    fn default() -> Self {
+        Self {
+            threshold: 0.95,
+            lookahead_ms: 5.0,
+        }
+    }
+}
+
+impl LimiterParams {
+    pub fn validate(&self) -> Result<(), String> {
+        if !(0.1..=1.0).contains(&self.threshold) {
+            return Err("Limiter threshold must be between 0.1 and 1.0".to_string());
+        }
+        if !(0.5..=50.0).contains(&self.lookahead_ms) {
+            return Err("Limiter look-ahead must be between 0.5 and 50 ms".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// Limiter с заглядыванием вперёд.
+/// 
+/// Держит пики ниже `threshold`: для каждого сэмпла нужное ослабление известно
+/// за `lookahead_ms` до него, и усиление опускается к нему линейно.
+/// 
+/// Выход отстаёт на `lookahead_ms`, после `flush` той же длины, что вход.
+pub struct Limiter {
+    sample_rate: u32,
+    params: LimiterParams,
+    limit: f32,
+    lookahead: usize,
+    /// Сэмплы, ждущие решения
+    delay: VecDeque<f32>,
+    /// Скользящий минимум нужного ослабления: (номер сэмпла, ослабление)
+    minimum: VecDeque<(u64, f32)>,
+    /// Последние `lookahead` значений скользящего минимума и их сумма
+    ramp: VecDeque<f32>,
+    ramp_sum: f64,
+    index: u64,
+    consumed: u64,
+    produced: u64,
+}
+
+impl Limiter {
+    /// Параметры:
+    /// * `sample_rate` - частота сигнала
+    /// * `params` - параметры стадии из профиля обработки
+    pub fn new(sample_rate: u32, params: &LimiterParams) -> Self {
+        let lookahead = ((sample_rate as f32 * params.lookahead_ms / 1000.0) as usize).max(1);
+        Self {
+            sample_rate,
+            params: *params,
+            limit: params.threshold.clamp(f32::EPSILON, 1.0),
+            lookahead,
+            delay: VecDeque::with_capacity(lookahead),
+            minimum: VecDeque::new(),
+            ramp: VecDeque::with_capacity(lookahead),
+            ramp_sum: 0.0,
+            index: 0,
+            consumed: 0,
+            produced: 0,
+        }
+    }
+
+    /// Пропускает сэмпл; выдаёт сэмпл, пришедший `lookahead - 1` сэмплов назад.
+    fn limit_sample(&mut self, sample: f32, output: &mut Vec<f32>) {
+        // Ослабление, при котором этот сэмпл не превысит limit
+        let needed = if sample.abs() > self.limit { self.limit / sample.abs() } else { 1.0 };
+
+        // Минимум нужного ослабления за последние lookahead сэмплов
+        while self.minimum.back().is_some_and(|&(_, value)| value >= needed) {
+            self.minimum.pop_back();
+        }
+        self.minimum.push_back((self.index, needed));
+        while self.minimum.front().is_some_and(|&(i, _)| i + self.lookahead as u64 <= self.index) {
+            self.minimum.pop_front();
+        }
+        let window_min = self.minimum.front().map_or(1.0, |&(_, value)| value);
+
+        // Среднее минимумов: каждый из них покрывает выдаваемый сэмпл, поэтому
+        // среднее не больше нужного ему ослабления и меняется плавно
+        self.ramp.push_back(window_min);
+        self.ramp_sum += window_min as f64;
+        if self.ramp.len() > self.lookahead {
+            self.ramp_sum -= self.ramp.pop_front().unwrap_or(1.0) as f64;
+        }
+
+        self.delay.push_back(sample);
+        self.index += 1;
+        if self.delay.len() == self.lookahead {
+            let delayed = self.delay.pop_front().unwrap_or(0.0);
+            let gain = (self.ramp_sum / self.lookahead as f64) as f32;
+            output.push((delayed * gain).clamp(-self.limit, self.limit));
+            self.produced += 1;
+        }
+    }
+}
+
+impl DspStage for Limiter {
+    fn process(&mut self, input: &[f32], output: &mut Vec<f32>) {
+        self.consumed += input.len() as u64;
+        for &sample in input {
+            self.limit_sample(sample, output);
+        }
+    }
+
+    fn flush(&mut self, output: &mut Vec<f32>) {
+        while self.produced < self.consumed {
+            self.limit_sample(0.0, output);
+        }
+    }
+
+    fn reset(&mut self) {
+        *self = Self::new(self.sample_rate, &self.params);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RATE: u32 = 16000;
+
+    fn tone(seconds: f32, amplitude: f32) -> Vec<f32> {
+        (0..(RATE as f32 * seconds) as usize)
+            .map(|n| amplitude * (2.0 * std::f32::consts::PI * 300.0 * n as f32 / RATE as f32).sin())
+            .collect()
+    }
+
+    #[test]
+    fn test_limiter_catches_sudden_peak() {
+        // Усиленная тихая речь и резкий кашель (пик в 3 раза выше полной шкалы)
+        let mut input = tone(0.2, 0.2);
+        input.extend(tone(0.05, 3.0));
+        input.extend(tone(0.2, 0.2));
+
+        let mut limiter = Limiter::new(RATE, &LimiterParams::default());
+        let mut output = Vec::new();
+        limiter.process(&input, &mut output);
+        limiter.flush(&mut output);
+        assert_eq!(output.len(), input.len());
+
+        let peak = output.iter().map(|s| s.abs()).fold(0.0, f32::max);
+        assert!(peak <= 0.95 + 1e-6, "peak {}", peak);
+        assert!(peak > 0.5, "burst over-compressed to {}", peak);
+        // Усиление опускается заранее, а не срезает пики: плоских вершин нет
+        let flat = output.windows(2).filter(|w| w.iter().all(|s| s.abs() >= 0.95 - 1e-6)).count();
+        assert_eq!(flat, 0);
+        // Вне пика сигнал не тронут и выровнен со входом
+        assert!((output[1000] - input[1000]).abs() < 1e-6);
+    }
+}
@@ -0,0 +1,370 @@
+use crate::audio::capture::SampleSink;
+#[cfg(windows)]
+use crate::audio::capture::CpalSource;
+use crate::audio::devices::{DeviceConfig, InputDevice, StreamPreferences};
+use crate::audio::source::{AudioSource, SourceConfig, SourceGuard};
+#[cfg(target_os = "linux")]
+use crate::audio::source::spawn_feeder;
+
+/// Префикс ID loopback источника: звук, который играет система
+pub const LOOPBACK_ID_PREFIX: &str = "loopback:";
+/// Части имён входных устройств, которые на самом деле записывают выход системы
+/// (виртуальные устройства и "Stereo Mix" звуковых карт)
+const LOOPBACK_NAME_HINTS: [&str; 6] = ["monitor", "loopback", "stereo mix", "what u hear", "blackhole", "soundflower"];
+/// Частота записи monitor источника, если ни предпочтения, ни источник её не задают
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+const DEFAULT_MONITOR_RATE: u32 = 48000;
+/// Задержка, которую `parec` запрашивает у звукового сервера
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+const PAREC_LATENCY_MS: u32 = 20;
+
+/// Похоже ли имя входного устройства на loopback (запись выхода системы).
+pub fn is_loopback_name(name: &str) -> bool {
+    let name = name.to_lowercase();
+    LOOPBACK_NAME_HINTS.iter().any(|hint| name.contains(hint))
+}
+
+/// Является ли ID устройства ID loopback источника.
+pub fn is_loopback_id(id: &str) -> bool {
+    id.starts_with(LOOPBACK_ID_PREFIX)
+}
+
+/// Перечисляет источники звука системы для записи.
+/// 
+/// * Linux - monitor источники PulseAudio/PipeWire (`pactl list sources`)
+/// * Windows - устройства вывода, которые пишутся через WASAPI loopback
+/// * остальные платформы - ничего: виртуальные устройства (BlackHole и т.п.)
+///   уже видны как входные и отмечаются по имени
+/// 
+/// Ошибки только логируются: без loopback источников список микрофонов всё равно нужен.
+pub fn list_loopback_devices() -> Vec<InputDevice> {
+    #[cfg(target_os = "linux")]
+    {
+        pulse_monitor_sources()
+    }
+    #[cfg(windows)]
+    {
+        wasapi_loopback_devices()
+    }
+    #[cfg(not(any(target_os = "linux", windows)))]
+    {
+        Vec::new()
+    }
+}
+
+/// Находит loopback источник по ID или имени.
+pub fn find_loopback_device(query: &str) -> Option<InputDevice> {
+    list_loopback_devices().into_iter().find(|d| d.id == query || d.name == query)
+}
+
+/// Monitor источники PulseAudio/PipeWire.
+#[cfg(target_os = "linux")]
+fn pulse_monitor_sources() -> Vec<InputDevice> {
+    // LC_ALL=C: заголовки полей pactl переводятся на язык системы
+    let output = std::process::Command::new("pactl")
+        .env("LC_ALL", "C")
+        .args(["list", "sources"])
+        .output();
+
+    match output {
+        Ok(output) if output.status.success() => parse_pactl_sources(&String::from_utf8_lossy(&output.stdout)),
+        Ok(output) => {
+            log::debug!("pactl list sources failed: {}", String::from_utf8_lossy(&output.stderr).trim());
+            Vec::new()
+        }
+        Err(e) => {
+            log::debug!("pactl is not available, no monitor sources: {}", e);
+            Vec::new()
+        }
+    }
+}
+
+/// Устройства вывода как loopback источники WASAPI.
+#[cfg(windows)]
+fn wasapi_loopback_devices() -> Vec<InputDevice> {
+    use cpal::traits::{DeviceTrait, HostTrait};
+
+    let host = cpal::default_host();
+    let default_name = host.default_output_device().and_then(|d| d.name().ok());
+    let devices = match host.output_devices() {
+        Ok(devices) => devices,
+        Err(e) => {
+            log::warn!("Failed to retrieve output devices: {}", e);
+            return Vec::new();
+        }
+    };
+
+    devices
+        .filter_map(|device| {
+            let name = device.name().ok()?;
+            let default_config = device.default_output_config().ok().map(|config| DeviceConfig {
+                sample_rate: config.sample_rate().0,
+                channels: config.channels(),
+                sample_format: format!("{:?}", config.sample_format()),
+            });
+            Some(InputDevice {
+                id: format!("{}{}", LOOPBACK_ID_PREFIX, name),
+                is_default: false,
+                is_loopback: true,
+                host_api: host.id().name().to_string(),
+                name: if default_name.as_deref() == Some(name.as_str()) {
+                    format!("{} (loopback, default output)", name)
+                } else {
+                    format!("{} (loopback)", name)
+                },
+                default_config,
+            })
+        })
+        .collect()
+}
+
+/// Разбирает вывод `LC_ALL=C pactl list sources` и оставляет только monitor источники.
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+fn parse_pactl_sources(text: &str) -> Vec<InputDevice> {
+    let mut devices = Vec::new();
+    let mut block: Option<(Option<String>, Option<String>, Option<DeviceConfig>)> = None;
+
+    let mut flush = |block: Option<(Option<String>, Option<String>, Option<DeviceConfig>)>| {
+        if let Some((Some(name), description, default_config)) = block {
+            if name.ends_with(".monitor") {
+                devices.push(InputDevice {
+                    id: format!("{}{}", LOOPBACK_ID_PREFIX, name),
+                    name: description.unwrap_or_else(|| name.clone()),
+                    host_api: "PulseAudio".to_string(),
+                    default_config,
+                    is_default: false,
+                    is_loopback: true,
+                });
+            }
+        }
+    };
+
+    for line in text.lines() {
+        if line.starts_with("Source #") {
+            flush(block.take());
+            block = Some((None, None, None));
+            continue;
+        }
+        let Some((name, description, config)) = block.as_mut() else { continue };
+        let line = line.trim();
+        if let Some(value) = line.strip_prefix("Name: ") {
+            *name = Some(value.to_string());
+        } else if let Some(value) = line.strip_prefix("Description: ") {
+            *description = Some(value.to_string());
+        } else if let Some(value) = line.strip_prefix("Sample Specification: ") {
+            *config = parse_sample_spec(value);
+        }
+    }
+    flush(block);
+    devices
+}
+
+/// Разбирает спецификацию PulseAudio вида `s16le 2ch 44100Hz`.
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+fn parse_sample_spec(spec: &str) -> Option<DeviceConfig> {
+    let mut parts = spec.split_whitespace();
+    let format = parts.next()?;
+    let channels = parts.next()?.strip_suffix("ch")?.parse().ok()?;
+    let sample_rate = parts.next()?.strip_suffix("Hz")?.parse().ok()?;
+
+    let sample_format = match format {
+        f if f.starts_with("float32") => "F32",
+        f if f.starts_with("s16") => "I16",
+        f if f.starts_with("s32") || f.starts_with("s24") => "I32",
+        "u8" => "U8",
+        other => other,
+    };
+    Some(DeviceConfig { sample_rate, channels, sample_format: sample_format.to_string() })
+}
+
+/// Аргументы `parec` для записи monitor источника в raw f32.
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+fn parec_args(monitor: &str, sample_rate: u32, channels: u16) -> Vec<String> {
+    vec![
+        format!("--device={}", monitor),
+        "--format=float32le".to_string(),
+        format!("--rate={}", sample_rate),
+        format!("--channels={}", channels),
+        format!("--latency-msec={}", PAREC_LATENCY_MS),
+    ]
+}
+
+/// Источник звука - то, что играет система (monitor / loopback).
+/// 
+/// На Linux monitor источник записывается через `parec --device=<источник>`
+/// (PulseAudio или PipeWire через pipewire-pulse): источник передаётся явно,
+/// окружение процесса не меняется. На Windows stream записи открывается на
+/// устройстве вывода (WASAPI loopback).
+pub struct LoopbackSource {
+    target: String,
+    #[cfg(target_os = "linux")]
+    monitor: Option<(String, SourceConfig)>,
+    #[cfg(windows)]
+    inner: Option<CpalSource>,
+}
+
+impl LoopbackSource {
+    /// `target` - ID loopback источника (`loopback:<имя>`) или имя без префикса
+    pub fn new(target: &str) -> Self {
+        Self {
+            target: target.strip_prefix(LOOPBACK_ID_PREFIX).unwrap_or(target).to_string(),
+            #[cfg(target_os = "linux")]
+            monitor: None,
+            #[cfg(windows)]
+            inner: None,
+        }
+    }
+}
+
+/// Процесс `parec`; drop завершает его.
+#[cfg(target_os = "linux")]
+struct ParecProcess(std::process::Child);
+
+#[cfg(target_os = "linux")]
+impl Drop for ParecProcess {
+    fn drop(&mut self) {
+        let _ = self.0.kill();
+        let _ = self.0.wait();
+    }
+}
+
+impl AudioSource for LoopbackSource {
+    #[cfg(target_os = "linux")]
+    fn configure(&mut self, preferences: &StreamPreferences) -> Result<SourceConfig, String> {
+        let id = format!("{}{}", LOOPBACK_ID_PREFIX, self.target);
+        let device = find_loopback_device(&id)
+            .or_else(|| find_loopback_device(&self.target))
+            .ok_or_else(|| format!("Monitor source not found: {}", self.target))?;
+        let monitor = device.id[LOOPBACK_ID_PREFIX.len()..].to_string();
+
+        // Звуковой сервер сам приводит звук к запрошенному формату
+        let config = SourceConfig {
+            sample_rate: preferences
+                .sample_rate
+                .or(device.default_config.as_ref().map(|c| c.sample_rate))
+                .unwrap_or(DEFAULT_MONITOR_RATE),
+            channels: preferences
+                .channels
+                .or(device.default_config.as_ref().map(|c| c.channels))
+                .unwrap_or(2)
+                .max(1),
+            sample_format: cpal::SampleFormat::F32,
+            name: device.name,
+            is_loopback: true,
+        };
+        self.monitor = Some((monitor, config.clone()));
+        Ok(config)
+    }
+
+    #[cfg(windows)]
+    fn configure(&mut self, preferences: &StreamPreferences) -> Result<SourceConfig, String> {
+        let mut inner = CpalSource::output(&self.target);
+        let config = inner.configure(preferences)?;
+        self.inner = Some(inner);
+        Ok(SourceConfig { is_loopback: true, ..config })
+    }
+
+    #[cfg(not(any(target_os = "linux", windows)))]
+    fn configure(&mut self, _preferences: &StreamPreferences) -> Result<SourceConfig, String> {
+        Err(format!(
+            "Loopback capture of {} is not supported on this platform, use a virtual input device",
+            self.target
+        ))
+    }
+
+    #[cfg(target_os = "linux")]
+    fn start(self: Box<Self>, sink: SampleSink) -> Result<SourceGuard, String> {
+        use std::io::Read;
+
+        let (monitor, config) = self.monitor.ok_or_else(|| "Loopback source is not configured".to_string())?;
+        let mut child = std::process::Command::new("parec")
+            .args(parec_args(&monitor, config.sample_rate, config.channels))
+            .stdin(std::process::Stdio::null())
+            .stdout(std::process::Stdio::piped())
+            .stderr(std::process::Stdio::null())
+            .spawn()
+            .map_err(|e| format!("Failed to start parec for {} ({})", monitor, e))?;
+        let mut stdout = child.stdout.take().ok_or_else(|| "parec has no stdout".to_string())?;
+        let process = ParecProcess(child);
+
+        let mut bytes = Vec::new();
+        let feeder = spawn_feeder("loopback-source", sink, config.sample_rate, config.channels, None, move |block, len| {
+            bytes.resize(len * 4, 0);
+            if let Err(e) = stdout.read_exact(&mut bytes) {
+                log::error!("Monitor source {} stopped: {}", monitor, e);
+                return false;
+            }
+            block.extend(bytes.chunks_exact(4).map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]])));
+            true
+        })?;
+        // Сначала останавливается поток чтения, затем процесс
+        Ok(Box::new((feeder, process)))
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    fn start(self: Box<Self>, sink: SampleSink) -> Result<SourceGuard, String> {
+        #[cfg(windows)]
+        {
+            let inner = self.inner.ok_or_else(|| "Loopback source is not configured".to_string())?;
+            Box::new(inner).start(sink)
+        }
+        #[cfg(not(windows))]
+        {
+            drop(sink);
+            Err(format!("Loopback capture of {} is not supported on this platform", self.target))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PACTL_OUTPUT: &str = "Source #0
+\tState: SUSPENDED
+\tName: alsa_output.pci-0000_00_1f.3.analog-stereo.monitor
+\tDescription: Monitor of Built-in Audio Analog Stereo
+\tDriver: PipeWire
+\tSample Specification: s32le 2ch 48000Hz
+\tChannel Map: front-left,front-right
+
+Source #1
+\tState: RUNNING
+\tName: alsa_input.pci-0000_00_1f.3.analog-stereo
+\tDescription: Built-in Audio Analog Stereo
+\tSample Specification: s16le 2ch 44100Hz
+";
+
+    #[test]
+    fn test_parse_pactl_keeps_monitors_only() {
+        let devices = parse_pactl_sources(PACTL_OUTPUT);
+        assert_eq!(devices.len(), 1);
+
+        let monitor = &devices[0];
+        assert_eq!(monitor.id, "loopback:alsa_output.pci-0000_00_1f.3.analog-stereo.monitor");
+        assert_eq!(monitor.name, "Monitor of Built-in Audio Analog Stereo");
+        assert!(monitor.is_loopback);
+        assert_eq!(
+            monitor.default_config,
+            Some(DeviceConfig { sample_rate: 48000, channels: 2, sample_format: "I32".to_string() })
+        );
+    }
+
+    #[test]
+    fn test_parec_args_name_monitor_explicitly() {
+        let args = parec_args("alsa_output.pci-0000_00_1f.3.analog-stereo.monitor", 48000, 2);
+        assert_eq!(args[0], "--device=alsa_output.pci-0000_00_1f.3.analog-stereo.monitor");
+        assert!(args.contains(&"--format=float32le".to_string()));
+        assert!(args.contains(&"--rate=48000".to_string()));
+        assert!(args.contains(&"--channels=2".to_string()));
+    }
+
+    #[test]
+    fn test_loopback_names() {
+        assert!(is_loopback_name("Stereo Mix (Realtek High Definition Audio)"));
+        assert!(is_loopback_name("BlackHole 2ch"));
+        assert!(!is_loopback_name("USB Microphone"));
+        assert!(is_loopback_id("loopback:Speakers"));
+        assert!(!is_loopback_id("alsa:default"));
+    }
+}
@@ -0,0 +1,361 @@
+use std::collections::VecDeque;
+use std::f64::consts::PI;
+
+use serde::{Deserialize, Serialize};
+
+use crate::audio::biquad::Biquad;
+use crate::audio::pipeline::DspStage;
+use crate::audio::resample::Resampler;
+
+/// Шаг блоков измерения (блоки по 400 мс с перекрытием 75%)
+const STEP_MS: u32 = 100;
+/// Шагов в блоке измерения
+const STEPS_PER_BLOCK: usize = 4;
+/// Абсолютный порог гейтинга, LUFS: тишина не занижает громкость
+const ABSOLUTE_GATE_LUFS: f64 = -70.0;
+/// Относительный порог гейтинга, LU ниже громкости блоков над абсолютным порогом
+const RELATIVE_GATE_LU: f64 = -10.0;
+/// Частота, до которой повышается дискретизация при измерении true peak
+const TRUE_PEAK_RATE: u32 = 192_000;
+
+/// Параметры нормализации громкости (стадия `loudness`).
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LoudnessParams {
+    /// Целевая интегральная громкость дубля, LUFS
+    pub target_lufs: f32,
+    /// Насколько можно поднять или опустить громкость, dB
+    pub max_gain_db: f32,
+}
+
+impl Default for LoudnessParams {
+    fn default() -> Self {
+        Self {
+            // Речь на уровне ~-18 dBFS RMS, оптимальном для Whisper
+            target_lufs: -18.0,
+            max_gain_db: 30.0,
+        }
+    }
+}
+
+impl LoudnessParams {
+    pub fn validate(&self) -> Result<(), String> {
+        if !(-40.0..=-6.0).contains(&self.target_lufs) {
+            return Err("Loudness target must be between -40 and -6 LUFS".to_string());
+        }
+        if !(0.0..=40.0).contains(&self.max_gain_db) {
+            return Err("Loudness max gain must be between 0 and 40 dB".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// Громкость сигнала и усиление нормализации (часть `ProcessingReport`).
+/// 
+/// В отчёте обработки громкость и пик измерены по дублю как записан (worker),
+/// а `gain_db` - усиление стадии `loudness`, которая измеряет свой вход:
+/// сигнал после стадий перед ней (фильтры, обрезка, АРУ).
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct LoudnessReport {
+    /// Интегральная громкость, LUFS (None - в дубле только тишина)
+    pub integrated_lufs: Option<f32>,
+    /// Истинный пик (между сэмплами), dBTP (None - сигнал нулевой)
+    pub true_peak_dbtp: Option<f32>,
+    /// Усиление стадии `loudness`, dB (0 - стадии нет в профиле)
+    pub gain_db: f32,
+}
+
+/// K-фильтр ITU-R BS.1770: полка +4 dB выше ~1.7 кГц (влияние головы)
+/// и фильтр высоких частот ~38 Гц (RLB).
+/// 
+/// Коэффициенты пересчитываются из аналоговых прототипов для любой частоты
+/// дискретизации; на 48 кГц совпадают с таблицами стандарта.
+fn k_weighting(sample_rate: u32) -> (Biquad, Biquad) {
+    let rate = sample_rate.max(1) as f64;
+
+    let (frequency, gain_db, q) = (1681.974450955533, 3.999843853973347, 0.7071752369554196);
+    let k = (PI * frequency / rate).tan();
+    let vh = 10f64.powf(gain_db / 20.0);
+    let vb = vh.powf(0.4996667741545416);
+    let shelf = Biquad::new(
+        [vh + vb * k / q + k * k, 2.0 * (k * k - vh), vh - vb * k / q + k * k],
+        [1.0 + k / q + k * k, 2.0 * (k * k - 1.0), 1.0 - k / q + k * k],
+    );
+
+    let (frequency, q) = (38.13547087602444, 0.5003270373238773);
+    let k = (PI * frequency / rate).tan();
+    // В стандарте числитель не нормирован: b = [1, -2, 1]
+    let a0 = 1.0 + k / q + k * k;
+    let high_pass = Biquad::new([a0, -2.0 * a0, a0], [a0, 2.0 * (k * k - 1.0), 1.0 - k / q + k * k]);
+
+    (shelf, high_pass)
+}
+
+/// Громкость по среднему квадрату K-взвешенного сигнала, LUFS.
+fn lufs(mean_square: f64) -> f64 {
+    -0.691 + 10.0 * mean_square.log10()
+}
+
+/// Измеритель громкости по ITU-R BS.1770-4 (моно).
+/// 
+/// Сигнал проходит K-фильтр, средний квадрат считается блоками по 400 мс
+/// с шагом 100 мс. Интегральная громкость - по блокам громче -70 LUFS
+/// (абсолютный гейт) и не тише их общей громкости на 10 LU (относительный гейт),
+/// поэтому паузы и тихий фон её не занижают.
+/// 
+/// True peak - максимум сигнала с дискретизацией, повышенной до ~192 кГц
+/// (`Resampler`): пик между сэмплами бывает выше любого сэмпла.
+pub struct LoudnessMeter {
+    shelf: Biquad,
+    high_pass: Biquad,
+    step_len: usize,
+    step_sum: f64,
+    step_count: usize,
+    /// Суммы квадратов последних шагов (для блока из `STEPS_PER_BLOCK`)
+    steps: VecDeque<f64>,
+    /// Средний квадрат каждого полного блока
+    blocks: Vec<f64>,
+    oversampler: Resampler,
+    oversampled: Vec<f32>,
+    peak: f32,
+}
+
+impl LoudnessMeter {
+    /// Параметры:
+    /// * `sample_rate` - частота сигнала
+    pub fn new(sample_rate: u32) -> Self {
+        let (shelf, high_pass) = k_weighting(sample_rate);
+        let factor = (TRUE_PEAK_RATE / sample_rate.max(1)).clamp(1, 4);
+        Self {
+            shelf,
+            high_pass,
+            step_len: (sample_rate as usize * STEP_MS as usize / 1000).max(1),
+            step_sum: 0.0,
+            step_count: 0,
+            steps: VecDeque::with_capacity(STEPS_PER_BLOCK),
+            blocks: Vec::new(),
+            oversampler: Resampler::new(sample_rate, sample_rate * factor),
+            oversampled: Vec::new(),
+            peak: 0.0,
+        }
+    }
+
+    /// Добавляет блок сигнала к измерению.
+    pub fn add(&mut self, input: &[f32]) {
+        for &sample in input {
+            let weighted = self.high_pass.filter(self.shelf.filter(sample)) as f64;
+            self.step_sum += weighted * weighted;
+            self.step_count += 1;
+            if self.step_count == self.step_len {
+                self.finish_step();
+            }
+        }
+
+        self.oversampled.clear();
+        self.oversampler.process(input, &mut self.oversampled);
+        self.update_peak(input);
+    }
+
+    fn finish_step(&mut self) {
+        if self.steps.len() == STEPS_PER_BLOCK {
+            self.steps.pop_front();
+        }
+        self.steps.push_back(self.step_sum);
+        if self.steps.len() == STEPS_PER_BLOCK {
+            let block_sum: f64 = self.steps.iter().sum();
+            self.blocks.push(block_sum / (STEPS_PER_BLOCK * self.step_len) as f64);
+        }
+        self.step_sum = 0.0;
+        self.step_count = 0;
+    }
+
+    fn update_peak(&mut self, input: &[f32]) {
+        let peak = self.oversampled.iter().chain(input).map(|s| s.abs()).fold(0.0, f32::max);
+        self.peak = self.peak.max(peak);
+    }
+
+    /// Интегральная громкость, LUFS. None - нет ни одного блока громче -70 LUFS
+    /// (тишина или сигнал короче 400 мс).
+    pub fn integrated_lufs(&self) -> Option<f32> {
+        let mean = |blocks: &[f64]| blocks.iter().sum::<f64>() / blocks.len() as f64;
+
+        let audible: Vec<f64> = self.blocks.iter().copied().filter(|&ms| lufs(ms) > ABSOLUTE_GATE_LUFS).collect();
+        if audible.is_empty() {
+            return None;
+        }
+        let relative_gate = lufs(mean(&audible)) + RELATIVE_GATE_LU;
+        let gated: Vec<f64> = audible.into_iter().filter(|&ms| lufs(ms) > relative_gate).collect();
+        Some(lufs(mean(&gated)) as f32)
+    }
+
+    /// Истинный пик всего сигнала, dBTP. None - сигнал нулевой.
+    /// Дописывает хвост передискретизации, поэтому вызывается после всего сигнала.
+    pub fn true_peak_dbtp(&mut self) -> Option<f32> {
+        self.oversampled.clear();
+        self.oversampler.flush(&mut self.oversampled);
+        self.update_peak(&[]);
+        (self.peak > 0.0).then(|| 20.0 * self.peak.log10())
+    }
+
+    /// Отчёт по всему сигналу с усилением `gain_db`.
+    /// Вызывается после всего сигнала (см. `true_peak_dbtp`).
+    pub fn report(&mut self, gain_db: f32) -> LoudnessReport {
+        LoudnessReport {
+            integrated_lufs: self.integrated_lufs(),
+            true_peak_dbtp: self.true_peak_dbtp(),
+            gain_db,
+        }
+    }
+}
+
+/// Нормализация громкости дубля к `target_lufs`.
+/// 
+/// В предварительном проходе `LoudnessMeter` измеряет громкость, затем весь
+/// дубль получает одно усиление (не больше `max_gain_db` в обе стороны),
+/// поэтому соотношение громкости внутри дубля сохраняется. Пики после
+/// усиления ловит стадия `limiter`. Тихий дубль (только тишина) не усиливается.
+pub struct Loudness {
+    params: LoudnessParams,
+    meter: LoudnessMeter,
+    /// Результат измерения, вычисляется при первом `process`
+    report: Option<LoudnessReport>,
+}
+
+impl Loudness {
+    /// Параметры:
+    /// * `sample_rate` - частота сигнала
+    /// * `params` - параметры стадии из профиля обработки
+    pub fn new(sample_rate: u32, params: &LoudnessParams) -> Self {
+        Self {
+            params: *params,
+            meter: LoudnessMeter::new(sample_rate),
+            report: None,
+        }
+    }
+
+    fn measure(&mut self) -> LoudnessReport {
+        let integrated_lufs = self.meter.integrated_lufs();
+        let gain_db = integrated_lufs.map_or(0.0, |measured| {
+            (self.params.target_lufs - measured).clamp(-self.params.max_gain_db, self.params.max_gain_db)
+        });
+        let report = self.meter.report(gain_db);
+        log::info!(
+            "Loudness stage input: {:?} LUFS, true peak {:?} dBTP, gain {:+.1} dB",
+            report.integrated_lufs, report.true_peak_dbtp, report.gain_db
+        );
+        report
+    }
+}
+
+impl DspStage for Loudness {
+    fn process(&mut self, input: &[f32], output: &mut Vec<f32>) {
+        let report = match self.report {
+            Some(report) => report,
+            None => {
+                let report = self.measure();
+                self.report = Some(report);
+                report
+            }
+        };
+        let gain = 10f32.powf(report.gain_db / 20.0);
+        output.extend(input.iter().map(|sample| sample * gain));
+    }
+
+    fn needs_analysis(&self) -> bool {
+        true
+    }
+
+    fn analyze(&mut self, input: &[f32]) {
+        self.meter.add(input);
+    }
+
+    fn loudness(&self) -> Option<LoudnessReport> {
+        self.report
+    }
+
+    fn reset(&mut self) {}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RATE: u32 = 48000;
+
+    fn sine(frequency: f32, amplitude: f32, seconds: f32) -> Vec<f32> {
+        (0..(RATE as f32 * seconds) as usize)
+            .map(|n| amplitude * (2.0 * std::f32::consts::PI * frequency * n as f32 / RATE as f32).sin())
+            .collect()
+    }
+
+    fn measure(input: &[f32]) -> LoudnessMeter {
+        let mut meter = LoudnessMeter::new(RATE);
+        for chunk in input.chunks(1000) {
+            meter.add(chunk);
+        }
+        meter
+    }
+
+    #[test]
+    fn test_k_weighting_matches_standard_at_48k() {
+        // Коэффициенты из таблиц 1 и 2 ITU-R BS.1770-4
+        let (shelf, high_pass) = k_weighting(RATE);
+        let expected_shelf = [1.53512485958697, -2.69169618940638, 1.19839281085285, -1.69065929318241, 0.73248077421585];
+        let expected_high_pass = [1.0, -2.0, 1.0, -1.99004745483398, 0.99007225036621];
+        for (filter, expected) in [(shelf, expected_shelf), (high_pass, expected_high_pass)] {
+            for (actual, expected) in filter.coefficients().into_iter().zip(expected) {
+                assert!((actual - expected).abs() < 1e-6, "{} != {}", actual, expected);
+            }
+        }
+    }
+
+    #[test]
+    fn test_sine_loudness() {
+        // Синус 1 кГц с амплитудой 0 dBFS по стандарту даёт -3.01 LUFS
+        let meter = measure(&sine(1000.0, 0.1, 5.0));
+        let lufs = meter.integrated_lufs().unwrap();
+        assert!((lufs + 23.01).abs() < 0.1, "{} LUFS", lufs);
+    }
+
+    #[test]
+    fn test_gating_ignores_silence_and_quiet_background() {
+        let mut input = sine(1000.0, 0.1, 4.0);
+        input.extend(vec![0.0; 2 * RATE as usize]);
+        // Фон на 37 dB тише речи: выше абсолютного гейта, ниже относительного
+        input.extend(sine(300.0, 0.0015, 6.0));
+        let lufs = measure(&input).integrated_lufs().unwrap();
+        assert!((lufs + 23.0).abs() < 0.2, "{} LUFS", lufs);
+
+        assert_eq!(measure(&vec![0.0; RATE as usize]).integrated_lufs(), None);
+        assert_eq!(measure(&sine(1000.0, 0.1, 0.3)).integrated_lufs(), None);
+    }
+
+    #[test]
+    fn test_true_peak_between_samples() {
+        // Синус на четверти частоты дискретизации со сдвигом фазы 45°:
+        // все сэмплы на 0.707 от амплитуды, истинный пик - между ними
+        let sample = 0.5 * std::f32::consts::FRAC_1_SQRT_2;
+        let input: Vec<f32> = [sample, sample, -sample, -sample].repeat(RATE as usize / 4);
+        let sample_peak = 20.0 * input.iter().map(|s| s.abs()).fold(0.0, f32::max).log10();
+        let true_peak = measure(&input).true_peak_dbtp().unwrap();
+        assert!((sample_peak + 9.03).abs() < 0.05, "sample peak {} dBFS", sample_peak);
+        assert!((true_peak + 6.02).abs() < 0.3, "true peak {} dBTP", true_peak);
+    }
+
+    #[test]
+    fn test_take_is_normalized_to_target() {
+        let mut input = sine(1000.0, 0.01, 3.0);
+        input.extend(vec![0.0; RATE as usize]);
+        let mut stage = Loudness::new(RATE, &LoudnessParams::default());
+        stage.analyze(&input);
+        let mut output = Vec::new();
+        stage.process(&input, &mut output);
+
+        let report = stage.loudness().unwrap();
+        let measured = report.integrated_lufs.unwrap();
+        assert!((measured + 43.0).abs() < 0.3, "{} LUFS", measured);
+        assert!((report.gain_db + 18.0 + measured).abs() < 1e-4, "gain {} dB", report.gain_db);
+        let lufs = measure(&output).integrated_lufs().unwrap();
+        assert!((lufs + 18.0).abs() < 0.1, "{} LUFS", lufs);
+    }
+}
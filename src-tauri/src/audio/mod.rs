@@ -1,4 +1,24 @@
 pub mod capture;
 pub mod processor;
 pub mod formats;
-pub mod worker;
\ No newline at end of file
+pub mod worker;
+pub mod session;
+pub mod recording;
+pub mod devices;
+pub mod downmix;
+pub mod level;
+pub mod vad;
+pub mod source;
+pub mod loopback;
+pub mod tracks;
+pub mod state;
+pub mod resample;
+pub mod denoise;
+pub mod agc;
+pub mod limiter;
+pub mod pipeline;
+pub mod trim;
+pub mod biquad;
+pub mod loudness;
+pub mod quality;
+pub mod gate;
\ No newline at end of file
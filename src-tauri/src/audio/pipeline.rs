@@ -0,0 +1,298 @@
+use serde::{Deserialize, Serialize};
+
+use crate::audio::agc::{Agc, AgcParams};
+use crate::audio::biquad::{Biquad, DcBlockParams, DcBlocker, HighPassParams};
+use crate::audio::denoise::{DenoiseParams, SpectralDenoiser};
+use crate::audio::gate::{GateParams, SoftGate};
+use crate::audio::limiter::{Limiter, LimiterParams};
+use crate::audio::loudness::{Loudness, LoudnessParams, LoudnessReport};
+use crate::audio::trim::{Trim, TrimParams};
+
+/// Стадия обработки дубля.
+/// 
+/// Стадии потоковые: получают блоки любого размера и могут выдавать сэмплы
+/// с задержкой (тогда остаток выдаётся в `flush`). Стадии, которым нужны
+/// уровни всего сигнала (порог noise gate, громкость), сначала получают сигнал в `analyze`.
+/// Длина выхода после `flush` равна длине входа, кроме стадий, обрезающих
+/// сигнал (см. `kept_range`).
+pub trait DspStage: Send {
+    /// Обрабатывает блок входа и дописывает готовые сэмплы в `output`.
+    fn process(&mut self, input: &[f32], output: &mut Vec<f32>);
+
+    /// Дописывает сэмплы, задержанные стадией, после конца сигнала.
+    fn flush(&mut self, _output: &mut Vec<f32>) {}
+
+    /// Нужен ли стадии предварительный проход по всему сигналу.
+    fn needs_analysis(&self) -> bool {
+        false
+    }
+
+    /// Предварительный проход: стадия видит тот же сигнал, что потом обработает.
+    fn analyze(&mut self, _input: &[f32]) {}
+
+    /// Какую часть входа стадия оставляет: (первый сэмпл, конец).
+    /// None - вход не обрезается.
+    fn kept_range(&self) -> Option<(u64, u64)> {
+        None
+    }
+
+    /// Измеренная громкость (для стадии нормализации громкости).
+    fn loudness(&self) -> Option<LoudnessReport> {
+        None
+    }
+
+    /// Сбрасывает потоковое состояние перед новым проходом.
+    /// Результат `analyze` сохраняется.
+    fn reset(&mut self);
+}
+
+/// Стадия цепочки обработки с параметрами (хранится в профиле обработки).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "stage", rename_all = "snake_case")]
+pub enum StageConfig {
+    /// Удаление постоянной составляющей
+    DcBlock(DcBlockParams),
+    /// Фильтр высоких частот (гул, вибрации)
+    HighPass(HighPassParams),
+    /// Мягкий noise gate с порогом по RMS всего дубля
+    NoiseGate(GateParams),
+    /// Спектральное вычитание шума
+    Denoise(DenoiseParams),
+    /// Обрезка тишины в начале и конце дубля
+    Trim(TrimParams),
+    /// Автоматическая регулировка усиления
+    Agc(AgcParams),
+    /// Нормализация громкости дубля (ITU-R BS.1770)
+    Loudness(LoudnessParams),
+    /// Limiter с заглядыванием вперёд
+    Limiter(LimiterParams),
+}
+
+impl StageConfig {
+    /// Проверяет параметры стадии.
+    pub fn validate(&self) -> Result<(), String> {
+        match self {
+            StageConfig::DcBlock(params) => params.validate(),
+            StageConfig::HighPass(params) => params.validate(),
+            StageConfig::NoiseGate(params) => params.validate(),
+            StageConfig::Denoise(params) => params.validate(),
+            StageConfig::Trim(params) => params.validate(),
+            StageConfig::Agc(params) => params.validate(),
+            StageConfig::Loudness(params) => params.validate(),
+            StageConfig::Limiter(params) => params.validate(),
+        }
+    }
+
+    /// Создаёт стадию для сигнала с частотой `sample_rate`.
+    pub fn build(&self, sample_rate: u32) -> Box<dyn DspStage> {
+        match self {
+            StageConfig::DcBlock(params) => Box::new(DcBlocker::new(sample_rate, params)),
+            StageConfig::HighPass(params) => Box::new(Biquad::high_pass(sample_rate, params)),
+            StageConfig::NoiseGate(params) => Box::new(SoftGate::new(params)),
+            StageConfig::Denoise(params) => Box::new(SpectralDenoiser::new(sample_rate, params)),
+            StageConfig::Trim(params) => Box::new(Trim::new(sample_rate, params)),
+            StageConfig::Agc(params) => Box::new(Agc::new(sample_rate, params)),
+            StageConfig::Loudness(params) => Box::new(Loudness::new(sample_rate, params)),
+            StageConfig::Limiter(params) => Box::new(Limiter::new(sample_rate, params)),
+        }
+    }
+}
+
+/// Цепочка стадий: выход каждой стадии - вход следующей.
+pub struct Pipeline {
+    stages: Vec<Box<dyn DspStage>>,
+    /// Промежуточные буферы между стадиями
+    buffers: Vec<Vec<f32>>,
+}
+
+impl Pipeline {
+    /// Собирает цепочку из конфигурации профиля.
+    /// 
+    /// Параметры:
+    /// * `configs` - стадии по порядку
+    /// * `sample_rate` - частота сигнала
+    pub fn build(configs: &[StageConfig], sample_rate: u32) -> Self {
+        Self::from_stages(configs.iter().map(|config| config.build(sample_rate)).collect())
+    }
+
+    pub fn from_stages(stages: Vec<Box<dyn DspStage>>) -> Self {
+        let buffers = vec![Vec::new(); stages.len()];
+        Self { stages, buffers }
+    }
+
+    /// Нужен ли предварительный проход (см. `prepare`).
+    pub fn needs_analysis(&self) -> bool {
+        self.stages.iter().any(|stage| stage.needs_analysis())
+    }
+
+    /// Предварительные проходы для стадий, которым нужен весь сигнал.
+    /// 
+    /// Для каждой такой стадии сигнал заново проходит через стадии до неё,
+    /// поэтому она анализирует ровно то, что потом обработает. После проходов
+    /// потоковое состояние сброшено и цепочка готова к `process`.
+    /// 
+    /// Параметры:
+    /// * `replay` - читает сигнал с начала и передаёт блоки получателю
+    pub fn prepare<R>(&mut self, mut replay: R) -> Result<(), String>
+    where
+        R: FnMut(&mut dyn FnMut(&[f32]) -> Result<(), String>) -> Result<(), String>,
+    {
+        for index in 0..self.stages.len() {
+            if !self.stages[index].needs_analysis() {
+                continue;
+            }
+
+            let (before, rest) = self.stages.split_at_mut(index);
+            let stage = &mut rest[0];
+            let mut output = Vec::new();
+            replay(&mut |block| {
+                output.clear();
+                run_stages(before, &mut self.buffers, block, &mut output);
+                stage.analyze(&output);
+                Ok(())
+            })?;
+            output.clear();
+            flush_stages(before, &mut self.buffers, &mut output);
+            stage.analyze(&output);
+
+            before.iter_mut().for_each(|stage| stage.reset());
+        }
+        Ok(())
+    }
+
+    /// Обрабатывает блок всеми стадиями и дописывает результат в `output`.
+    pub fn process(&mut self, input: &[f32], output: &mut Vec<f32>) {
+        run_stages(&mut self.stages, &mut self.buffers, input, output);
+    }
+
+    /// Какая часть входа цепочки попала в выход: (первый сэмпл, конец).
+    /// None - сигнал не обрезан.
+    pub fn kept_range(&self) -> Option<(u64, u64)> {
+        self.stages
+            .iter()
+            .filter_map(|stage| stage.kept_range())
+            .reduce(|(start, end), (from, to)| (start + from, (start + to).min(end)))
+    }
+
+    /// Громкость, измеренная последней стадией нормализации громкости.
+    pub fn loudness(&self) -> Option<LoudnessReport> {
+        self.stages.iter().rev().find_map(|stage| stage.loudness())
+    }
+
+    /// Выдаёт хвосты всех стадий по порядку.
+    pub fn flush(&mut self, output: &mut Vec<f32>) {
+        flush_stages(&mut self.stages, &mut self.buffers, output);
+    }
+}
+
+/// Пропускает блок через стадии по порядку.
+fn run_stages(stages: &mut [Box<dyn DspStage>], buffers: &mut [Vec<f32>], input: &[f32], output: &mut Vec<f32>) {
+    let Some((last, first)) = stages.split_last_mut() else {
+        output.extend_from_slice(input);
+        return;
+    };
+
+    // Стадия `index` читает выход предыдущей (или `input`) и пишет в `buffers[index]`
+    for (index, stage) in first.iter_mut().enumerate() {
+        let (done, rest) = buffers.split_at_mut(index);
+        let source = done.last().map_or(input, |buffer| buffer.as_slice());
+        rest[0].clear();
+        stage.process(source, &mut rest[0]);
+    }
+    let source = first.len().checked_sub(1).map_or(input, |index| buffers[index].as_slice());
+    last.process(source, output);
+}
+
+/// Выдаёт хвост каждой стадии, пропуская его через следующие.
+fn flush_stages(stages: &mut [Box<dyn DspStage>], buffers: &mut [Vec<f32>], output: &mut Vec<f32>) {
+    for index in 0..stages.len() {
+        let mut tail = Vec::new();
+        stages[index].flush(&mut tail);
+        run_stages(&mut stages[index + 1..], &mut buffers[index + 1..], &tail, output);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    /// Стадия с задержкой: выдаёт вход на `delay` сэмплов позже, умноженным на `gain`.
+    struct Delay {
+        delay: usize,
+        gain: f32,
+        pending: Vec<f32>,
+        /// Сколько сэмплов получил анализ (None - стадии анализ не нужен)
+        analyzed: Option<Arc<AtomicUsize>>,
+    }
+
+    impl Delay {
+        fn new(delay: usize, gain: f32) -> Self {
+            Self { delay, gain, pending: vec![0.0; delay], analyzed: None }
+        }
+    }
+
+    impl DspStage for Delay {
+        fn process(&mut self, input: &[f32], output: &mut Vec<f32>) {
+            self.pending.extend(input.iter().map(|x| x * self.gain));
+            let ready = self.pending.len().saturating_sub(self.delay);
+            output.extend(self.pending.drain(..ready));
+        }
+
+        fn flush(&mut self, output: &mut Vec<f32>) {
+            output.append(&mut self.pending);
+        }
+
+        fn needs_analysis(&self) -> bool {
+            self.analyzed.is_some()
+        }
+
+        fn analyze(&mut self, input: &[f32]) {
+            if let Some(analyzed) = &self.analyzed {
+                analyzed.fetch_add(input.len(), Ordering::Relaxed);
+            }
+        }
+
+        fn reset(&mut self) {
+            self.pending = vec![0.0; self.delay];
+        }
+    }
+
+    #[test]
+    fn test_stages_are_chained_and_flushed() {
+        let mut pipeline = Pipeline::from_stages(vec![Box::new(Delay::new(3, 2.0)), Box::new(Delay::new(2, 0.5))]);
+        let input: Vec<f32> = (1..=10).map(|x| x as f32).collect();
+
+        let mut output = Vec::new();
+        for block in input.chunks(4) {
+            pipeline.process(block, &mut output);
+        }
+        pipeline.flush(&mut output);
+
+        // Задержки складываются, хвосты обеих стадий выданы
+        let mut expected = vec![0.0; 5];
+        expected.extend(&input);
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_analysis_sees_output_of_previous_stages() {
+        let analyzed = Arc::new(AtomicUsize::new(0));
+        let analyzing = Delay { analyzed: Some(analyzed.clone()), ..Delay::new(0, 1.0) };
+        let mut pipeline = Pipeline::from_stages(vec![Box::new(Delay::new(3, 2.0)), Box::new(analyzing)]);
+        assert!(pipeline.needs_analysis());
+
+        let input = vec![0.5; 10];
+        pipeline.prepare(|sink| input.chunks(4).try_for_each(&mut *sink)).unwrap();
+        // Анализ получил весь сигнал вместе с задержкой первой стадии
+        assert_eq!(analyzed.load(Ordering::Relaxed), 13);
+
+        // Первая стадия после прохода сброшена: задержка снова в начале
+        let mut output = Vec::new();
+        pipeline.process(&input, &mut output);
+        pipeline.flush(&mut output);
+        assert_eq!(output.len(), 13);
+        assert_eq!(&output[..3], &[0.0; 3]);
+    }
+}
@@ -1,184 +1,92 @@
-use std::sync::{Arc, RwLock, Mutex};
+use std::sync::{Arc, Mutex};
+use serde::Serialize;
+use crate::audio::loudness::LoudnessReport;
+use crate::audio::pipeline::Pipeline;
+use crate::audio::quality::QualityReport;
+use crate::audio::recording::Take;
+use crate::audio::trim::TrimOffsets;
 use crate::types::AudioCapture;
 
-/// Обрабатывает сырые аудиосэмплы: применяет noise gate и усиление.
-/// 
-/// Реализует лучшие практики для распознавания речи (Whisper):
-/// - Целевой RMS: 0.12 (оптимально для речи, особенно русского языка)
-/// - Адаптивный gain: автоматически подстраивается на основе входного RMS
-/// - Мягкий noise gate: 15% от входного RMS (сохраняет детали речи)
-/// - Предотвращение клиппинга: если peak > 0.95, снижаем gain
-/// - Используется F32 формат без потерь (WAV)
-/// 
-/// Параметры:
-/// * `input_data` - срез входных аудиосэмплов (f32, без потерь)
-/// * `buffer` - Arc на RwLock буфер для записи обработанных данных
-/// * `state` - Arc на Mutex состояния AudioCapture с параметрами обработки
-pub fn process_audio(
-    input_data: &[f32],
-    buffer: &Arc<RwLock<Vec<f32>>>,
-    state: std::sync::Arc<Mutex<AudioCapture>>,
-) {
-    let rms_input = calculate_rms(input_data);
-    let peak_input = input_data.iter().map(|x| x.abs()).fold(0.0f32, f32::max);
-    
-    log::debug!(
-        "Processing audio: input RMS={:.6}, peak={:.6}",
-        rms_input, peak_input
-    );
-
-    // Автоматическая адаптивная подстройка параметров на основе RMS
-    let (mut gain, 
-        sample_rate, 
-        buffer_duration_seconds,
-         peak_threshold,
-          soft_gate_factor
-        ) = {
-        let capture = state.lock().unwrap();
-        (
-            capture.gain,
-            capture.sample_rate as usize,
-            capture.buffer_duration_seconds,
-            capture.peak_prevention_threshold,
-            capture.soft_noise_gate_factor,
-        )
-    };
+/// Размер блока (во фреймах) для потоковой обработки дубля
+pub const PROCESS_CHUNK_FRAMES: usize = 4096;
 
-    // Целевой RMS для речи: 0.12 
-    // (оптимален для Whisper, особенно для русского языка)
-    let target_rms = 0.12;
-    
-    // Автоматически подстраиваем gain на основе входного RMS
-    if rms_input > 0.001 {
-        let mut adaptive_gain = target_rms / rms_input;
-        
-        // Ограничиваем диапазон: от 0.5 до 10.0
-        adaptive_gain = adaptive_gain.clamp(0.5, 10.0);
-        
-        // ПРЕДОТВРАЩЕНИЕ КЛИППИНГА: если peak * gain > peak_threshold, снижаем gain
-        if peak_input * adaptive_gain > peak_threshold {
-            let max_gain_for_peak = (peak_threshold / peak_input).max(0.5);
-            adaptive_gain = adaptive_gain.min(max_gain_for_peak);
-            log::info!(
-                "CLIPPING PREVENTION: Reduced gain from target {:.2} to {:.2} (peak was {:.6})",
-                target_rms / rms_input, adaptive_gain, peak_input
-            );
-        }
-        
-        gain = adaptive_gain;
-        log::debug!(
-            "ADAPTIVE: Input RMS {:.6}, peak {:.6} → gain {:.2} (target RMS: {:.2})",
-            rms_input, peak_input, gain, target_rms
-        );
-    }
-    
-    // Noise threshold = 15% от входного RMS 
-    // (мягкий noise gate для сохранения деталей речи)
-    let noise_threshold = (rms_input * 0.15).min(0.01);
-    
-    let processed = process_and_filter(
-        input_data,
-        noise_threshold,
-        gain,
-        soft_gate_factor,
-    );
-
-    match buffer.write() {
-        Ok(mut buf) => {
-            buf.extend_from_slice(&processed);
-            manage_buffer(&mut buf, sample_rate, buffer_duration_seconds);
-        }
-        Err(e) => {
-            log::error!("Failed to acquire write lock on buffer: {}", e);
-        }
-    }
+/// Что обработка сделала с дублем (payload 'processing-finished').
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ProcessingReport {
+    /// Какая часть дубля осталась после обрезки тишины (None - не обрезан)
+    pub trim: Option<TrimOffsets>,
+    /// Громкость и истинный пик дубля как записан, усиление стадии нормализации
+    /// (заполняет worker; `process_take` отдаёт измерение самой стадии)
+    pub loudness: Option<LoudnessReport>,
+    /// Качество дубля как записан (заполняет worker)
+    pub quality: Option<QualityReport>,
 }
 
-/// Применяет мягкий noise gate и gain к каждому сэмплу.
+/// Обрабатывает дубль цепочкой стадий выбранного профиля обработки.
 /// 
-/// Реализует лучшие практики:
-/// - Сэмплы с амплитудой меньше noise_threshold не полностью обнуляются,
-///   а снижаются с множителем soft_gate_factor (обычно 0.2) для сохранения деталей
-/// - Остальные сэмплы умножаются на gain и ограничиваются диапазоном [-1.0, 1.0]
-/// - Без многократного перекодирования (F32 - формат без потерь)
+/// Реализует лучшие практики для распознавания речи (Whisper). Цепочка по умолчанию:
+/// - Удаление постоянной составляющей и фильтр высоких частот 80 Гц: смещение
+///   и гул дешёвых микрофонов не завышают RMS для порога шума и АРУ
+/// - Мягкий noise gate: 15% от входного RMS (сохраняет детали речи)
+///   или спектральное вычитание шума в профиле для шумных помещений
+/// - Обрезка тишины до и после речи (Whisper не тратит время на паузы
+///   и не галлюцинирует на них)
+/// - Потоковая АРУ: усиление следует за громкостью с attack/release и выравнивает
+///   тихие и громкие части дубля
+/// - Нормализация громкости (ITU-R BS.1770) к -18 LUFS: воспринимаемая громкость
+///   речи, паузы не учитываются (оптимально для речи, особенно русского языка).
+///   Стадия измеряет сигнал после АРУ и доводит его до цели одним усилением
+/// - Limiter: предотвращение клиппинга, пики не выше 0.95
+/// - Используется F32 формат без потерь (WAV)
 /// 
-/// Параметры:
-/// * `input_data` - входные сэмплы
-/// * `noise_threshold` - порог шума (абсолютное значение)
-/// * `gain` - коэффициент усиления
-/// * `soft_gate_factor` - множитель для тихих сэмплов (обычно 0.2 = 20%)
-fn process_and_filter(
-    input_data: &[f32],
-    noise_threshold: f32,
-    gain: f32,
-    soft_gate_factor: f32,
-) -> Vec<f32> {
-    let rms_input = calculate_rms(input_data);
-    let peak_input = input_data.iter().map(|x| x.abs()).fold(0.0f32, f32::max);
-    
-    log::debug!("Input: RMS={:.6}, Peak={:.6}, Threshold={:.6}", rms_input, peak_input, noise_threshold);
-
-    let processed: Vec<f32> = input_data
-        .iter()
-        .map(|&sample| {
-            // Мягкий noise gate: применяем мягкий множитель для очень тихих сэмплов
-            // Это сохраняет детали речи, включая тихие согласные и фрикативы
-            if sample.abs() < noise_threshold {
-                sample * soft_gate_factor  // Оставляем % (обычно 20%) от тихих сэмплов
-            } else {
-                sample
-            }
-        })
-        .map(|sample| {
-            // Применяем усиление и ограничиваем диапазон (предотвращение клиппинга)
-            let amplified = sample * gain;
-            amplified.clamp(-1.0, 1.0)
-        })
-        .collect();
-
-    let rms_output = calculate_rms(&processed);
-    let peak_output = processed.iter().map(|x| x.abs()).fold(0.0f32, f32::max);
-    
-    log::debug!(
-        "Output: RMS={:.6}, Peak={:.6} (gain={:.2}, threshold={:.6})",
-        rms_output, peak_output, gain, noise_threshold
-    );
-    
-    processed
-}
-
-
-/// Тримит буфер до максимального размера и логирует RMS каждые 1024 сэмплов.
+/// Цепочка настраивается в профиле (`AudioSettings::processing_profiles`, см. `StageConfig`).
+/// 
+/// Дубль читается блоками по PROCESS_CHUNK_FRAMES, обработанные блоки отдаются в `sink`.
+/// Поэтому длинные записи, сброшенные на диск, целиком в память не загружаются.
+/// Стадии, которым нужен весь дубль (порог noise gate, границы речи), получают его
+/// предварительным проходом (см. `Pipeline::prepare`).
 /// 
-/// Если буфер превышает max_samples (sample_rate * buffer_duration_seconds),
-/// удаляет старейшие данные через drain.
+/// Возвращает `ProcessingReport`: по `trim` время в обработанном звуке
+/// переводится во время исходного дубля, `loudness` - измерение и усиление
+/// стадии нормализации (None - стадии нет).
 /// 
 /// Параметры:
-/// * `buffer` - мутабельная ссылка на буфер сэмплов
-/// * `sample_rate` - частота дискретизации (Гц)
-/// * `buffer_duration_seconds` - максимальная длительность буфера (сек)
-fn manage_buffer(buffer: &mut Vec<f32>, sample_rate: usize, buffer_duration_seconds: usize) {
-    let max_samples = sample_rate * buffer_duration_seconds;
+/// * `take` - записанный дубль (в памяти или на диске)
+/// * `state` - Arc на Mutex состояния AudioCapture с профилем обработки
+/// * `sink` - получатель обработанных блоков
+pub fn process_take<F>(
+    take: &Take,
+    state: &Arc<Mutex<AudioCapture>>,
+    mut sink: F,
+) -> Result<ProcessingReport, String>
+where
+    F: FnMut(&[f32]) -> Result<(), String>,
+{
+    let (name, profile) = {
+        let capture = state.lock().map_err(|_| "Failed to lock audio state".to_string())?;
+        (capture.settings.processing_profile.clone(), capture.settings.active_processing())
+    };
+    log::debug!("Processing take with profile {:?}: {:?}", name, profile.stages);
 
-    if buffer.len() > max_samples {
-        buffer.drain(0..buffer.len() - max_samples);
+    let mut pipeline = Pipeline::build(&profile.stages, take.sample_rate);
+    if pipeline.needs_analysis() {
+        pipeline.prepare(|f| take.for_each_chunk(PROCESS_CHUNK_FRAMES, f))?;
     }
 
-    let should_log = buffer.len() % 4096 == 0 && buffer.len() > 0;
-    if should_log {
-        let rms = calculate_rms(buffer);
-        log::debug!("Buffer RMS: {:.6}, size: {} samples", rms, buffer.len());
+    let mut processed = Vec::with_capacity(PROCESS_CHUNK_FRAMES);
+    take.for_each_chunk(PROCESS_CHUNK_FRAMES, |chunk| {
+        processed.clear();
+        pipeline.process(chunk, &mut processed);
+        sink(&processed)
+    })?;
+
+    processed.clear();
+    pipeline.flush(&mut processed);
+    sink(&processed)?;
+
+    let trim = pipeline.kept_range().map(|range| TrimOffsets::from_samples(range, take.sample_rate));
+    if let Some(trim) = trim {
+        log::info!("Trimmed silence: kept {} - {} ms of the take", trim.start_ms, trim.end_ms);
     }
+    Ok(ProcessingReport { trim, loudness: pipeline.loudness(), quality: None })
 }
-
-/// Вычисляет RMS (Root Mean Square) для массива сэмплов.
-/// 
-/// Возвращает 0.0 для пустого массива.
-/// 
-/// Параметры:
-/// * `data` - срез аудиосэмплов
-fn calculate_rms(data: &[f32]) -> f32 {
-    if data.is_empty() { return 0.0; }
-    let sum_squares: f32 = data.iter().map(|&x| x * x).sum();
-    (sum_squares / data.len() as f32).sqrt()
-}
\ No newline at end of file
@@ -0,0 +1,272 @@
+use serde::Serialize;
+
+use crate::audio::level::{to_dbfs, CLIP_THRESHOLD, MIN_DBFS};
+
+/// Длительность кадра анализа
+const FRAME_MS: u32 = 20;
+/// Доля самых тихих кадров, по которой оценивается уровень шума
+const NOISE_PERCENTILE: f32 = 0.1;
+/// Насколько кадр речи громче уровня шума, dB
+const SPEECH_MARGIN_DB: f32 = 10.0;
+/// Кадры тише этого уровня никогда не считаются речью
+const MIN_SPEECH_DBFS: f32 = -55.0;
+
+/// Клиппинг дольше этой доли дубля (%) - предупреждение
+const CLIPPING_WARNING_PERCENT: f32 = 0.5;
+/// Пик ниже этого уровня (dBFS) - вход слишком тихий
+const QUIET_PEAK_DBFS: f32 = -20.0;
+/// Отношение сигнал/шум ниже этого (dB) - шумно
+const LOW_SNR_DB: f32 = 15.0;
+/// Уровень шума выше этого (dBFS) - шумный микрофон или помещение
+const HIGH_NOISE_FLOOR_DBFS: f32 = -45.0;
+/// Речи меньше этой доли дубля - микрофон, скорее всего, не тот
+const LOW_SPEECH_RATIO: f32 = 0.1;
+/// Дубль короче этого (секунды) распознаётся плохо
+const SHORT_TAKE_SECONDS: f32 = 0.5;
+
+/// Отчёт о качестве записанного дубля (часть `ProcessingReport`).
+/// 
+/// Считается по дублю как записан (до обработки): показывает, что пришло
+/// с микрофона, когда распознавание получилось плохим.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct QualityReport {
+    /// Длительность дубля, секунды
+    pub duration_seconds: f32,
+    /// Пиковая амплитуда, dBFS
+    pub peak_dbfs: f32,
+    /// RMS всего дубля, dBFS
+    pub rms_dbfs: f32,
+    /// Доля сэмплов на границе диапазона, %
+    pub clipping_percent: f32,
+    /// Оценка отношения сигнал/шум: уровень речи над уровнем шума, dB
+    /// (None - речи не нашлось)
+    pub snr_db: Option<f32>,
+    /// Уровень шума: RMS самых тихих кадров, dBFS
+    pub noise_floor_dbfs: f32,
+    /// Доля кадров с речью (0.0..1.0)
+    pub speech_ratio: f32,
+    /// Предупреждения для пользователя ("input clipped 4% of the time")
+    pub warnings: Vec<String>,
+}
+
+/// Анализатор качества дубля.
+/// 
+/// Получает дубль блоками и копит пик, RMS, клиппинг и уровни кадров по 20 мс.
+/// Уровень шума - 10-й процентиль уровней кадров, речь - кадры громче шума
+/// на 10 dB (и не тише -55 dBFS).
+pub struct QualityAnalyzer {
+    sample_rate: u32,
+    frame_len: usize,
+    samples: u64,
+    sum_squares: f64,
+    peak: f32,
+    clipped: u64,
+    /// Уровни завершённых кадров, dBFS
+    frames: Vec<f32>,
+    frame_sum: f64,
+    frame_count: usize,
+}
+
+impl QualityAnalyzer {
+    /// Параметры:
+    /// * `sample_rate` - частота дискретизации моно сигнала
+    pub fn new(sample_rate: u32) -> Self {
+        Self {
+            sample_rate,
+            frame_len: (sample_rate as usize * FRAME_MS as usize / 1000).max(1),
+            samples: 0,
+            sum_squares: 0.0,
+            peak: 0.0,
+            clipped: 0,
+            frames: Vec::new(),
+            frame_sum: 0.0,
+            frame_count: 0,
+        }
+    }
+
+    /// Добавляет блок дубля.
+    pub fn add(&mut self, input: &[f32]) {
+        for &sample in input {
+            let square = (sample * sample) as f64;
+            self.sum_squares += square;
+            self.peak = self.peak.max(sample.abs());
+            if sample.abs() >= CLIP_THRESHOLD {
+                self.clipped += 1;
+            }
+
+            self.frame_sum += square;
+            self.frame_count += 1;
+            if self.frame_count == self.frame_len {
+                self.finish_frame();
+            }
+        }
+        self.samples += input.len() as u64;
+    }
+
+    fn finish_frame(&mut self) {
+        let rms = (self.frame_sum / self.frame_count as f64).sqrt() as f32;
+        self.frames.push(to_dbfs(rms));
+        self.frame_sum = 0.0;
+        self.frame_count = 0;
+    }
+
+    /// Считает отчёт по всему дублю.
+    pub fn finish(mut self) -> QualityReport {
+        if self.frame_count > 0 {
+            self.finish_frame();
+        }
+
+        let duration_seconds = self.samples as f32 / self.sample_rate.max(1) as f32;
+        let rms = if self.samples == 0 { 0.0 } else { (self.sum_squares / self.samples as f64).sqrt() as f32 };
+        let clipping_percent = if self.samples == 0 { 0.0 } else { self.clipped as f32 * 100.0 / self.samples as f32 };
+
+        let mut sorted = self.frames.clone();
+        sorted.sort_by(f32::total_cmp);
+        let noise_floor_dbfs = sorted
+            .get((sorted.len() as f32 * NOISE_PERCENTILE) as usize)
+            .copied()
+            .unwrap_or(MIN_DBFS);
+
+        let speech_threshold = (noise_floor_dbfs + SPEECH_MARGIN_DB).max(MIN_SPEECH_DBFS);
+        let speech: Vec<f32> = self.frames.iter().copied().filter(|&level| level >= speech_threshold).collect();
+        let speech_ratio = if self.frames.is_empty() { 0.0 } else { speech.len() as f32 / self.frames.len() as f32 };
+        let snr_db = (!speech.is_empty()).then(|| {
+            let power = speech.iter().map(|&level| 10f64.powf(level as f64 / 10.0)).sum::<f64>() / speech.len() as f64;
+            10.0 * power.log10() as f32 - noise_floor_dbfs
+        });
+
+        let mut report = QualityReport {
+            duration_seconds,
+            peak_dbfs: to_dbfs(self.peak),
+            rms_dbfs: to_dbfs(rms),
+            clipping_percent,
+            snr_db,
+            noise_floor_dbfs,
+            speech_ratio,
+            warnings: Vec::new(),
+        };
+        report.warnings = warnings(&report);
+        report
+    }
+}
+
+/// Предупреждения по порогам качества.
+fn warnings(report: &QualityReport) -> Vec<String> {
+    let mut warnings = Vec::new();
+    if report.duration_seconds < SHORT_TAKE_SECONDS {
+        warnings.push(format!("take is very short ({:.1} s)", report.duration_seconds));
+    }
+    if report.clipping_percent > CLIPPING_WARNING_PERCENT {
+        warnings.push(format!("input clipped {} of the time", percent(report.clipping_percent)));
+    }
+    if report.peak_dbfs > MIN_DBFS && report.peak_dbfs < QUIET_PEAK_DBFS {
+        warnings.push(format!("input is too quiet: peak at {:.0} dBFS", report.peak_dbfs));
+    }
+    if report.noise_floor_dbfs > HIGH_NOISE_FLOOR_DBFS {
+        warnings.push(format!("noise floor is high ({:.0} dBFS)", report.noise_floor_dbfs));
+    }
+    if let Some(snr) = report.snr_db.filter(|&snr| snr < LOW_SNR_DB) {
+        warnings.push(format!("background noise is loud: estimated SNR {:.0} dB", snr));
+    }
+    if report.speech_ratio < LOW_SPEECH_RATIO {
+        warnings.push(format!("almost no speech detected ({} of the take)", percent(report.speech_ratio * 100.0)));
+    }
+    warnings
+}
+
+/// Проценты без лишних знаков: "4%", но "0.6%".
+fn percent(value: f32) -> String {
+    if value >= 1.0 {
+        format!("{:.0}%", value)
+    } else {
+        format!("{:.1}%", value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RATE: u32 = 16000;
+
+    fn tone(seconds: f32, amplitude: f32) -> Vec<f32> {
+        (0..(RATE as f32 * seconds) as usize)
+            .map(|n| amplitude * (2.0 * std::f32::consts::PI * 300.0 * n as f32 / RATE as f32).sin())
+            .collect()
+    }
+
+    /// Детерминированный шум с равномерным распределением в [-amplitude, amplitude].
+    fn noise(seconds: f32, amplitude: f32) -> Vec<f32> {
+        let mut state = 12345u32;
+        (0..(RATE as f32 * seconds) as usize)
+            .map(|_| {
+                state = state.wrapping_mul(1103515245).wrapping_add(12345);
+                amplitude * ((state >> 8) as f32 / (1u32 << 24) as f32 * 2.0 - 1.0)
+            })
+            .collect()
+    }
+
+    fn analyze(input: &[f32]) -> QualityReport {
+        let mut analyzer = QualityAnalyzer::new(RATE);
+        for chunk in input.chunks(1000) {
+            analyzer.add(chunk);
+        }
+        analyzer.finish()
+    }
+
+    fn mix(a: &[f32], b: &[f32]) -> Vec<f32> {
+        a.iter().zip(b).map(|(x, y)| x + y).collect()
+    }
+
+    #[test]
+    fn test_clean_take_has_no_warnings() {
+        // Фон -70 dBFS, 3 с речи между паузами по 1 с
+        let mut speech = vec![0.0; RATE as usize];
+        speech.extend(tone(3.0, 0.3));
+        speech.extend(vec![0.0; RATE as usize]);
+        let input = mix(&speech, &noise(5.0, 0.0005));
+
+        let report = analyze(&input);
+        assert!((report.duration_seconds - 5.0).abs() < 1e-3);
+        assert!((report.peak_dbfs + 10.5).abs() < 0.1, "peak {}", report.peak_dbfs);
+        assert!((report.noise_floor_dbfs + 70.8).abs() < 1.0, "noise floor {}", report.noise_floor_dbfs);
+        assert!((report.speech_ratio - 0.6).abs() < 0.01, "speech ratio {}", report.speech_ratio);
+        // Речь -13.5 dBFS RMS над шумом -70.8 dBFS
+        let snr = report.snr_db.unwrap();
+        assert!((snr - 57.3).abs() < 1.0, "snr {}", snr);
+        assert_eq!(report.clipping_percent, 0.0);
+        assert!(report.warnings.is_empty(), "{:?}", report.warnings);
+    }
+
+    #[test]
+    fn test_clipped_take() {
+        // 1 с паузы, затем 3 с речи, которая упирается в полную шкалу
+        let mut input = noise(1.0, 0.0005);
+        input.extend(tone(3.0, 1.2).iter().map(|s| s.clamp(-1.0, 1.0)));
+        let report = analyze(&input);
+
+        // Синус с амплитудой 1.2 выше 0.999 около 36% периода, речь - 3/4 дубля
+        assert!((report.clipping_percent - 27.3).abs() < 1.0, "clipping {}", report.clipping_percent);
+        let expected = format!("input clipped {:.0}% of the time", report.clipping_percent);
+        assert_eq!(report.warnings, vec![expected]);
+    }
+
+    #[test]
+    fn test_quiet_and_noisy_takes() {
+        let mut speech = vec![0.0; RATE as usize];
+        speech.extend(tone(2.0, 0.06));
+        let report = analyze(&mix(&speech, &noise(3.0, 0.02)));
+        assert!(report.snr_db.unwrap() < LOW_SNR_DB, "snr {:?}", report.snr_db);
+        assert!(report.warnings.iter().any(|w| w.starts_with("background noise is loud")), "{:?}", report.warnings);
+        assert!(report.warnings.iter().any(|w| w.starts_with("noise floor is high")), "{:?}", report.warnings);
+        assert!(report.warnings.iter().any(|w| w.starts_with("input is too quiet")), "{:?}", report.warnings);
+
+        let report = analyze(&vec![0.0; RATE as usize / 5]);
+        assert_eq!(report.snr_db, None);
+        assert_eq!(report.peak_dbfs, MIN_DBFS);
+        assert_eq!(
+            report.warnings,
+            vec!["take is very short (0.2 s)".to_string(), "almost no speech detected (0.0% of the take)".to_string()]
+        );
+    }
+}
@@ -0,0 +1,338 @@
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::PathBuf;
+
+/// Записанный дубль: сэмплы в памяти или во временном WAV файле.
+/// 
+/// Короткие записи остаются в памяти, длинные сбрасываются на диск
+/// (см. `RecordingWriter`). Потребители читают дубль блоками через
+/// `for_each_chunk` и не зависят от того, где лежат данные.
+/// Временный файл удаляется вместе с дублем.
+pub struct Take {
+    pub sample_rate: u32,
+    pub channels: u16,
+    storage: TakeStorage,
+}
+
+enum TakeStorage {
+    Memory(Vec<f32>),
+    Spilled { path: PathBuf, len: usize },
+}
+
+impl Take {
+    /// Создаёт дубль из сэмплов в памяти.
+    #[cfg(test)]
+    pub fn from_samples(samples: Vec<f32>, sample_rate: u32, channels: u16) -> Self {
+        Self {
+            sample_rate,
+            channels,
+            storage: TakeStorage::Memory(samples),
+        }
+    }
+
+    /// Количество сэмплов (всех каналов).
+    pub fn len(&self) -> usize {
+        match &self.storage {
+            TakeStorage::Memory(samples) => samples.len(),
+            TakeStorage::Spilled { len, .. } => *len,
+        }
+    }
+
+    /// Пуст ли дубль (ни одного сэмпла).
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Длительность дубля в секундах.
+    pub fn duration_seconds(&self) -> f32 {
+        let per_second = self.sample_rate as usize * self.channels.max(1) as usize;
+        if per_second == 0 {
+            return 0.0;
+        }
+        self.len() as f32 / per_second as f32
+    }
+
+    /// Был ли дубль сброшен на диск.
+    pub fn is_spilled(&self) -> bool {
+        matches!(self.storage, TakeStorage::Spilled { .. })
+    }
+
+    /// Последовательно передаёт сэмплы дубля в `f` блоками по `frames` фреймов.
+    /// 
+    /// Для сброшенного на диск дубля файл читается потоково, целиком
+    /// в память он не загружается.
+    /// 
+    /// Параметры:
+    /// * `frames` - размер блока во фреймах (сэмплах на канал)
+    /// * `f` - обработчик блока, ошибка прерывает чтение
+    pub fn for_each_chunk<F>(&self, frames: usize, mut f: F) -> Result<(), String>
+    where
+        F: FnMut(&[f32]) -> Result<(), String>,
+    {
+        let chunk_len = frames.max(1) * self.channels.max(1) as usize;
+
+        match &self.storage {
+            TakeStorage::Memory(samples) => {
+                for chunk in samples.chunks(chunk_len) {
+                    f(chunk)?;
+                }
+            }
+            TakeStorage::Spilled { path, .. } => {
+                let mut reader = hound::WavReader::open(path)
+                    .map_err(|e| format!("Failed to open spilled take {:?}: {}", path, e))?;
+                let mut chunk = Vec::with_capacity(chunk_len);
+                for sample in reader.samples::<f32>() {
+                    chunk.push(sample.map_err(|e| format!("Spilled take read error: {}", e))?);
+                    if chunk.len() == chunk_len {
+                        f(&chunk)?;
+                        chunk.clear();
+                    }
+                }
+                if !chunk.is_empty() {
+                    f(&chunk)?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Drop for Take {
+    fn drop(&mut self) {
+        if let TakeStorage::Spilled { path, .. } = &self.storage {
+            if let Err(e) = std::fs::remove_file(path) {
+                log::warn!("Failed to remove spilled take {:?}: {}", path, e);
+            }
+        }
+    }
+}
+
+/// Дорожка многодорожечного дубля: запись одного источника.
+pub struct Track {
+    /// Кто говорит на дорожке ("Me", "Them"); None - обычный дубль с одним источником
+    pub label: Option<String>,
+    pub take: Take,
+}
+
+/// Задание для worker: дорожки одного дубля, выровненные по времени начала.
+/// 
+/// Обычный дубль - одна дорожка без метки. Первая дорожка - основной
+/// источник (микрофон), по ней считаются длительность и авто-стоп.
+pub struct TakeJob {
+    pub tracks: Vec<Track>,
+}
+
+impl TakeJob {
+    /// Запись основного источника.
+    pub fn primary(&self) -> Option<&Take> {
+        self.tracks.first().map(|track| &track.take)
+    }
+
+    /// Пуст ли дубль (основной источник ничего не записал).
+    pub fn is_empty(&self) -> bool {
+        self.primary().is_none_or(Take::is_empty)
+    }
+
+    /// Длительность основной дорожки в секундах.
+    pub fn duration_seconds(&self) -> f32 {
+        self.primary().map_or(0.0, Take::duration_seconds)
+    }
+
+    /// Несколько источников: дорожки распознаются отдельно и сводятся в диалог.
+    pub fn is_multitrack(&self) -> bool {
+        self.tracks.iter().any(|track| track.label.is_some())
+    }
+}
+
+impl From<Take> for TakeJob {
+    fn from(take: Take) -> Self {
+        Self { tracks: vec![Track { label: None, take }] }
+    }
+}
+
+/// Накопитель записи со сбросом на диск.
+/// 
+/// Пока запись короче порога, сэмплы копятся в памяти. После превышения порога
+/// накопленное и все последующие блоки пишутся во временный WAV (F32) файл,
+/// и память больше не растёт. Если файл создать не удалось, запись продолжается в памяти.
+pub struct RecordingWriter {
+    sample_rate: u32,
+    channels: u16,
+    memory: Vec<f32>,
+    spill: Option<SpillFile>,
+    spill_path: Option<PathBuf>,
+    spill_threshold: usize,
+    len: usize,
+    write_failed: bool,
+}
+
+struct SpillFile {
+    path: PathBuf,
+    writer: hound::WavWriter<BufWriter<File>>,
+}
+
+impl RecordingWriter {
+    /// Параметры:
+    /// * `sample_rate` - частота дискретизации записи
+    /// * `channels` - количество каналов
+    /// * `spill_threshold` - после скольких сэмплов запись уходит на диск
+    /// * `spill_path` - путь к временному файлу (None = всегда в памяти)
+    pub fn new(sample_rate: u32, channels: u16, spill_threshold: usize, spill_path: Option<PathBuf>) -> Self {
+        Self {
+            sample_rate,
+            channels,
+            memory: Vec::with_capacity(spill_threshold.min(sample_rate as usize * channels as usize)),
+            spill: None,
+            spill_path,
+            spill_threshold,
+            len: 0,
+            write_failed: false,
+        }
+    }
+
+    /// Количество записанных сэмплов (всех каналов).
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Записано ли хоть что-то.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Добавляет блок сэмплов в запись.
+    /// 
+    /// Если запись во временный файл сломалась (например, закончилось место),
+    /// дальнейшие сэмплы отбрасываются, а уже записанное сохраняется.
+    pub fn push(&mut self, samples: &[f32]) {
+        if samples.is_empty() || self.write_failed {
+            return;
+        }
+
+        if let Some(spill) = self.spill.as_mut() {
+            if let Err(e) = write_samples(&mut spill.writer, samples) {
+                log::error!("Failed to write spilled take, truncating at {} samples: {}", self.len, e);
+                self.write_failed = true;
+                return;
+            }
+            self.len += samples.len();
+            return;
+        }
+
+        self.memory.extend_from_slice(samples);
+        self.len += samples.len();
+        if self.memory.len() > self.spill_threshold {
+            self.start_spill();
+        }
+    }
+
+    /// Завершает запись и возвращает дубль.
+    pub fn finish(mut self) -> Result<Take, String> {
+        let storage = match self.spill.take() {
+            Some(spill) => {
+                // При ошибке записи в заголовке WAV может остаться неверная длина
+                if let Err(e) = spill.writer.finalize() {
+                    let _ = std::fs::remove_file(&spill.path);
+                    return Err(format!("Failed to finalize spilled take: {}", e));
+                }
+                log::info!("Take spilled to disk: {:?} ({} samples)", spill.path, self.len);
+                TakeStorage::Spilled { path: spill.path, len: self.len }
+            }
+            None => TakeStorage::Memory(std::mem::take(&mut self.memory)),
+        };
+
+        Ok(Take {
+            sample_rate: self.sample_rate,
+            channels: self.channels,
+            storage,
+        })
+    }
+
+    /// Переносит накопленные в памяти сэмплы во временный файл.
+    fn start_spill(&mut self) {
+        let Some(path) = self.spill_path.take() else {
+            return;
+        };
+
+        let spec = hound::WavSpec {
+            channels: self.channels,
+            sample_rate: self.sample_rate,
+            bits_per_sample: 32,
+            sample_format: hound::SampleFormat::Float,
+        };
+
+        let result = hound::WavWriter::create(&path, spec)
+            .map_err(|e| e.to_string())
+            .and_then(|mut writer| write_samples(&mut writer, &self.memory).map(|_| writer));
+
+        match result {
+            Ok(writer) => {
+                log::info!("Recording exceeded {} samples, spilling to {:?}", self.spill_threshold, path);
+                self.memory = Vec::new();
+                self.spill = Some(SpillFile { path, writer });
+            }
+            Err(e) => {
+                log::error!("Failed to spill recording to {:?}, keeping it in memory: {}", path, e);
+                let _ = std::fs::remove_file(&path);
+            }
+        }
+    }
+}
+
+fn write_samples(writer: &mut hound::WavWriter<BufWriter<File>>, samples: &[f32]) -> Result<(), String> {
+    for &s in samples {
+        writer.write_sample(s).map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn collect(take: &Take, frames: usize) -> Vec<f32> {
+        let mut out = Vec::new();
+        take.for_each_chunk(frames, |chunk| {
+            out.extend_from_slice(chunk);
+            Ok(())
+        }).unwrap();
+        out
+    }
+
+    #[test]
+    fn test_short_take_stays_in_memory() {
+        let mut writer = RecordingWriter::new(16000, 1, 100, None);
+        writer.push(&[0.1, 0.2, 0.3]);
+        let take = writer.finish().unwrap();
+        assert!(!take.is_spilled());
+        assert_eq!(collect(&take, 2), vec![0.1, 0.2, 0.3]);
+    }
+
+    #[test]
+    fn test_long_take_spills_to_disk() {
+        let path = std::env::temp_dir().join(format!("spill_test_{}.wav", std::process::id()));
+        let input: Vec<f32> = (0..1000).map(|i| (i as f32 / 1000.0) - 0.5).collect();
+
+        let mut writer = RecordingWriter::new(16000, 2, 300, Some(path.clone()));
+        for block in input.chunks(64) {
+            writer.push(block);
+        }
+        let take = writer.finish().unwrap();
+
+        assert!(take.is_spilled());
+        assert_eq!(take.len(), input.len());
+        assert_eq!(collect(&take, 50), input);
+
+        drop(take);
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn test_job_is_empty_when_primary_track_is_empty() {
+        let local = Track { label: Some("Me".to_string()), take: Take::from_samples(Vec::new(), 16000, 1) };
+        let remote = Track { label: Some("Them".to_string()), take: Take::from_samples(vec![0.1; 10], 16000, 1) };
+        let job = TakeJob { tracks: vec![local, remote] };
+        assert!(job.is_empty());
+        assert!(!TakeJob::from(Take::from_samples(vec![0.1; 10], 16000, 1)).is_empty());
+    }
+}
@@ -0,0 +1,299 @@
+use std::f64::consts::PI;
+
+/// Частота, которую ожидает распознавание (Whisper)
+pub const TARGET_SAMPLE_RATE: u32 = 16000;
+/// Пересечений нуля sinc с каждой стороны от центра фильтра (на частоте среза)
+const ZERO_CROSSINGS: usize = 32;
+/// Частота среза относительно меньшей из частот Найквиста: запас на переходную полосу,
+/// чтобы к новой частоте Найквиста фильтр уже подавлял сигнал
+const ROLLOFF: f64 = 0.92;
+/// Параметр окна Кайзера: ~85 dB подавления в полосе задерживания
+const KAISER_BETA: f64 = 8.6;
+/// Больше фаз не хранится: при редких частотах (огромный числитель отношения)
+/// положение отсчёта округляется до 1/MAX_PHASES сэмпла
+const MAX_PHASES: usize = 1024;
+
+/// Потоковый ресэмплер с ограничением полосы (polyphase windowed-sinc).
+/// 
+/// Отношение частот сокращается до L/M (48000 -> 16000 = 1/3, 44100 -> 16000 = 160/441).
+/// Для каждой из L дробных позиций отсчёта заранее считается свой набор
+/// коэффициентов - sinc с окном Кайзера. Частота среза - `ROLLOFF` от меньшей
+/// из частот Найквиста, поэтому при понижении частоты всё выше новой частоты
+/// Найквиста подавляется до прореживания, а не заворачивается в полосу речи.
+/// 
+/// Работает с моно сигналом блоками любого размера: результат не зависит от
+/// того, как вход нарезан. Задержка фильтра компенсирована, выход выровнен по
+/// времени со входом и имеет длину `ceil(n * L / M)`.
+/// 
+/// При одинаковых частотах вход проходит без изменений (фильтр не строится).
+pub struct Resampler {
+    up: usize,
+    down: usize,
+    /// Коэффициенты фаз подряд: фаза p занимает `taps` значений с `p * taps`
+    coefficients: Vec<f32>,
+    phases: usize,
+    taps: usize,
+    /// Вход, который ещё понадобится фильтру (в начале - `taps / 2` нулей слева)
+    buffer: Vec<f32>,
+    /// Целая часть позиции следующего отсчёта относительно начала `buffer`
+    position: usize,
+    /// Дробная часть позиции в единицах 1/L
+    remainder: usize,
+    consumed: u64,
+    produced: u64,
+}
+
+impl Resampler {
+    /// Параметры:
+    /// * `src_rate` - частота входа
+    /// * `dst_rate` - частота выхода
+    pub fn new(src_rate: u32, dst_rate: u32) -> Self {
+        let divisor = gcd(src_rate.max(1) as usize, dst_rate.max(1) as usize);
+        let up = dst_rate.max(1) as usize / divisor;
+        let down = src_rate.max(1) as usize / divisor;
+        if up == down {
+            return Self {
+                up,
+                down,
+                coefficients: Vec::new(),
+                phases: 0,
+                taps: 0,
+                buffer: Vec::new(),
+                position: 0,
+                remainder: 0,
+                consumed: 0,
+                produced: 0,
+            };
+        }
+
+        // Срез в долях частоты Найквиста входа; при понижении частоты фильтр шире во времени
+        let cutoff = ROLLOFF * (up as f64 / down as f64).min(1.0);
+        let half = (ZERO_CROSSINGS as f64 / cutoff).ceil() as usize;
+        let taps = 2 * half;
+        let phases = up.min(MAX_PHASES);
+
+        let mut coefficients = Vec::with_capacity(phases * taps);
+        for phase in 0..phases {
+            let fraction = phase as f64 / phases as f64;
+            let start = coefficients.len();
+            // Тап k умножается на вход x[i - half + 1 + k], отсчёт стоит в i + fraction
+            coefficients.extend((0..taps).map(|k| {
+                let offset = fraction + half as f64 - 1.0 - k as f64;
+                (cutoff * sinc(cutoff * offset) * kaiser(offset / half as f64)) as f32
+            }));
+            // Каждая фаза пропускает постоянную составляющую без изменений
+            let sum: f32 = coefficients[start..].iter().sum();
+            if sum.abs() > f32::EPSILON {
+                coefficients[start..].iter_mut().for_each(|c| *c /= sum);
+            }
+        }
+
+        Self {
+            up,
+            down,
+            coefficients,
+            phases,
+            taps,
+            buffer: vec![0.0; half - 1],
+            position: 0,
+            remainder: 0,
+            consumed: 0,
+            produced: 0,
+        }
+    }
+
+    /// Ресэмплирует блок входа и дописывает готовые отсчёты в `output`.
+    pub fn process(&mut self, input: &[f32], output: &mut Vec<f32>) {
+        if self.is_passthrough() {
+            output.extend_from_slice(input);
+            return;
+        }
+        self.consumed += input.len() as u64;
+        self.buffer.extend_from_slice(input);
+        self.drain(output, u64::MAX);
+    }
+
+    /// Дописывает хвост: отсчёты, которым не хватало входа справа.
+    /// После `flush` ресэмплер можно использовать для нового сигнала.
+    pub fn flush(&mut self, output: &mut Vec<f32>) {
+        if self.is_passthrough() {
+            return;
+        }
+        let expected = (self.consumed * self.up as u64).div_ceil(self.down as u64);
+        self.buffer.extend(std::iter::repeat_n(0.0, self.taps));
+        self.drain(output, expected);
+
+        self.buffer.clear();
+        self.buffer.resize(self.taps / 2 - 1, 0.0);
+        self.position = 0;
+        self.remainder = 0;
+        self.consumed = 0;
+        self.produced = 0;
+    }
+
+    /// Частоты совпадают: вход копируется в выход как есть.
+    pub fn is_passthrough(&self) -> bool {
+        self.up == self.down
+    }
+
+    /// Считает отсчёты, для которых во входе уже есть все тапы (не больше `limit` всего).
+    fn drain(&mut self, output: &mut Vec<f32>, limit: u64) {
+        while self.position + self.taps <= self.buffer.len() && self.produced < limit {
+            let phase = self.remainder * self.phases / self.up;
+            let coefficients = &self.coefficients[phase * self.taps..(phase + 1) * self.taps];
+            let window = &self.buffer[self.position..self.position + self.taps];
+            output.push(window.iter().zip(coefficients).map(|(x, c)| x * c).sum());
+            self.produced += 1;
+
+            self.remainder += self.down;
+            self.position += self.remainder / self.up;
+            self.remainder %= self.up;
+        }
+
+        // Вход левее текущего отсчёта фильтру больше не нужен
+        let used = self.position.min(self.buffer.len());
+        self.buffer.drain(..used);
+        self.position -= used;
+    }
+}
+
+/// Ресэмплирует сигнал целиком.
+pub fn resample(input: &[f32], src_rate: u32, dst_rate: u32) -> Vec<f32> {
+    if src_rate == dst_rate || input.is_empty() {
+        return input.to_vec();
+    }
+    let mut resampler = Resampler::new(src_rate, dst_rate);
+    let mut output = Vec::with_capacity((input.len() as u64 * dst_rate as u64 / src_rate as u64) as usize + 1);
+    resampler.process(input, &mut output);
+    resampler.flush(&mut output);
+    output
+}
+
+fn gcd(a: usize, b: usize) -> usize {
+    if b == 0 { a } else { gcd(b, a % b) }
+}
+
+/// sin(pi x) / (pi x)
+fn sinc(x: f64) -> f64 {
+    if x.abs() < 1e-12 {
+        1.0
+    } else {
+        (PI * x).sin() / (PI * x)
+    }
+}
+
+/// Окно Кайзера на [-1, 1], ноль за его пределами.
+fn kaiser(x: f64) -> f64 {
+    if x.abs() > 1.0 {
+        return 0.0;
+    }
+    bessel_i0(KAISER_BETA * (1.0 - x * x).sqrt()) / bessel_i0(KAISER_BETA)
+}
+
+/// Модифицированная функция Бесселя первого рода нулевого порядка (ряд).
+fn bessel_i0(x: f64) -> f64 {
+    let mut sum = 1.0;
+    let mut term = 1.0;
+    let half = x / 2.0;
+    for k in 1..64 {
+        term *= half / k as f64;
+        let squared = term * term;
+        sum += squared;
+        if squared < sum * 1e-17 {
+            break;
+        }
+    }
+    sum
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Линейный sweep синуса от `from` до `to` Гц.
+    fn sweep(rate: u32, seconds: f64, from: f64, to: f64, amplitude: f32) -> Vec<f32> {
+        let len = (rate as f64 * seconds) as usize;
+        (0..len)
+            .map(|n| {
+                let t = n as f64 / rate as f64;
+                let phase = 2.0 * PI * (from * t + (to - from) * t * t / (2.0 * seconds));
+                amplitude * phase.sin() as f32
+            })
+            .collect()
+    }
+
+    /// RMS без краёв, где фильтр видит нули за пределами сигнала.
+    fn inner_rms(samples: &[f32], edge: usize) -> f32 {
+        let inner = &samples[edge..samples.len() - edge];
+        (inner.iter().map(|s| s * s).sum::<f32>() / inner.len() as f32).sqrt()
+    }
+
+    fn db(ratio: f32) -> f32 {
+        20.0 * ratio.log10()
+    }
+
+    #[test]
+    fn test_speech_band_sweep_passes_unchanged() {
+        for src_rate in [48000, 44100] {
+            let input = sweep(src_rate, 2.0, 50.0, 6500.0, 0.5);
+            let output = resample(&input, src_rate, TARGET_SAMPLE_RATE);
+
+            let gain = db(inner_rms(&output, 1600) / inner_rms(&input, 1600 * src_rate as usize / 16000));
+            assert!(gain.abs() < 0.05, "{} Hz: passband gain {} dB", src_rate, gain);
+        }
+    }
+
+    #[test]
+    fn test_sweep_above_nyquist_does_not_alias() {
+        for src_rate in [48000, 44100] {
+            // Весь sweep выше 8 кГц: при линейной интерполяции он заворачивается в полосу речи
+            let input = sweep(src_rate, 2.0, 8200.0, 20000.0, 0.5);
+            let output = resample(&input, src_rate, TARGET_SAMPLE_RATE);
+
+            let leak = db(inner_rms(&output, 1600) / inner_rms(&input, 0));
+            assert!(leak < -60.0, "{} Hz: aliasing at {} dB", src_rate, leak);
+        }
+    }
+
+    #[test]
+    fn test_output_is_aligned_with_input() {
+        let input = sweep(48000, 0.5, 300.0, 300.0, 0.5);
+        let output = resample(&input, 48000, TARGET_SAMPLE_RATE);
+        assert_eq!(output.len(), 8000);
+
+        // Отсчёт n выхода соответствует отсчёту 3n входа
+        for n in [1000, 4000, 7000] {
+            assert!((output[n] - input[3 * n]).abs() < 1e-3, "sample {}: {} vs {}", n, output[n], input[3 * n]);
+        }
+    }
+
+    #[test]
+    fn test_chunked_processing_matches_whole_signal() {
+        let input = sweep(44100, 0.3, 100.0, 4000.0, 0.5);
+        let whole = resample(&input, 44100, TARGET_SAMPLE_RATE);
+
+        let mut resampler = Resampler::new(44100, TARGET_SAMPLE_RATE);
+        let mut chunked = Vec::new();
+        for chunk in input.chunks(777) {
+            resampler.process(chunk, &mut chunked);
+        }
+        resampler.flush(&mut chunked);
+
+        assert_eq!(chunked.len(), (input.len() * 160).div_ceil(441));
+        assert_eq!(chunked, whole);
+    }
+
+    #[test]
+    fn test_equal_rates_pass_through() {
+        let input = sweep(TARGET_SAMPLE_RATE, 0.5, 100.0, 7000.0, 0.5);
+        let mut resampler = Resampler::new(TARGET_SAMPLE_RATE, TARGET_SAMPLE_RATE);
+        assert!(resampler.is_passthrough());
+
+        let mut output = Vec::new();
+        for chunk in input.chunks(1000) {
+            resampler.process(chunk, &mut output);
+        }
+        resampler.flush(&mut output);
+        assert_eq!(output, input);
+    }
+}
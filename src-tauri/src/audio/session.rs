@@ -0,0 +1,1197 @@
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::collections::VecDeque;
+use std::path::PathBuf;
+use std::sync::{mpsc, Arc, Mutex};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+use rtrb::Consumer;
+use serde::Serialize;
+use tauri::{AppHandle, Emitter};
+use tokio::sync::mpsc::Sender;
+
+use crate::audio::capture::{device_opener, start_audio_capture_with_stream, take_stream_error, CaptureError, CaptureStream, StreamErrorKind, StreamOpener};
+use crate::audio::downmix::Downmixer;
+use crate::audio::level::{LevelMeter, LevelReport};
+use crate::audio::devices::{default_input_device_name, find_input_device, DeviceConfig, StreamPreferences};
+use crate::audio::recording::{RecordingWriter, Take, TakeJob};
+use crate::audio::state::{finish_job, set_state, SessionState};
+use crate::audio::tracks::{CompanionTrack, LOCAL_TRACK_LABEL, REMOTE_TRACK_LABEL};
+use crate::audio::vad::{VadConfig, VadEvent, VoiceActivityDetector};
+use crate::types::AudioCapture;
+use crate::utils::cache::SharedAudioCache;
+
+/// Как часто поток сессии забирает сэмплы из кольцевого буфера
+const DRAIN_INTERVAL: Duration = Duration::from_millis(10);
+/// Минимальный интервал между событиями 'audio-level'
+const LEVEL_REPORT_INTERVAL: Duration = Duration::from_millis(50);
+/// Минимальный интервал между событиями 'audio-overrun'
+const OVERRUN_REPORT_INTERVAL: Duration = Duration::from_secs(1);
+/// Сколько раз stream переоткрывается на том же устройстве после сбоя backend
+const STREAM_REOPEN_ATTEMPTS: u32 = 3;
+/// Пауза перед каждой попыткой переоткрыть stream
+const STREAM_REOPEN_DELAY: Duration = Duration::from_millis(200);
+/// Pre-roll занимает не больше 1/N лимита длины записи (`max_record_seconds`)
+const PRE_ROLL_BUDGET_SHARE: usize = 2;
+
+/// Payload события 'audio-overrun'.
+#[derive(Debug, Clone, Serialize)]
+pub struct OverrunReport {
+    /// Всего сэмплов потеряно в текущей записи
+    pub dropped_samples: u64,
+}
+
+/// Payload события 'hands-free-recording'.
+#[derive(Debug, Clone, Serialize)]
+pub struct HandsFreeReport {
+    /// true - детектор речи начал дубль, false - дубль завершён по тишине
+    pub recording: bool,
+}
+
+/// Payload события 'recording-device-lost'.
+#[derive(Debug, Clone, Serialize)]
+pub struct DeviceLostReport {
+    /// Устройство, которое отключилось во время записи
+    pub lost_device: String,
+    /// Устройство, на которое переключилась запись (None = запись завершена)
+    pub switched_to: Option<String>,
+    /// Почему не удалось переключиться
+    pub error: Option<String>,
+}
+
+/// Payload события 'recording-error'.
+#[derive(Debug, Clone, Serialize)]
+pub struct RecordingErrorReport {
+    /// Вид ошибки stream
+    pub kind: StreamErrorKind,
+    /// Устройство, на котором произошла ошибка
+    pub device: String,
+    /// Метка дорожки звонка (None = основной источник)
+    pub track: Option<String>,
+    /// Текст ошибки
+    pub message: String,
+    /// Запись продолжается (stream переоткрыт, устройство заменено или ошибка не мешает записи)
+    pub recovered: bool,
+}
+
+/// Получатель событий потока сессии.
+/// 
+/// В приложении события уходят во frontend через AppHandle; без него
+/// (запись без окна, тесты) события не отправляются.
+#[derive(Clone, Default)]
+pub struct Events(Option<AppHandle>);
+
+impl Events {
+    fn app(&self) -> Option<&AppHandle> {
+        self.0.as_ref()
+    }
+
+    fn emit<S: Serialize + Clone>(&self, event: &str, payload: S) {
+        if let Some(app) = &self.0 {
+            if let Err(e) = app.emit(event, payload) {
+                log::error!("Failed to emit {} event: {}", event, e);
+            }
+        }
+    }
+}
+
+/// Сессия записи, которая владеет cpal stream.
+/// 
+/// cpal::Stream не является Send, поэтому stream создаётся и живёт в выделенном
+/// потоке. Этот же поток - потребитель кольцевого буфера: каждые ~10 мс он
+/// забирает сэмплы, накопленные audio callback, и собирает из них запись.
+/// 
+/// Длинные записи сбрасываются во временный файл в AudioCache
+/// (см. `RecordingWriter`), поэтому длительность ограничена только настройкой
+/// `max_record_seconds` (None = без ограничения).
+/// 
+/// Если устройство отключилось во время записи, stream закрывается, записанное
+/// сохраняется, и запись продолжается на устройстве по умолчанию
+/// (настройка `device_failover`). Если переключиться нельзя, запись завершается
+/// как при авто-стопе. В обоих случаях эмитится 'recording-device-lost'.
+/// 
+/// При сбое backend stream переоткрывается на том же устройстве
+/// (до `STREAM_REOPEN_ATTEMPTS` попыток), иначе запись тоже завершается.
+/// Каждая ошибка stream эмитит 'recording-error' с её видом и исходом.
+/// 
+/// С настройкой `pre_roll_enabled` stream открывается заранее (`start_standby`)
+/// и держит последние секунды звука, которые добавляются в начало дубля -
+/// так задержка открытия устройства не съедает первое слово.
+/// 
+/// В режиме hands-free (`AudioSettings::hands_free`) тот же stream слушает
+/// детектор речи (см. `VoiceActivityDetector`): дубль начинается с началом речи
+/// и заканчивается после `vad_trailing_silence_ms` тишины, после чего сразу
+/// уходит в очередь обработки, а stream возвращается в режим ожидания.
+/// 
+/// Дубль проходит состояния `SessionState` (Idle -> Arming -> Recording <-> Paused
+/// -> Finalizing -> Queued), каждый переход эмитит 'recording-state'. Дубль,
+/// завершённый без команды остановки (авто-стоп, конец источника, потеря
+/// устройства), поток сам отправляет в очередь обработки - `stop` его уже не ждёт.
+/// 
+/// Stream закрывается:
+/// - при явной остановке (`stop`)
+/// - при авто-стопе по длительности (поток завершается сам)
+/// - при отключении устройства без возможности переключения
+/// - при выключении pre-roll (`stop_standby`)
+/// - при выходе из приложения (`Drop` / RunEvent::Exit)
+pub struct RecordingSession {
+    cache: SharedAudioCache,
+    processing_sender: Sender<TakeJob>,
+    active: Option<ActiveStream>,
+}
+
+/// Чем закончился дубль в `run_consumer`.
+enum TakeEnd {
+    /// Остановка командой: дубль забирает `RecordingSession::stop`
+    Stopped,
+    /// Авто-стоп, конец источника или потеря устройства: поток сам ставит дубль в очередь
+    Finished,
+    /// Тишина в режиме hands-free: stream остаётся открытым для следующего дубля
+    Silence(CaptureStream),
+}
+
+/// Команды потоку, владеющему stream.
+enum Control {
+    /// Начать запись из режима pre-roll
+    Record,
+    /// Закрыть stream
+    Stop,
+}
+
+/// Поток, владеющий открытым stream. Возвращает дубль, остановленный командой
+/// (None, если stream был открыт только для pre-roll или дубль уже ушёл в очередь сам).
+struct ActiveStream {
+    control_tx: mpsc::Sender<Control>,
+    thread: JoinHandle<Result<Option<TakeJob>, String>>,
+    device_id: String,
+    device_name: String,
+    config: DeviceConfig,
+    /// Между дублями stream остаётся открытым в режиме pre-roll
+    pre_roll: bool,
+}
+
+impl RecordingSession {
+    /// Параметры:
+    /// * `cache` - кэш для временных файлов длинных записей
+    /// * `processing_sender` - очередь обработки для дублей, завершённых в режиме hands-free
+    pub fn new(cache: SharedAudioCache, processing_sender: Sender<TakeJob>) -> Self {
+        Self { cache, processing_sender, active: None }
+    }
+
+    /// Открывает stream на устройстве и начинает запись.
+    /// 
+    /// Если stream уже открыт в режиме pre-roll на том же устройстве и с подходящей
+    /// конфигурацией, запись начинается на нём, а накопленный pre-roll попадает
+    /// в начало дубля. Иначе stream от предыдущей записи или pre-roll
+    /// сначала освобождается. Если дубль уже идёт (начат детектором речи),
+    /// запись продолжается без изменений.
+    /// 
+    /// Параметры:
+    /// * `capture` - Arc на состояние AudioCapture с флагами записи
+    /// * `device_id` - ID аудиоустройства для захвата (`InputDevice::id`)
+    /// * `preferences` - желаемые параметры stream
+    /// * `app` - AppHandle для отправки событий во frontend
+    /// 
+    /// Возвращает конфигурацию, которая фактически выбрана для stream.
+    pub fn start(
+        &mut self,
+        capture: Arc<Mutex<AudioCapture>>,
+        device_id: String,
+        preferences: StreamPreferences,
+        app: AppHandle,
+    ) -> Result<DeviceConfig, String> {
+        let events = Events(Some(app));
+        let (state, call_track) = {
+            let cap = capture.lock().map_err(|_| "Failed to lock audio state".to_string())?;
+            // Запись звонка: второй источник пишется отдельной дорожкой
+            let call_track = cap.settings.call_track_device.clone().filter(|call_device| *call_device != device_id);
+            (cap.session_state, call_track)
+        };
+        if let Some(active) = self.active.as_ref().filter(|a| state.is_recording() && !a.thread.is_finished()) {
+            log::info!("Recording already in progress on {}", active.device_name);
+            return Ok(active.config.clone());
+        }
+        set_state(&capture, events.app(), AudioCapture::arm)?;
+
+        // Pre-roll есть только у основного источника, дорожки начинались бы не вместе.
+        // Поток pre-roll мог уже завершиться (например, устройство отключилось) - тогда stream открывается заново
+        if let Some(active) = self.active.as_ref().filter(|a| call_track.is_none() && a.can_record(&device_id, &preferences)) {
+            if active.control_tx.send(Control::Record).is_ok() {
+                set_state(&capture, events.app(), AudioCapture::begin_recording)?;
+                log::info!("Recording started from pre-roll on {}", active.device_name);
+                return Ok(active.config.clone());
+            }
+        }
+
+        if let Some(Ok(stale)) = self.stop() {
+            if !stale.is_empty() {
+                log::warn!("Discarding {:.1}s from previous unclaimed take", stale.duration_seconds());
+            }
+        }
+
+        // Дорожка открывается первой и начинает писать вместе с основным источником
+        let companions = match call_track {
+            Some(call_device) => match CompanionTrack::spawn(&capture, device_opener(call_device.clone(), StreamPreferences::default()), REMOTE_TRACK_LABEL, &self.cache, events.clone()) {
+                Ok(companion) => vec![companion],
+                Err(e) => {
+                    disarm(&capture, &events);
+                    return Err(format!("Failed to open call track {}: {}", call_device, e));
+                }
+            },
+            None => Vec::new(),
+        };
+
+        let open = device_opener(device_id.clone(), preferences);
+        match self.spawn_stream(capture.clone(), device_id, open, events.clone(), None, companions) {
+            Ok(active) => {
+                let config = active.config.clone();
+                self.active = Some(active);
+                Ok(config)
+            }
+            Err(e) => {
+                disarm(&capture, &events);
+                Err(e)
+            }
+        }
+    }
+
+    /// Открывает stream в режиме pre-roll, если он включён в настройках
+    /// (`pre_roll_enabled` или `hands_free`).
+    /// 
+    /// Микрофон остаётся открытым между записями, и последние
+    /// `AudioSettings::pre_roll_seconds` секунд держатся в памяти.
+    /// Ничего не делает, если stream уже открыт или дубль ещё не забран.
+    /// 
+    /// Параметры:
+    /// * `capture` - Arc на состояние AudioCapture с настройками
+    /// * `app` - AppHandle для отправки событий во frontend
+    pub fn start_standby(&mut self, capture: Arc<Mutex<AudioCapture>>, app: AppHandle) -> Result<(), String> {
+        if self.active.is_some() {
+            return Ok(());
+        }
+
+        let settings = capture.lock().map_err(|_| "Failed to lock audio state".to_string())?.settings.clone();
+        if !settings.pre_roll_enabled && !settings.hands_free {
+            return Ok(());
+        }
+        let device_id = settings
+            .input_device
+            .or_else(default_input_device_name)
+            .ok_or_else(|| "No input device available for pre-roll".to_string())?;
+
+        let open = device_opener(device_id.clone(), StreamPreferences::default());
+        let active = self.spawn_stream(
+            capture,
+            device_id,
+            open,
+            Events(Some(app)),
+            Some(settings.pre_roll_seconds),
+            Vec::new(),
+        )?;
+        log::info!("Pre-roll capture started on {} ({}s)", active.device_name, settings.pre_roll_seconds);
+        self.active = Some(active);
+        Ok(())
+    }
+
+    /// Закрывает stream pre-roll (выключение настройки или смена её параметров).
+    /// 
+    /// Идущую запись не трогает.
+    pub fn stop_standby(&mut self, capture: &Arc<Mutex<AudioCapture>>) {
+        let idle = capture
+            .lock()
+            .is_ok_and(|cap| matches!(cap.session_state, SessionState::Idle | SessionState::Queued));
+        if idle && self.active.as_ref().is_some_and(|a| a.pre_roll) {
+            self.stop();
+        }
+    }
+
+    /// Закрывает stream, дожидается освобождения устройства и возвращает запись.
+    /// 
+    /// Возвращает None, если запись не шла (stream не был открыт или был открыт
+    /// только для pre-roll) или дубль уже завершился сам (авто-стоп) - такой дубль
+    /// поток сессии отправил в очередь обработки без `stop`.
+    /// 
+    /// Возвращённый дубль находится в состоянии Finalizing: вызывающий переводит
+    /// сессию в Queued (дубль отправлен на обработку) или Idle.
+    pub fn stop(&mut self) -> Option<Result<TakeJob, String>> {
+        let active = self.active.take()?;
+
+        // Поток мог уже завершиться по авто-стопу - ошибка отправки не важна
+        let _ = active.control_tx.send(Control::Stop);
+        active
+            .thread
+            .join()
+            .unwrap_or_else(|_| Err(format!("Audio stream thread panicked ({})", active.device_id)))
+            .transpose()
+    }
+
+    /// Запускает поток, который открывает stream и владеет им.
+    /// 
+    /// С `pre_roll_seconds` поток начинает в режиме pre-roll и ждёт `Control::Record`
+    /// (или начала речи в режиме hands-free), без него - сразу пишет дубль.
+    /// 
+    /// `open` открывает stream уже в потоке (`device_opener` для устройства `device_id`).
+    /// `companions` - дорожки записи звонка: поток закрывает их вместе с дублем
+    /// и добавляет в него.
+    fn spawn_stream(
+        &self,
+        capture: Arc<Mutex<AudioCapture>>,
+        device_id: String,
+        open: StreamOpener,
+        events: Events,
+        pre_roll_seconds: Option<u32>,
+        mut companions: Vec<CompanionTrack>,
+    ) -> Result<ActiveStream, String> {
+        let (control_tx, control_rx) = mpsc::channel::<Control>();
+        let (ready_tx, ready_rx) = mpsc::sync_channel::<Result<(DeviceConfig, String), String>>(1);
+        let thread_device_id = device_id.clone();
+        let cache = self.cache.clone();
+        let processing_sender = self.processing_sender.clone();
+
+        let thread = std::thread::Builder::new()
+            .name("audio-stream".to_string())
+            .spawn(move || {
+                let mut stream = match open(capture.clone()) {
+                    Ok(stream) => stream,
+                    Err(e) => {
+                        for companion in companions {
+                            let _ = companion.finish();
+                        }
+                        let _ = ready_tx.send(Err(e.clone()));
+                        return Err(e);
+                    }
+                };
+                let settings = capture.lock().map(|cap| cap.settings.clone()).unwrap_or_default();
+                // Детектор речи работает только на stream, который остаётся открытым между дублями
+                let mut vad = pre_roll_seconds.filter(|_| settings.hands_free).map(|_| {
+                    VoiceActivityDetector::new(stream.sample_rate, VadConfig {
+                        threshold_db: settings.vad_threshold_db,
+                        trailing_silence_ms: settings.vad_trailing_silence_ms,
+                    })
+                });
+                if pre_roll_seconds.is_none() {
+                    if let Err(e) = set_state(&capture, events.app(), AudioCapture::begin_recording) {
+                        log::error!("Failed to begin recording: {}", e);
+                    }
+                }
+                let config = DeviceConfig {
+                    sample_rate: stream.sample_rate,
+                    channels: stream.channels,
+                    sample_format: format!("{:?}", stream.sample_format),
+                };
+                let _ = ready_tx.send(Ok((config, stream.device_name.clone())));
+
+                loop {
+                    let pre_roll = match pre_roll_seconds {
+                        Some(seconds) => match run_standby(&mut stream, &capture, seconds, vad.as_mut(), &control_rx, &events) {
+                            Some(pre_roll) => pre_roll,
+                            None => {
+                                close_standby(&capture, stream);
+                                log::info!("Pre-roll stream released: {}", thread_device_id);
+                                return Ok(None);
+                            }
+                        },
+                        None => Vec::new(),
+                    };
+
+                    let spill_path = cache.generate_wav_path("take");
+                    let (take, end) = run_consumer(stream, &capture, spill_path, pre_roll, vad.as_mut(), &control_rx, &events);
+                    let job = take.map(|take| with_companions(take, std::mem::take(&mut companions)));
+                    match end {
+                        TakeEnd::Stopped => {
+                            log::info!("Audio stream released: {}", thread_device_id);
+                            return job.map(Some);
+                        }
+                        TakeEnd::Finished => {
+                            log::info!("Audio stream released: {}", thread_device_id);
+                            queue_job(&processing_sender, job, &capture, &events);
+                            return Ok(None);
+                        }
+                        // Дубль завершён по тишине: сразу в очередь, stream снова ждёт речи
+                        TakeEnd::Silence(rest) => {
+                            stream = rest;
+                            emit_hands_free(&events, false);
+                            queue_job(&processing_sender, job, &capture, &events);
+                        }
+                    }
+                }
+            })
+            .map_err(|e| format!("Failed to spawn audio stream thread: {}", e))?;
+
+        let started = ready_rx
+            .recv()
+            .map_err(|_| "Audio stream thread exited unexpectedly".to_string())
+            .and_then(|res| res);
+
+        match started {
+            Ok((config, device_name)) => Ok(ActiveStream {
+                control_tx,
+                thread,
+                device_id,
+                device_name,
+                config,
+                pre_roll: pre_roll_seconds.is_some(),
+            }),
+            Err(e) => {
+                let _ = thread.join();
+                Err(e)
+            }
+        }
+    }
+}
+
+impl ActiveStream {
+    /// Можно ли начать запись на этом stream pre-roll вместо открытия нового.
+    fn can_record(&self, device_id: &str, preferences: &StreamPreferences) -> bool {
+        if !self.pre_roll || self.thread.is_finished() {
+            return false;
+        }
+        let same_device = device_id == self.device_id
+            || device_id == self.device_name
+            || find_input_device(device_id).is_ok_and(|(_, info)| info.name == self.device_name);
+        same_device && preferences_match(preferences, &self.config)
+    }
+}
+
+impl Drop for RecordingSession {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+/// Обёртка для хранения RecordingSession в состоянии Tauri.
+pub type SharedRecordingSession = Arc<Mutex<RecordingSession>>;
+
+/// Цикл потребителя: забирает сэмплы из кольцевого буфера до остановки или авто-стопа.
+/// 
+/// На паузе (`AudioCapture::is_paused`) stream остаётся открытым, а сэмплы
+/// отбрасываются, поэтому лимит длительности считается только по записанному.
+/// 
+/// Stream дропается до финального опустошения буфера, чтобы callback гарантированно
+/// не дописал сэмплы после того как запись отдана.
+/// 
+/// Параметры:
+/// * `capture_stream` - открытый stream и потребитель кольцевого буфера
+/// * `capture` - состояние AudioCapture (флаг записи, счётчик переполнений, настройки)
+/// * `spill_path` - временный файл для сброса длинной записи на диск
+/// * `pre_roll` - звук до начала записи (моно), идёт в начало дубля
+/// * `vad` - детектор речи режима hands-free: дубль заканчивается после тишины
+/// * `control_rx` - канал сигнала остановки
+/// * `events` - получатель событий 'audio-level', 'audio-overrun', 'recording-device-lost' и 'recording-error'
+/// 
+/// Возвращает дубль и то, чем он закончился (для тишины - с всё ещё открытым stream).
+/// К возврату сессия в состоянии Finalizing.
+fn run_consumer(
+    capture_stream: CaptureStream,
+    capture: &Arc<Mutex<AudioCapture>>,
+    spill_path: PathBuf,
+    pre_roll: Vec<f32>,
+    mut vad: Option<&mut VoiceActivityDetector>,
+    control_rx: &mpsc::Receiver<Control>,
+    events: &Events,
+) -> (Result<Take, String>, TakeEnd) {
+    let CaptureStream { stream, mut consumer, sample_rate, channels, mut sample_format, mut device_name, is_loopback, mut source_id } = capture_stream;
+    let mut stream_channels = channels;
+    let mut stream = Some(stream);
+    let (is_capturing, is_paused, overruns, device_lost, source_ended, stream_error, settings) = {
+        // Без состояния записывать некуда: stream закрывается вместе с возвратом
+        let Ok(cap) = capture.lock() else {
+            return (Err("Failed to lock audio state".to_string()), TakeEnd::Finished);
+        };
+        (
+            cap.is_capturing.clone(),
+            cap.is_paused.clone(),
+            cap.overrun_samples.clone(),
+            cap.device_lost.clone(),
+            cap.source_ended.clone(),
+            cap.stream_error.clone(),
+            cap.settings.clone(),
+        )
+    };
+
+    // Запись хранится в моно: каналы сводятся сразу при чтении из кольцевого буфера
+    let mut downmixer = Downmixer::new(settings.channel_strategy, channels);
+    let max_samples = settings.max_record_seconds.map(|s| s as usize * sample_rate as usize);
+    let spill_threshold = settings.spill_after_seconds as usize * sample_rate as usize;
+    let mut recording = RecordingWriter::new(sample_rate, 1, spill_threshold, Some(spill_path));
+    if !pre_roll.is_empty() {
+        let pre_roll = pre_roll_tail(&pre_roll, max_samples);
+        log::debug!("Prepending {} pre-roll samples", pre_roll.len());
+        recording.push(pre_roll);
+    }
+    let mut meter = LevelMeter::new(sample_rate, settings.noise_floor_dbfs);
+    let mut last_level = Instant::now();
+    let mut reported_overruns = 0u64;
+    let mut last_report = Instant::now();
+    let mut ended_by_silence = false;
+    let mut stopped = false;
+
+    loop {
+        // Запись уже идёт, поэтому любая команда, кроме повторного Record, - остановка
+        let stop_requested = !matches!(
+            control_rx.recv_timeout(DRAIN_INTERVAL),
+            Err(mpsc::RecvTimeoutError::Timeout) | Ok(Control::Record)
+        );
+
+        let (level, voice) = drain_ring(
+            &mut consumer, &mut downmixer, &mut meter, vad.as_deref_mut(), &mut recording, max_samples, &is_paused,
+        );
+        if let Some(level) = level {
+            if last_level.elapsed() >= LEVEL_REPORT_INTERVAL {
+                report_level(events, capture, level);
+                last_level = Instant::now();
+            }
+        }
+
+        if last_report.elapsed() >= OVERRUN_REPORT_INTERVAL {
+            report_overruns(events, &overruns, &mut reported_overruns);
+            last_report = Instant::now();
+        }
+
+        if stop_requested {
+            stopped = true;
+            break;
+        }
+
+        // Остаток источника заберёт финальное опустошение буфера
+        if source_ended.load(Ordering::Acquire) {
+            log::info!("Audio source ended: {}", device_name);
+            break;
+        }
+
+        if voice == Some(VadEvent::SpeechEnd) {
+            log::info!("Trailing silence detected, finishing hands-free take");
+            ended_by_silence = true;
+            break;
+        }
+
+        let fault = if device_lost.swap(false, Ordering::AcqRel) {
+            Some(CaptureError { kind: StreamErrorKind::DeviceLost, message: format!("Input device lost: {}", device_name) })
+        } else {
+            take_stream_error(&stream_error)
+        };
+
+        if let Some(error) = fault.as_ref().filter(|e| e.kind == StreamErrorKind::BufferOverrun) {
+            // Драйвер сам восстанавливается после xrun, потерянное видно в отчёте
+            report_stream_error(events, error, &device_name, None, true);
+        } else if let Some(error) = fault {
+            // Сначала закрываем stream и забираем всё, что успело прийти с устройства
+            is_capturing.store(false, Ordering::Release);
+            drop(stream.take());
+            drain_ring(&mut consumer, &mut downmixer, &mut meter, None, &mut recording, max_samples, &is_paused);
+
+            let next = match error.kind {
+                // Звук системы не подменяется микрофоном
+                StreamErrorKind::DeviceLost if is_loopback => {
+                    Err("Loopback source is not replaced by a microphone".to_string())
+                }
+                StreamErrorKind::DeviceLost if settings.device_failover => {
+                    fail_over(capture, &device_name, sample_rate, channels)
+                }
+                StreamErrorKind::DeviceLost => Err("Device failover is disabled".to_string()),
+                _ => reopen_stream(capture, &source_id, sample_rate, channels).map(|next| (device_name.clone(), next)),
+            };
+
+            let switched_to = match next {
+                Ok((next_device, next)) => {
+                    log::warn!("{}, recording continues on {}", error.message, next_device);
+                    downmixer = Downmixer::new(settings.channel_strategy, next.channels);
+                    stream_channels = next.channels;
+                    sample_format = next.sample_format;
+                    source_id = next.source_id;
+                    stream = Some(next.stream);
+                    consumer = next.consumer;
+                    Ok(next_device)
+                }
+                Err(e) => {
+                    log::warn!("{}, finishing take ({})", error.message, e);
+                    Err(e)
+                }
+            };
+
+            report_stream_error(events, &error, &device_name, None, switched_to.is_ok());
+            if error.kind == StreamErrorKind::DeviceLost {
+                let report = match &switched_to {
+                    Ok(next_device) => DeviceLostReport {
+                        lost_device: std::mem::replace(&mut device_name, next_device.clone()),
+                        switched_to: Some(next_device.clone()),
+                        error: None,
+                    },
+                    Err(e) => DeviceLostReport { lost_device: device_name.clone(), switched_to: None, error: Some(e.clone()) },
+                };
+                events.emit("recording-device-lost", report);
+            }
+            if switched_to.is_err() {
+                break;
+            }
+        }
+
+        if let Some(max) = max_samples {
+            if recording.len() >= max {
+                log::info!(
+                    "Max recording duration reached ({}s), auto-stopping. Buffer size: {} samples",
+                    settings.max_record_seconds.unwrap_or_default(), max
+                );
+                break;
+            }
+        }
+    }
+
+    if let Err(e) = set_state(capture, events.app(), AudioCapture::finalize) {
+        log::error!("Failed to finalize take: {}", e);
+    }
+    let end = match stream {
+        // Stream остаётся открытым: звук после конца дубля пойдёт в pre-roll следующего
+        Some(stream) if ended_by_silence => TakeEnd::Silence(CaptureStream {
+            stream,
+            consumer,
+            sample_rate,
+            channels: stream_channels,
+            sample_format,
+            device_name,
+            is_loopback,
+            source_id,
+        }),
+        stream => {
+            is_capturing.store(false, Ordering::Release);
+            drop(stream);
+            drain_ring(&mut consumer, &mut downmixer, &mut meter, None, &mut recording, max_samples, &is_paused);
+            if let Ok(mut cap) = capture.lock() {
+                cap.active_device = None;
+            }
+            if stopped { TakeEnd::Stopped } else { TakeEnd::Finished }
+        }
+    };
+
+    report_overruns(events, &overruns, &mut reported_overruns);
+    if reported_overruns > 0 {
+        log::warn!("Recording finished with {} samples lost to ring buffer overruns", reported_overruns);
+    }
+    (recording.finish(), end)
+}
+
+/// Открывает stream на устройстве по умолчанию вместо отключившегося.
+/// 
+/// Новое устройство должно совпадать с записью по частоте, иначе дубль стал бы
+/// неоднородным - в этом случае возвращается ошибка, и запись завершается.
+/// Количество каналов может отличаться: запись всё равно сводится в моно.
+/// Время старта записи не меняется.
+/// 
+/// Возвращает имя нового устройства и открытый stream.
+fn fail_over(
+    capture: &Arc<Mutex<AudioCapture>>,
+    lost_device: &str,
+    sample_rate: u32,
+    channels: u16,
+) -> Result<(String, CaptureStream), String> {
+    let next_device = default_input_device_name()
+        .ok_or_else(|| "No default input device available".to_string())?;
+    if next_device == lost_device {
+        return Err(format!("Default input device {} is unavailable", next_device));
+    }
+
+    let next = open_matching(capture, &next_device, sample_rate, channels)?;
+    Ok((next_device, next))
+}
+
+/// Переоткрывает stream на том же источнике после сбоя backend.
+/// 
+/// Сбои вроде перезапуска звукового сервера обычно проходят за доли секунды,
+/// поэтому делается несколько попыток с паузой между ними.
+pub(crate) fn reopen_stream(
+    capture: &Arc<Mutex<AudioCapture>>,
+    source_id: &str,
+    sample_rate: u32,
+    channels: u16,
+) -> Result<CaptureStream, String> {
+    let mut last_error = String::new();
+    for attempt in 1..=STREAM_REOPEN_ATTEMPTS {
+        std::thread::sleep(STREAM_REOPEN_DELAY);
+        match open_matching(capture, source_id, sample_rate, channels) {
+            Ok(stream) => {
+                log::info!("Stream reopened on {} (attempt {})", source_id, attempt);
+                return Ok(stream);
+            }
+            Err(e) => {
+                log::warn!("Failed to reopen stream on {} (attempt {}): {}", source_id, attempt, e);
+                last_error = e;
+            }
+        }
+    }
+    Err(format!("Failed to reopen stream after {} attempts: {}", STREAM_REOPEN_ATTEMPTS, last_error))
+}
+
+/// Открывает stream с параметрами текущей записи.
+/// 
+/// Частота должна совпасть, иначе дубль стал бы неоднородным.
+/// Количество каналов может отличаться: запись всё равно сводится в моно.
+fn open_matching(
+    capture: &Arc<Mutex<AudioCapture>>,
+    device_id: &str,
+    sample_rate: u32,
+    channels: u16,
+) -> Result<CaptureStream, String> {
+    let preferences = StreamPreferences {
+        sample_rate: Some(sample_rate),
+        channels: Some(channels),
+        sample_format: None,
+    };
+    let next = start_audio_capture_with_stream(capture.clone(), device_id.to_string(), &preferences)?;
+
+    if next.sample_rate != sample_rate {
+        if let Ok(cap) = capture.lock() {
+            cap.is_capturing.store(false, Ordering::Release);
+        }
+        return Err(format!(
+            "Input device {} uses {} Hz, take is {} Hz",
+            next.device_name, next.sample_rate, sample_rate
+        ));
+    }
+    Ok(next)
+}
+
+/// Последние сэмплы pre-roll, которые идут в начало дубля.
+/// 
+/// Pre-roll занимает не больше половины лимита длины записи, чтобы на саму
+/// запись оставалось место; от него остаются самые свежие сэмплы - звук
+/// прямо перед началом записи.
+fn pre_roll_tail(pre_roll: &[f32], max_samples: Option<usize>) -> &[f32] {
+    let keep = max_samples.map_or(pre_roll.len(), |max| pre_roll.len().min(max / PRE_ROLL_BUDGET_SHARE));
+    &pre_roll[pre_roll.len() - keep..]
+}
+
+/// Режим pre-roll: stream открыт, последние `pre_roll_seconds` секунд звука
+/// (уже сведённые в моно) держатся в памяти до команды записи.
+/// 
+/// С детектором речи (режим hands-free) запись начинается сама, когда
+/// начинается речь, - в этом случае эмитится 'hands-free-recording'.
+/// 
+/// После сбоя backend stream переоткрывается на месте, накопленный pre-roll сохраняется.
+/// 
+/// Возвращает накопленный pre-roll по `Control::Record` или началу речи,
+/// None - если пришла остановка, устройство отключилось или stream не переоткрылся.
+fn run_standby(
+    capture_stream: &mut CaptureStream,
+    capture: &Arc<Mutex<AudioCapture>>,
+    pre_roll_seconds: u32,
+    mut vad: Option<&mut VoiceActivityDetector>,
+    control_rx: &mpsc::Receiver<Control>,
+    events: &Events,
+) -> Option<Vec<f32>> {
+    let (device_lost, source_ended, stream_error, strategy) = {
+        let cap = capture.lock().ok()?;
+        (cap.device_lost.clone(), cap.source_ended.clone(), cap.stream_error.clone(), cap.settings.channel_strategy)
+    };
+    let mut downmixer = Downmixer::new(strategy, capture_stream.channels);
+    let capacity = pre_roll_seconds as usize * capture_stream.sample_rate as usize;
+    let mut pre_roll = VecDeque::with_capacity(capacity);
+
+    loop {
+        let command = control_rx.recv_timeout(DRAIN_INTERVAL);
+        let mut speech_started = false;
+
+        let available = capture_stream.consumer.slots();
+        if let Ok(chunk) = capture_stream.consumer.read_chunk(available) {
+            let (first, second) = chunk.as_slices();
+            for part in [first, second] {
+                let mono = downmixer.process(part);
+                pre_roll.extend(mono);
+                if let Some(vad) = vad.as_deref_mut() {
+                    speech_started |= vad.process(mono) == Some(VadEvent::SpeechStart);
+                }
+            }
+            chunk.commit_all();
+            let excess = pre_roll.len().saturating_sub(capacity);
+            pre_roll.drain(..excess);
+        }
+
+        match command {
+            Ok(Control::Record) => {
+                // Запись начата вручную: тишина после неё тоже завершит дубль
+                if let Some(vad) = vad {
+                    vad.begin_speech();
+                }
+                return Some(pre_roll.into());
+            }
+            Ok(Control::Stop) | Err(mpsc::RecvTimeoutError::Disconnected) => return None,
+            Err(mpsc::RecvTimeoutError::Timeout) => {}
+        }
+
+        if device_lost.swap(false, Ordering::AcqRel) {
+            log::warn!("Pre-roll device lost: {}", capture_stream.device_name);
+            let error = CaptureError { kind: StreamErrorKind::DeviceLost, message: "Input device lost".to_string() };
+            report_stream_error(events, &error, &capture_stream.device_name, None, false);
+            return None;
+        }
+
+        if let Some(error) = take_stream_error(&stream_error) {
+            if error.kind == StreamErrorKind::BufferOverrun {
+                report_stream_error(events, &error, &capture_stream.device_name, None, true);
+            } else {
+                // Старый stream закрывается до открытия нового на том же устройстве
+                capture.lock().ok()?.is_capturing.store(false, Ordering::Release);
+                drop(std::mem::replace(&mut capture_stream.stream, Box::new(())));
+                let reopened = reopen_stream(capture, &capture_stream.source_id, capture_stream.sample_rate, capture_stream.channels);
+                report_stream_error(events, &error, &capture_stream.device_name, None, reopened.is_ok());
+                match reopened {
+                    Ok(next) => {
+                        downmixer = Downmixer::new(strategy, next.channels);
+                        *capture_stream = next;
+                    }
+                    Err(e) => {
+                        log::warn!("Pre-roll stream lost: {} ({})", capture_stream.device_name, e);
+                        return None;
+                    }
+                }
+            }
+        }
+
+        if source_ended.load(Ordering::Acquire) {
+            log::info!("Pre-roll source ended: {}", capture_stream.device_name);
+            return None;
+        }
+
+        if speech_started {
+            // Запись могла уже начаться кнопкой - тогда дубль начнёт Control::Record
+            let started = set_state(capture, events.app(), AudioCapture::arm)
+                .and_then(|_| set_state(capture, events.app(), AudioCapture::begin_recording));
+            if let Err(e) = started {
+                log::warn!("Speech detected, but hands-free take was not started: {}", e);
+                continue;
+            }
+            log::info!("Speech detected, starting hands-free take on {}", capture_stream.device_name);
+            emit_hands_free(events, true);
+            return Some(pre_roll.into());
+        }
+    }
+}
+
+/// Эмитит 'recording-error' с видом ошибки stream и её исходом.
+/// 
+/// `track` - метка дорожки звонка, если ошибка в её источнике.
+pub(crate) fn report_stream_error(events: &Events, error: &CaptureError, device: &str, track: Option<&str>, recovered: bool) {
+    events.emit("recording-error", RecordingErrorReport {
+        kind: error.kind,
+        device: device.to_string(),
+        track: track.map(str::to_string),
+        message: error.message.clone(),
+        recovered,
+    });
+}
+
+/// Эмитит 'hands-free-recording' при начале или завершении дубля детектором речи.
+fn emit_hands_free(events: &Events, recording: bool) {
+    events.emit("hands-free-recording", HandsFreeReport { recording });
+}
+
+/// Собирает дубль с дорожками записи звонка: основная дорожка помечается "Me",
+/// дополнительные закрываются и добавляются следом. Сбой дополнительной дорожки
+/// не мешает отдать основную.
+fn with_companions(take: Take, companions: Vec<CompanionTrack>) -> TakeJob {
+    let mut job = TakeJob::from(take);
+    if companions.is_empty() {
+        return job;
+    }
+    job.tracks[0].label = Some(LOCAL_TRACK_LABEL.to_string());
+    for companion in companions {
+        match companion.finish() {
+            Ok(track) => job.tracks.push(track),
+            Err(e) => log::error!("Call track failed: {}", e),
+        }
+    }
+    job
+}
+
+/// Отправляет дубль, завершённый без команды остановки (авто-стоп, тишина,
+/// конец источника), в очередь обработки.
+/// 
+/// Сессия переходит в Queued, а если дубль пуст или не записался - в Idle.
+fn queue_job(sender: &Sender<TakeJob>, job: Result<TakeJob, String>, capture: &Arc<Mutex<AudioCapture>>, events: &Events) {
+    let job = match job {
+        Ok(job) if job.is_empty() => Err("Finished take is empty, skipping".to_string()),
+        job => job,
+    };
+
+    let job = match job {
+        Ok(job) => job,
+        Err(e) => {
+            log::warn!("{}", e);
+            disarm(capture, events);
+            return;
+        }
+    };
+    // Дубль учитывается в очереди до отправки (см. `AudioCapture::queue_take`)
+    log::info!("Queueing finished take: {:.1}s", job.duration_seconds());
+    if let Err(e) = set_state(capture, events.app(), AudioCapture::queue_take) {
+        log::warn!("{}", e);
+        disarm(capture, events);
+        return;
+    }
+    if let Err(e) = sender.try_send(job) {
+        log::warn!("Failed to queue finished take: {}", e);
+        finish_job(capture, events.app());
+    }
+}
+
+/// Возвращает сессию в Idle: stream для записи не открылся или дубль не ушёл в очередь.
+fn disarm(capture: &Arc<Mutex<AudioCapture>>, events: &Events) {
+    if let Err(e) = set_state(capture, events.app(), |cap| cap.transition(SessionState::Idle)) {
+        log::error!("Failed to reset recording state: {}", e);
+    }
+}
+
+/// Закрывает stream, открытый только для pre-roll.
+fn close_standby(capture: &Arc<Mutex<AudioCapture>>, capture_stream: CaptureStream) {
+    if let Ok(mut cap) = capture.lock() {
+        cap.is_capturing.store(false, Ordering::Release);
+        cap.active_device = None;
+    }
+    drop(capture_stream);
+}
+
+/// Подходит ли открытая конфигурация stream под заданные предпочтения.
+fn preferences_match(preferences: &StreamPreferences, config: &DeviceConfig) -> bool {
+    preferences.sample_rate.is_none_or(|rate| rate == config.sample_rate)
+        && preferences.channels.is_none_or(|channels| channels == config.channels)
+        && preferences
+            .sample_format
+            .as_ref()
+            .is_none_or(|format| format.eq_ignore_ascii_case(&config.sample_format))
+}
+
+/// Эмитит 'audio-level' и сохраняет последний уровень в состоянии AudioCapture.
+fn report_level(events: &Events, capture: &Arc<Mutex<AudioCapture>>, level: LevelReport) {
+    if let Ok(mut cap) = capture.lock() {
+        cap.volume_level = level.peak;
+        cap.rms_input = level.rms;
+    }
+    events.emit("audio-level", level);
+}
+
+/// Эмитит 'audio-overrun', если с прошлого отчёта были потеряны сэмплы.
+fn report_overruns(events: &Events, overruns: &AtomicU64, reported: &mut u64) {
+    let dropped = overruns.load(Ordering::Relaxed);
+    if dropped <= *reported {
+        return;
+    }
+
+    log::warn!("Ring buffer overrun: {} samples dropped so far", dropped);
+    events.emit("audio-overrun", OverrunReport { dropped_samples: dropped });
+    *reported = dropped;
+}
+
+/// Переносит все доступные сэмплы из кольцевого буфера в запись, сводя их в моно.
+/// 
+/// Записанные сэмплы проходят через измеритель уровня и детектор речи;
+/// возвращается уровень последнего завершённого блока и переход речь/тишина.
+/// 
+/// Сэмплы сверх `max_samples` отбрасываются (авто-стоп сработает на этой итерации).
+/// На паузе отбрасываются все сэмплы: буфер всегда содержит целые фреймы,
+/// поэтому сведение после паузы не сбивается.
+fn drain_ring(
+    consumer: &mut Consumer<f32>,
+    downmixer: &mut Downmixer,
+    meter: &mut LevelMeter,
+    mut vad: Option<&mut VoiceActivityDetector>,
+    recording: &mut RecordingWriter,
+    max_samples: Option<usize>,
+    is_paused: &AtomicBool,
+) -> (Option<LevelReport>, Option<VadEvent>) {
+    let mut level = None;
+    let mut voice = None;
+    let available = consumer.slots();
+    if let Ok(chunk) = consumer.read_chunk(available) {
+        if is_paused.load(Ordering::Acquire) {
+            chunk.commit_all();
+            return (None, None);
+        }
+        let (first, second) = chunk.as_slices();
+        for part in [first, second] {
+            let part = downmixer.process(part);
+            let room = max_samples.map_or(part.len(), |max| max.saturating_sub(recording.len()));
+            let part = &part[..part.len().min(room)];
+            recording.push(part);
+            level = meter.push(part).or(level);
+            if let Some(vad) = vad.as_deref_mut() {
+                voice = vad.process(part).or(voice);
+            }
+        }
+        chunk.commit_all();
+    }
+    (level, voice)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audio::capture::open_source;
+    use crate::audio::processor::process_take;
+    use crate::audio::source::{Signal, SyntheticSource};
+    use crate::utils::cache::AudioCache;
+
+    fn headless_capture(max_record_seconds: Option<u32>) -> Arc<Mutex<AudioCapture>> {
+        let mut capture = AudioCapture::default();
+        capture.settings.max_record_seconds = max_record_seconds;
+        Arc::new(Mutex::new(capture))
+    }
+
+    fn begin_recording(capture: &Arc<Mutex<AudioCapture>>) {
+        let mut cap = capture.lock().unwrap();
+        cap.arm().unwrap();
+        cap.begin_recording().unwrap();
+    }
+
+    fn spill_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("session_test_{}_{}.wav", name, std::process::id()))
+    }
+
+    #[test]
+    fn test_synthetic_take_is_recorded_and_processed() {
+        let capture = headless_capture(Some(1));
+        let source = SyntheticSource::new(16000, 2)
+            .forever(Signal::Sine { frequency: 440.0, amplitude: 0.05 })
+            .with_speed(Some(8.0));
+        let stream = open_source(capture.clone(), Box::new(source), &StreamPreferences::default()).unwrap();
+        begin_recording(&capture);
+        let (_control_tx, control_rx) = mpsc::channel();
+
+        let (take, end) = run_consumer(stream, &capture, spill_path("synthetic"), Vec::new(), None, &control_rx, &Events::default());
+        let take = take.unwrap();
+        // Авто-стоп: дубль уходит в очередь без команды остановки
+        assert!(matches!(end, TakeEnd::Finished));
+        assert_eq!(capture.lock().unwrap().session_state, SessionState::Finalizing);
+        assert_eq!((take.len(), take.sample_rate, take.channels), (16000, 16000, 1));
+        assert!(!capture.lock().unwrap().is_capturing.load(Ordering::Acquire));
+
+        // Тихий сигнал поднимается обработкой к целевому уровню
+        let mut processed = Vec::new();
+        process_take(&take, &capture, |chunk| {
+            processed.extend_from_slice(chunk);
+            Ok(())
+        })
+        .unwrap();
+        let rms = (processed.iter().map(|s| s * s).sum::<f32>() / processed.len() as f32).sqrt();
+        assert_eq!(processed.len(), 16000);
+        assert!(rms > 0.05, "processed rms {}", rms);
+    }
+
+    #[test]
+    fn test_source_end_finishes_take() {
+        let capture = headless_capture(None);
+        let source = SyntheticSource::new(8000, 1)
+            .then(Signal::Sine { frequency: 300.0, amplitude: 0.3 }, Duration::from_millis(500))
+            .with_speed(None);
+        let stream = open_source(capture.clone(), Box::new(source), &StreamPreferences::default()).unwrap();
+        begin_recording(&capture);
+        let (_control_tx, control_rx) = mpsc::channel();
+
+        let (take, _) = run_consumer(stream, &capture, spill_path("source_end"), Vec::new(), None, &control_rx, &Events::default());
+        assert_eq!(take.unwrap().len(), 4000);
+        assert!(!capture.lock().unwrap().is_recording.load(Ordering::Acquire));
+    }
+
+    #[test]
+    fn test_hands_free_take_ends_on_silence() {
+        let capture = headless_capture(None);
+        let source = SyntheticSource::new(16000, 1)
+            .then(Signal::Silence, Duration::from_millis(300))
+            .then(Signal::Sine { frequency: 220.0, amplitude: 0.3 }, Duration::from_millis(500))
+            .then(Signal::Silence, Duration::from_millis(800))
+            .with_speed(Some(4.0));
+        let mut stream = open_source(capture.clone(), Box::new(source), &StreamPreferences::default()).unwrap();
+        let mut vad = VoiceActivityDetector::new(16000, VadConfig { threshold_db: 12.0, trailing_silence_ms: 300 });
+        let (_control_tx, control_rx) = mpsc::channel();
+        let events = Events::default();
+
+        let pre_roll = run_standby(&mut stream, &capture, 1, Some(&mut vad), &control_rx, &events).unwrap();
+        assert!(capture.lock().unwrap().is_recording.load(Ordering::Acquire));
+
+        let (take, end) = run_consumer(stream, &capture, spill_path("hands_free"), pre_roll, Some(&mut vad), &control_rx, &events);
+        let take = take.unwrap();
+        // Речь и тишина до её конца, но не вся запись источника
+        assert!(take.duration_seconds() > 0.8 && take.duration_seconds() < 1.5, "{}s", take.duration_seconds());
+        match end {
+            TakeEnd::Silence(rest) => close_standby(&capture, rest),
+            _ => panic!("hands-free take must end on silence"),
+        }
+    }
+
+    #[test]
+    fn test_backend_error_reopens_stream() {
+        let capture = headless_capture(None);
+        let (control_tx, control_rx) = mpsc::channel();
+        let stream_error = capture.lock().unwrap().stream_error.clone();
+
+        // Stream не Send - открывается в потоке, который им владеет, как в spawn_stream
+        let thread_capture = capture.clone();
+        let consumer = std::thread::spawn(move || {
+            let source = SyntheticSource::new(48000, 1).forever(Signal::Sine { frequency: 440.0, amplitude: 0.3 });
+            let stream = open_source(thread_capture.clone(), Box::new(source), &StreamPreferences::default()).unwrap();
+            // Переоткрытие идёт по ID: синтетический ID принимается только в тестах
+            let stream = CaptureStream { source_id: "synth:440".to_string(), ..stream };
+            begin_recording(&thread_capture);
+            let (take, end) = run_consumer(stream, &thread_capture, spill_path("reopen"), Vec::new(), None, &control_rx, &Events::default());
+            assert!(matches!(end, TakeEnd::Stopped));
+            take
+        });
+        std::thread::sleep(Duration::from_millis(300));
+        *stream_error.lock().unwrap() = Some(CaptureError { kind: StreamErrorKind::Backend, message: "EIO".to_string() });
+        std::thread::sleep(Duration::from_millis(700));
+        assert!(capture.lock().unwrap().is_recording.load(Ordering::Acquire));
+        control_tx.send(Control::Stop).unwrap();
+
+        // Запись продолжилась после переоткрытия: в дубле и звук до сбоя, и после
+        let seconds = consumer.join().unwrap().unwrap().duration_seconds();
+        assert!(seconds > 0.6 && seconds < 1.1, "take is {}s", seconds);
+        assert!(!capture.lock().unwrap().is_capturing.load(Ordering::Acquire));
+    }
+
+    #[test]
+    fn test_auto_stop_queues_take_itself() {
+        let capture = headless_capture(Some(1));
+        let (sender, mut receiver) = tokio::sync::mpsc::channel(1);
+        let mut session = RecordingSession::new(Arc::new(AudioCache::new().unwrap()), sender);
+        capture.lock().unwrap().arm().unwrap();
+
+        let open: StreamOpener = Box::new(|capture| {
+            let source = SyntheticSource::new(48000, 1).forever(Signal::Sine { frequency: 440.0, amplitude: 0.3 });
+            open_source(capture, Box::new(source), &StreamPreferences::default())
+        });
+        let active = session
+            .spawn_stream(capture.clone(), "Synthetic".to_string(), open, Events::default(), None, Vec::new())
+            .unwrap();
+        session.active = Some(active);
+        assert_eq!(capture.lock().unwrap().session_state, SessionState::Recording);
+
+        // Авто-стоп через секунду: дубль уходит в очередь без stop
+        let job = receiver.blocking_recv().unwrap();
+        assert!((job.duration_seconds() - 1.0).abs() < 0.01, "{}s", job.duration_seconds());
+        assert!(session.stop().is_none());
+        assert_eq!(capture.lock().unwrap().session_state, SessionState::Queued);
+    }
+
+    #[test]
+    fn test_pre_roll_keeps_latest_samples_within_budget() {
+        let pre_roll: Vec<f32> = (0..10).map(|i| i as f32).collect();
+        assert_eq!(pre_roll_tail(&pre_roll, None), &pre_roll[..]);
+        assert_eq!(pre_roll_tail(&pre_roll, Some(100)), &pre_roll[..]);
+        // Лимит 8 сэмплов: pre-roll не больше половины, самые свежие
+        assert_eq!(pre_roll_tail(&pre_roll, Some(8)), &[6.0, 7.0, 8.0, 9.0]);
+        assert!(pre_roll_tail(&pre_roll, Some(1)).is_empty());
+    }
+
+    #[test]
+    fn test_preferences_match_open_config() {
+        let config = DeviceConfig { sample_rate: 48000, channels: 2, sample_format: "F32".to_string() };
+        assert!(preferences_match(&StreamPreferences::default(), &config));
+
+        let prefs = StreamPreferences { sample_rate: Some(48000), channels: None, sample_format: Some("f32".to_string()) };
+        assert!(preferences_match(&prefs, &config));
+
+        let prefs = StreamPreferences { sample_rate: Some(16000), channels: None, sample_format: None };
+        assert!(!preferences_match(&prefs, &config));
+        let prefs = StreamPreferences { sample_rate: None, channels: Some(1), sample_format: None };
+        assert!(!preferences_match(&prefs, &config));
+    }
+}
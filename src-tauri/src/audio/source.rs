@@ -0,0 +1,427 @@
+use std::any::Any;
+#[cfg(test)]
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+use cpal::SampleFormat;
+
+use crate::audio::capture::{CpalSource, SampleSink};
+use crate::audio::devices::StreamPreferences;
+use crate::audio::loopback::{LoopbackSource, LOOPBACK_ID_PREFIX};
+
+/// Префикс ID синтетического источника в тестах (`synth:440`, `synth:noise`, `synth:silence`)
+#[cfg(test)]
+pub const SYNTH_SOURCE_PREFIX: &str = "synth:";
+/// Размер блока, которым источники без устройства пишут в кольцевой буфер
+const FEED_BLOCK_MS: u32 = 10;
+/// Амплитуда синтетического сигнала, заданного через ID
+#[cfg(test)]
+const SYNTH_AMPLITUDE: f32 = 0.3;
+/// Частота дискретизации синтетического сигнала, заданного через ID
+#[cfg(test)]
+const SYNTH_SAMPLE_RATE: u32 = 48000;
+
+/// Открытый источник: пока значение живо, источник пишет сэмплы в кольцевой буфер.
+/// Drop останавливает источник (закрывает cpal stream или поток генерации).
+pub type SourceGuard = Box<dyn Any>;
+
+/// Конфигурация, которую выбрал источник.
+#[derive(Debug, Clone)]
+pub struct SourceConfig {
+    pub sample_rate: u32,
+    pub channels: u16,
+    pub sample_format: SampleFormat,
+    /// Имя источника (устройство, файл)
+    pub name: String,
+    /// Источник записывает звук, который играет система (monitor / loopback)
+    pub is_loopback: bool,
+}
+
+/// Источник звука для записи: устройство ввода, WAV файл или синтетический сигнал.
+/// 
+/// Источник открывается в два шага: `configure` выбирает конфигурацию (от неё
+/// зависит размер кольцевого буфера), `start` запускает запись interleaved f32
+/// сэмплов в `SampleSink`. Сессия, сведение и обработка дубля не зависят от
+/// того, откуда пришёл звук, поэтому в тестах запись и обработка дубля
+/// проверяются без звуковой карты (WAV файл и синтетический сигнал есть только
+/// в тестовой сборке).
+pub trait AudioSource {
+    /// Выбирает конфигурацию, ближайшую к `preferences` из доступных источнику.
+    fn configure(&mut self, preferences: &StreamPreferences) -> Result<SourceConfig, String>;
+
+    /// Запускает источник. Возвращает guard, который останавливает его при drop.
+    fn start(self: Box<Self>, sink: SampleSink) -> Result<SourceGuard, String>;
+}
+
+/// Создаёт источник по ID.
+/// 
+/// * `loopback:<имя>` - звук, который играет система (см. loopback.rs)
+/// * всё остальное - устройство ввода (`InputDevice::id` или имя)
+/// 
+/// Файлы и синтетический сигнал по ID не открываются: такой ID мог бы прийти
+/// из frontend и сохраниться как устройство по умолчанию. В тестах их источники
+/// создаются напрямую (`WavFileSource`, `SyntheticSource`), а `synth:...`
+/// принимается только для переоткрытия stream по ID.
+pub fn source_from_id(id: &str) -> Result<Box<dyn AudioSource>, String> {
+    if id.starts_with(LOOPBACK_ID_PREFIX) {
+        return Ok(Box::new(LoopbackSource::new(id)));
+    }
+    #[cfg(test)]
+    if let Some(spec) = id.strip_prefix(SYNTH_SOURCE_PREFIX) {
+        return synthetic_from_spec(spec).map(|source| Box::new(source) as Box<dyn AudioSource>);
+    }
+    Ok(Box::new(CpalSource::new(id)))
+}
+
+/// Бесконечный синтетический сигнал по спецификации `440`, `noise` или `silence`.
+#[cfg(test)]
+fn synthetic_from_spec(spec: &str) -> Result<SyntheticSource, String> {
+    let signal = match spec {
+        "silence" => Signal::Silence,
+        "noise" => Signal::Noise { amplitude: SYNTH_AMPLITUDE },
+        frequency => Signal::Sine {
+            frequency: frequency
+                .parse()
+                .map_err(|_| format!("Invalid synthetic source: {}", spec))?,
+            amplitude: SYNTH_AMPLITUDE,
+        },
+    };
+    Ok(SyntheticSource::new(SYNTH_SAMPLE_RATE, 1).forever(signal))
+}
+
+/// WAV файл как источник звука.
+/// 
+/// Файл проигрывается в заданном темпе (`speed`: 1.0 - реальное время,
+/// None - так быстро, как успевает потребитель). После конца файла источник
+/// сообщает о завершении (`SampleSink::finish`), и запись останавливается.
+/// Конфигурация определяется файлом, предпочтения stream не учитываются.
+#[cfg(test)]
+pub struct WavFileSource {
+    path: PathBuf,
+    speed: Option<f32>,
+    spec: Option<hound::WavSpec>,
+}
+
+#[cfg(test)]
+impl WavFileSource {
+    /// Параметры:
+    /// * `path` - путь к WAV файлу (PCM 8-32 бит или float 32 бит)
+    /// * `speed` - темп относительно реального времени (None = без ограничения)
+    pub fn new(path: impl AsRef<Path>, speed: Option<f32>) -> Self {
+        Self { path: path.as_ref().to_path_buf(), speed, spec: None }
+    }
+}
+
+#[cfg(test)]
+impl AudioSource for WavFileSource {
+    fn configure(&mut self, _preferences: &StreamPreferences) -> Result<SourceConfig, String> {
+        let reader = hound::WavReader::open(&self.path)
+            .map_err(|e| format!("Failed to open WAV source {:?}: {}", self.path, e))?;
+        let spec = reader.spec();
+        let sample_format = match (spec.sample_format, spec.bits_per_sample) {
+            (hound::SampleFormat::Float, 32) => SampleFormat::F32,
+            (hound::SampleFormat::Int, 8) => SampleFormat::I8,
+            (hound::SampleFormat::Int, 16) => SampleFormat::I16,
+            (hound::SampleFormat::Int, 17..=32) => SampleFormat::I32,
+            (format, bits) => return Err(format!("Unsupported WAV source format: {:?} {} bit", format, bits)),
+        };
+        self.spec = Some(spec);
+
+        Ok(SourceConfig {
+            sample_rate: spec.sample_rate,
+            channels: spec.channels,
+            sample_format,
+            name: self.path.file_name().map_or_else(
+                || self.path.display().to_string(),
+                |name| name.to_string_lossy().to_string(),
+            ),
+            is_loopback: false,
+        })
+    }
+
+    fn start(self: Box<Self>, sink: SampleSink) -> Result<SourceGuard, String> {
+        let spec = self.spec.ok_or_else(|| "WAV source is not configured".to_string())?;
+        let reader = hound::WavReader::open(&self.path)
+            .map_err(|e| format!("Failed to open WAV source {:?}: {}", self.path, e))?;
+        let path = self.path.clone();
+
+        let mut samples: Box<dyn Iterator<Item = Result<f32, hound::Error>> + Send> = match spec.sample_format {
+            hound::SampleFormat::Float => Box::new(reader.into_samples::<f32>()),
+            hound::SampleFormat::Int => {
+                let scale = 1.0 / (1u64 << (spec.bits_per_sample - 1)) as f32;
+                Box::new(reader.into_samples::<i32>().map(move |s| s.map(|v| v as f32 * scale)))
+            }
+        };
+
+        spawn_feeder("wav-source", sink, spec.sample_rate, spec.channels, self.speed, move |block, len| {
+            for _ in 0..len {
+                match samples.next() {
+                    Some(Ok(sample)) => block.push(sample),
+                    Some(Err(e)) => {
+                        log::error!("WAV source {:?} read error: {}", path, e);
+                        return false;
+                    }
+                    None => return false,
+                }
+            }
+            true
+        })
+    }
+}
+
+/// Сигнал синтетического источника.
+#[cfg(test)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Signal {
+    Silence,
+    Sine { frequency: f32, amplitude: f32 },
+    /// Равномерный белый шум
+    Noise { amplitude: f32 },
+}
+
+/// Синтетический источник звука: последовательность сигналов заданной длительности.
+/// 
+/// Один и тот же сигнал пишется во все каналы. Частота и количество каналов
+/// берутся из предпочтений stream, если они заданы. После последнего
+/// сегмента источник сообщает о завершении (`forever` - бесконечный сегмент).
+#[cfg(test)]
+pub struct SyntheticSource {
+    sample_rate: u32,
+    channels: u16,
+    segments: Vec<(Signal, Option<Duration>)>,
+    speed: Option<f32>,
+}
+
+#[cfg(test)]
+impl SyntheticSource {
+    /// Пустой источник в реальном времени; сегменты добавляются через `then`/`forever`.
+    pub fn new(sample_rate: u32, channels: u16) -> Self {
+        Self { sample_rate, channels: channels.max(1), segments: Vec::new(), speed: Some(1.0) }
+    }
+
+    /// Добавляет сегмент сигнала заданной длительности.
+    pub fn then(mut self, signal: Signal, duration: Duration) -> Self {
+        self.segments.push((signal, Some(duration)));
+        self
+    }
+
+    /// Добавляет бесконечный сегмент (сегменты после него не проигрываются).
+    pub fn forever(mut self, signal: Signal) -> Self {
+        self.segments.push((signal, None));
+        self
+    }
+
+    /// Темп относительно реального времени (None = без ограничения).
+    pub fn with_speed(mut self, speed: Option<f32>) -> Self {
+        self.speed = speed;
+        self
+    }
+}
+
+#[cfg(test)]
+impl AudioSource for SyntheticSource {
+    fn configure(&mut self, preferences: &StreamPreferences) -> Result<SourceConfig, String> {
+        self.sample_rate = preferences.sample_rate.unwrap_or(self.sample_rate);
+        self.channels = preferences.channels.unwrap_or(self.channels).max(1);
+        Ok(SourceConfig {
+            sample_rate: self.sample_rate,
+            channels: self.channels,
+            sample_format: SampleFormat::F32,
+            name: "Synthetic".to_string(),
+            is_loopback: false,
+        })
+    }
+
+    fn start(self: Box<Self>, sink: SampleSink) -> Result<SourceGuard, String> {
+        let SyntheticSource { sample_rate, channels, segments, speed } = *self;
+        let mut segments = segments.into_iter();
+        let mut current = segments.next();
+        let mut segment_frame = 0u64;
+        let mut frame = 0u64;
+        let mut noise_state = 0x2545_f491u32;
+
+        spawn_feeder("synthetic-source", sink, sample_rate, channels, speed, move |block, len| {
+            for _ in 0..len / channels as usize {
+                let Some((signal, duration)) = current else {
+                    return false;
+                };
+                if duration.is_some_and(|d| segment_frame >= (d.as_secs_f64() * sample_rate as f64) as u64) {
+                    current = segments.next();
+                    segment_frame = 0;
+                    continue;
+                }
+
+                let t = frame as f32 / sample_rate as f32;
+                let value = match signal {
+                    Signal::Silence => 0.0,
+                    Signal::Sine { frequency, amplitude } => amplitude * (2.0 * std::f32::consts::PI * frequency * t).sin(),
+                    Signal::Noise { amplitude } => {
+                        noise_state = noise_state.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+                        amplitude * ((noise_state >> 8) as f32 / (1u32 << 23) as f32 - 1.0)
+                    }
+                };
+                block.extend(std::iter::repeat_n(value, channels as usize));
+                segment_frame += 1;
+                frame += 1;
+            }
+            current.is_some()
+        })
+    }
+}
+
+/// Поток, который пишет сгенерированные сэмплы в кольцевой буфер.
+/// 
+/// Drop останавливает поток и дожидается его завершения.
+struct Feeder {
+    stop: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl Drop for Feeder {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Release);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+/// Запускает поток-источник без устройства.
+/// 
+/// `fill` дописывает в блок до `len` interleaved сэмплов и возвращает false,
+/// когда источник закончился. Темп задаёт `speed` (1.0 - реальное время,
+/// None - без пауз, ограничивает только место в кольцевом буфере).
+pub fn spawn_feeder<F>(
+    name: &str,
+    mut sink: SampleSink,
+    sample_rate: u32,
+    channels: u16,
+    speed: Option<f32>,
+    mut fill: F,
+) -> Result<SourceGuard, String>
+where
+    F: FnMut(&mut Vec<f32>, usize) -> bool + Send + 'static,
+{
+    let stop = Arc::new(AtomicBool::new(false));
+    let thread_stop = stop.clone();
+    let channels = channels.max(1) as usize;
+    let block_len = (sample_rate * FEED_BLOCK_MS / 1000).max(1) as usize * channels;
+
+    let thread = std::thread::Builder::new()
+        .name(name.to_string())
+        .spawn(move || {
+            let started = Instant::now();
+            let mut block = Vec::with_capacity(block_len);
+            let mut fed_frames = 0u64;
+
+            loop {
+                block.clear();
+                let more = fill(&mut block, block_len);
+                if !sink.push_blocking(&block, &thread_stop) {
+                    return;
+                }
+                fed_frames += (block.len() / channels) as u64;
+                if !more {
+                    sink.finish();
+                    return;
+                }
+
+                if let Some(speed) = speed.filter(|s| *s > 0.0) {
+                    let due = Duration::from_secs_f64(fed_frames as f64 / (sample_rate as f64 * speed as f64));
+                    if let Some(wait) = due.checked_sub(started.elapsed()) {
+                        std::thread::sleep(wait);
+                    }
+                }
+            }
+        })
+        .map_err(|e| format!("Failed to spawn {} thread: {}", name, e))?;
+
+    Ok(Box::new(Feeder { stop, thread: Some(thread) }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+    use crate::audio::capture::open_source;
+    use crate::types::AudioCapture;
+
+    /// Читает из кольцевого буфера, пока источник не закончится
+    fn read_until_end(capture: &Arc<Mutex<AudioCapture>>, consumer: &mut rtrb::Consumer<f32>) -> Vec<f32> {
+        let ended = capture.lock().unwrap().source_ended.clone();
+        let deadline = Instant::now() + Duration::from_secs(5);
+        let mut out = Vec::new();
+        loop {
+            let finished = ended.load(Ordering::Acquire);
+            while let Ok(sample) = consumer.pop() {
+                out.push(sample);
+            }
+            if finished || Instant::now() > deadline {
+                return out;
+            }
+            std::thread::sleep(Duration::from_millis(1));
+        }
+    }
+
+    #[test]
+    fn test_synthetic_source_plays_segments() {
+        let capture = Arc::new(Mutex::new(AudioCapture::default()));
+        let source = SyntheticSource::new(8000, 1)
+            .then(Signal::Sine { frequency: 440.0, amplitude: 0.5 }, Duration::from_millis(100))
+            .then(Signal::Silence, Duration::from_millis(50))
+            .with_speed(None);
+
+        let mut stream = open_source(capture.clone(), Box::new(source), &StreamPreferences::default()).unwrap();
+        assert_eq!(stream.sample_rate, 8000);
+        let samples = read_until_end(&capture, &mut stream.consumer);
+
+        assert_eq!(samples.len(), 1200);
+        assert!(samples[..800].iter().any(|s| s.abs() > 0.4));
+        assert!(samples[800..].iter().all(|&s| s == 0.0));
+    }
+
+    #[test]
+    fn test_synthetic_source_follows_preferences() {
+        let capture = Arc::new(Mutex::new(AudioCapture::default()));
+        let source = SyntheticSource::new(8000, 1).then(Signal::Silence, Duration::from_millis(10)).with_speed(None);
+        let preferences = StreamPreferences { sample_rate: Some(16000), channels: Some(2), sample_format: None };
+
+        let mut stream = open_source(capture.clone(), Box::new(source), &preferences).unwrap();
+        assert_eq!((stream.sample_rate, stream.channels), (16000, 2));
+        assert_eq!(read_until_end(&capture, &mut stream.consumer).len(), 320);
+    }
+
+    #[test]
+    fn test_wav_file_source_reads_whole_file() {
+        let path = std::env::temp_dir().join(format!("wav_source_test_{}.wav", std::process::id()));
+        let spec = hound::WavSpec { channels: 2, sample_rate: 16000, bits_per_sample: 16, sample_format: hound::SampleFormat::Int };
+        let mut writer = hound::WavWriter::create(&path, spec).unwrap();
+        for i in 0..4000 {
+            writer.write_sample(if i % 2 == 0 { 16384i16 } else { -16384i16 }).unwrap();
+        }
+        writer.finalize().unwrap();
+
+        let capture = Arc::new(Mutex::new(AudioCapture::default()));
+        let source = WavFileSource::new(&path, None);
+        let mut stream = open_source(capture.clone(), Box::new(source), &StreamPreferences::default()).unwrap();
+        assert_eq!((stream.sample_rate, stream.channels), (16000, 2));
+
+        let samples = read_until_end(&capture, &mut stream.consumer);
+        drop(stream);
+        let _ = std::fs::remove_file(&path);
+
+        assert_eq!(samples.len(), 4000);
+        assert_eq!(&samples[..2], &[0.5, -0.5]);
+    }
+
+    #[test]
+    fn test_synthetic_spec() {
+        assert!(synthetic_from_spec("440").is_ok());
+        assert!(synthetic_from_spec("noise").is_ok());
+        assert!(synthetic_from_spec("abc").is_err());
+        assert!(source_from_id("synth:abc").is_err());
+    }
+}
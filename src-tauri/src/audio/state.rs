@@ -0,0 +1,154 @@
+use std::sync::{Arc, Mutex};
+
+use serde::Serialize;
+use tauri::{AppHandle, Emitter};
+
+use crate::types::AudioCapture;
+
+/// Состояние сессии записи.
+/// 
+/// Единственный источник правды о том, что происходит с дублем. Атомарные
+/// флаги `AudioCapture` (`is_recording`, `is_paused`) повторяют его для потоков,
+/// которые не берут мьютекс (callback, дорожки звонка).
+/// 
+/// Переходы:
+/// - Idle / Queued -> Arming: запрошена запись (кнопка или начало речи)
+/// - Arming -> Recording: stream открыт; Arming -> Idle: открыть не удалось
+/// - Recording <-> Paused
+/// - Recording / Paused -> Finalizing: остановка, авто-стоп, тишина, конец источника
+/// - Finalizing -> Queued: дубль отправлен на обработку; Finalizing -> Idle: дубль пуст или потерян
+/// - Queued -> Idle: очередь обработки опустела (`AudioCapture::pending_jobs`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SessionState {
+    /// Записи нет (stream закрыт или открыт только для pre-roll)
+    #[default]
+    Idle,
+    /// Запись запрошена, stream открывается
+    Arming,
+    /// Сэмплы пишутся в дубль
+    Recording,
+    /// Дубль начат, но сэмплы отбрасываются до продолжения
+    Paused,
+    /// Stream закрывается, дубль собирается из кольцевого буфера
+    Finalizing,
+    /// Дубль в очереди обработки
+    Queued,
+}
+
+/// Payload события 'recording-state'.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct StateChange {
+    pub from: SessionState,
+    pub to: SessionState,
+}
+
+impl SessionState {
+    /// Разрешён ли переход в состояние `next`.
+    pub fn can_become(self, next: SessionState) -> bool {
+        use SessionState::*;
+        matches!(
+            (self, next),
+            (Idle | Queued, Arming)
+                | (Arming, Recording | Idle)
+                | (Recording, Paused | Finalizing)
+                | (Paused, Recording | Finalizing)
+                | (Finalizing, Queued | Idle)
+                | (Queued, Idle)
+        )
+    }
+
+    /// Дубль начат (в том числе на паузе).
+    pub fn is_recording(self) -> bool {
+        matches!(self, SessionState::Recording | SessionState::Paused)
+    }
+
+    /// Проверяет переход и возвращает его.
+    pub fn transition(self, next: SessionState) -> Result<StateChange, String> {
+        if !self.can_become(next) {
+            return Err(format!("Invalid recording state transition: {:?} -> {:?}", self, next));
+        }
+        Ok(StateChange { from: self, to: next })
+    }
+}
+
+/// Применяет переход состояния к AudioCapture и эмитит 'recording-state'.
+/// 
+/// Параметры:
+/// * `capture` - состояние AudioCapture
+/// * `app` - AppHandle для события (None - без окна, тесты)
+/// * `change` - переход (`AudioCapture::arm`, `begin_recording`, `pause` и т.д.)
+pub fn set_state<F>(capture: &Arc<Mutex<AudioCapture>>, app: Option<&AppHandle>, change: F) -> Result<StateChange, String>
+where
+    F: FnOnce(&mut AudioCapture) -> Result<StateChange, String>,
+{
+    let change = change(&mut *capture.lock().map_err(|_| "Failed to lock audio state".to_string())?)?;
+    emit_state(app, change);
+    Ok(change)
+}
+
+/// Отмечает, что дубль покинул очередь обработки (`AudioCapture::finish_job`),
+/// и эмитит 'recording-state', если очередь опустела и сессия перешла в Idle.
+pub fn finish_job(capture: &Arc<Mutex<AudioCapture>>, app: Option<&AppHandle>) {
+    let change = match capture.lock() {
+        Ok(mut cap) => cap.finish_job(),
+        Err(_) => {
+            log::error!("Failed to lock audio state");
+            return;
+        }
+    };
+    if let Some(change) = change {
+        emit_state(app, change);
+    }
+}
+
+fn emit_state(app: Option<&AppHandle>, change: StateChange) {
+    log::debug!("Recording state: {:?} -> {:?}", change.from, change.to);
+    if let Some(app) = app {
+        if let Err(e) = app.emit("recording-state", change) {
+            log::error!("Failed to emit recording-state event: {}", e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use SessionState::*;
+
+    #[test]
+    fn test_take_lifecycle_transitions() {
+        let path = [Idle, Arming, Recording, Paused, Recording, Finalizing, Queued, Arming, Recording, Finalizing, Idle];
+        for pair in path.windows(2) {
+            assert!(pair[0].can_become(pair[1]), "{:?} -> {:?}", pair[0], pair[1]);
+        }
+    }
+
+    #[test]
+    fn test_queue_becomes_idle_after_last_job() {
+        let capture = Arc::new(Mutex::new(AudioCapture { session_state: Finalizing, ..AudioCapture::default() }));
+        set_state(&capture, None, AudioCapture::queue_take).unwrap();
+
+        // Второй дубль поставлен в очередь, пока первый обрабатывается
+        set_state(&capture, None, AudioCapture::arm).unwrap();
+        set_state(&capture, None, AudioCapture::begin_recording).unwrap();
+        set_state(&capture, None, |cap| cap.transition(Finalizing)).unwrap();
+        set_state(&capture, None, AudioCapture::queue_take).unwrap();
+
+        finish_job(&capture, None);
+        assert_eq!(capture.lock().unwrap().session_state, Queued);
+        finish_job(&capture, None);
+        assert_eq!(capture.lock().unwrap().session_state, Idle);
+        assert_eq!(capture.lock().unwrap().pending_jobs, 0);
+    }
+
+    #[test]
+    fn test_invalid_transitions_are_rejected() {
+        // Остановка после авто-стопа: дубль уже собран, повторно его не финализировать
+        assert!(Queued.transition(Finalizing).is_err());
+        assert!(Idle.transition(Finalizing).is_err());
+        assert!(Idle.transition(Paused).is_err());
+        assert!(Finalizing.transition(Arming).is_err());
+        assert!(Paused.transition(Paused).is_err());
+    }
+}
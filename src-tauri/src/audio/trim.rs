@@ -0,0 +1,257 @@
+use serde::{Deserialize, Serialize};
+
+use crate::audio::pipeline::DspStage;
+
+/// Длительность кадра анализа
+const FRAME_MS: u32 = 20;
+/// Кадры тише этого уровня никогда не считаются речью
+const MIN_SPEECH_DBFS: f32 = -55.0;
+/// Сколько подряд громких кадров нужно, чтобы считать их речью
+/// (отсекает щелчок кнопки записи в начале и конце дубля)
+const MIN_SPEECH_MS: u32 = 60;
+/// Нижняя граница уровня в dBFS
+const MIN_DBFS: f32 = -120.0;
+
+/// Параметры обрезки тишины (стадия `trim`).
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TrimParams {
+    /// Уровень кадра относительно самого громкого кадра дубля, dB,
+    /// ниже которого кадр считается тишиной
+    pub threshold_db: f32,
+    /// Сколько миллисекунд тишины оставить перед речью
+    pub leading_margin_ms: u32,
+    /// Сколько миллисекунд тишины оставить после речи
+    pub trailing_margin_ms: u32,
+}
+
+impl Default for TrimParams {
+    fn default() -> Self {
+        Self {
+            threshold_db: -40.0,
+            leading_margin_ms: 200,
+            trailing_margin_ms: 300,
+        }
+    }
+}
+
+impl TrimParams {
+    pub fn validate(&self) -> Result<(), String> {
+        if !(-80.0..=-10.0).contains(&self.threshold_db) {
+            return Err("Trim threshold must be between -80 and -10 dB".to_string());
+        }
+        if self.leading_margin_ms > 2000 || self.trailing_margin_ms > 2000 {
+            return Err("Trim margins must not exceed 2000 ms".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// Где в исходном дубле лежит обработанный звук после обрезки тишины.
+/// 
+/// Время в обработанном файле + `start_ms` = время в исходном дубле (pre WAV).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct TrimOffsets {
+    /// Начало оставленной части, мс от начала дубля
+    pub start_ms: u64,
+    /// Конец оставленной части, мс от начала дубля
+    pub end_ms: u64,
+}
+
+impl TrimOffsets {
+    /// Переводит диапазон сэмплов в миллисекунды.
+    pub fn from_samples((start, end): (u64, u64), sample_rate: u32) -> Self {
+        let to_ms = |samples: u64| samples * 1000 / sample_rate.max(1) as u64;
+        Self { start_ms: to_ms(start), end_ms: to_ms(end) }
+    }
+}
+
+/// Обрезка тишины в начале и конце дубля по энергии.
+/// 
+/// В предварительном проходе сигнал режется на кадры по 20 мс. Речь - первые
+/// и последние ~60 мс подряд кадров, которые не тише самого громкого кадра
+/// на `threshold_db` (и не тише -55 dBFS). Всё до речи и после неё отбрасывается,
+/// кроме полей `leading_margin_ms` и `trailing_margin_ms`, чтобы не срезать
+/// тихие начала и концы слов.
+/// 
+/// Если речи не нашлось, дубль не обрезается. Какая часть осталась - `kept_range`.
+pub struct Trim {
+    params: TrimParams,
+    frame_len: usize,
+    sample_rate: u32,
+    /// Уровни кадров (dBFS) из предварительного прохода
+    levels: Vec<f32>,
+    frame_sum: f64,
+    frame_count: usize,
+    analyzed: u64,
+    /// Оставляемый диапазон сэмплов, вычисляется при первом `process`
+    kept: Option<Option<(u64, u64)>>,
+    position: u64,
+}
+
+impl Trim {
+    /// Параметры:
+    /// * `sample_rate` - частота сигнала
+    /// * `params` - параметры стадии из профиля обработки
+    pub fn new(sample_rate: u32, params: &TrimParams) -> Self {
+        Self {
+            params: *params,
+            frame_len: (sample_rate as usize * FRAME_MS as usize / 1000).max(1),
+            sample_rate,
+            levels: Vec::new(),
+            frame_sum: 0.0,
+            frame_count: 0,
+            analyzed: 0,
+            kept: None,
+            position: 0,
+        }
+    }
+
+    /// Ищет речь по уровням кадров и возвращает оставляемый диапазон сэмплов.
+    fn speech_range(&self) -> Option<(u64, u64)> {
+        let mut levels = self.levels.clone();
+        if self.frame_count > 0 {
+            levels.push(frame_dbfs(self.frame_sum, self.frame_count));
+        }
+
+        let loudest = levels.iter().copied().fold(MIN_DBFS, f32::max);
+        let threshold = (loudest + self.params.threshold_db).max(MIN_SPEECH_DBFS);
+        let min_run = MIN_SPEECH_MS.div_ceil(FRAME_MS) as usize;
+
+        // Серии громких кадров подряд (начало, конец), достаточно длинные для речи
+        let mut runs = Vec::new();
+        let mut run_start = None;
+        for (index, &level) in levels.iter().chain(std::iter::once(&MIN_DBFS)).enumerate() {
+            match (level >= threshold, run_start) {
+                (true, None) => run_start = Some(index),
+                (false, Some(start)) => {
+                    if index - start >= min_run {
+                        runs.push((start, index));
+                    }
+                    run_start = None;
+                }
+                _ => {}
+            }
+        }
+        let (first, _) = *runs.first()?;
+        let (_, last) = *runs.last()?;
+
+        let margin = |ms: u32| self.sample_rate as u64 * ms as u64 / 1000;
+        let start = (first * self.frame_len) as u64;
+        let end = (last * self.frame_len) as u64;
+        Some((
+            start.saturating_sub(margin(self.params.leading_margin_ms)),
+            (end + margin(self.params.trailing_margin_ms)).min(self.analyzed),
+        ))
+    }
+}
+
+/// Уровень кадра по сумме квадратов, dBFS.
+fn frame_dbfs(sum_squares: f64, count: usize) -> f32 {
+    let mean_square = sum_squares / count.max(1) as f64;
+    (10.0 * mean_square.log10() as f32).max(MIN_DBFS)
+}
+
+impl DspStage for Trim {
+    fn process(&mut self, input: &[f32], output: &mut Vec<f32>) {
+        let kept = self.kept_range();
+        self.kept = Some(kept);
+        let block_start = self.position;
+        self.position += input.len() as u64;
+
+        let Some((start, end)) = kept else {
+            output.extend_from_slice(input);
+            return;
+        };
+        let from = start.clamp(block_start, self.position) - block_start;
+        let to = end.clamp(block_start, self.position) - block_start;
+        output.extend_from_slice(&input[from as usize..to as usize]);
+    }
+
+    fn needs_analysis(&self) -> bool {
+        true
+    }
+
+    fn analyze(&mut self, input: &[f32]) {
+        self.analyzed += input.len() as u64;
+        for &sample in input {
+            self.frame_sum += (sample * sample) as f64;
+            self.frame_count += 1;
+            if self.frame_count == self.frame_len {
+                self.levels.push(frame_dbfs(self.frame_sum, self.frame_count));
+                self.frame_sum = 0.0;
+                self.frame_count = 0;
+            }
+        }
+    }
+
+    fn kept_range(&self) -> Option<(u64, u64)> {
+        self.kept.unwrap_or_else(|| self.speech_range())
+    }
+
+    fn reset(&mut self) {
+        self.position = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RATE: u32 = 16000;
+
+    fn tone(seconds: f32, amplitude: f32) -> Vec<f32> {
+        (0..(RATE as f32 * seconds) as usize)
+            .map(|n| amplitude * (2.0 * std::f32::consts::PI * 300.0 * n as f32 / RATE as f32).sin())
+            .collect()
+    }
+
+    fn run(params: &TrimParams, input: &[f32]) -> (Vec<f32>, Option<(u64, u64)>) {
+        let mut trim = Trim::new(RATE, params);
+        for chunk in input.chunks(1000) {
+            trim.analyze(chunk);
+        }
+        let mut output = Vec::new();
+        for chunk in input.chunks(1000) {
+            trim.process(chunk, &mut output);
+        }
+        (output, trim.kept_range())
+    }
+
+    #[test]
+    fn test_trims_silence_keeping_margins() {
+        // 1 с фонового шума, 1 с речи, 2 с шума
+        let mut input = tone(1.0, 0.0003);
+        input.extend(tone(1.0, 0.2));
+        input.extend(tone(2.0, 0.0003));
+
+        let (output, kept) = run(&TrimParams::default(), &input);
+        let (start, end) = kept.unwrap();
+        assert_eq!(start, (RATE - RATE / 5) as u64);
+        assert_eq!(end, (2 * RATE + RATE * 3 / 10) as u64);
+        assert_eq!(output, &input[start as usize..end as usize]);
+
+        let offsets = TrimOffsets::from_samples((start, end), RATE);
+        assert_eq!(offsets, TrimOffsets { start_ms: 800, end_ms: 2300 });
+    }
+
+    #[test]
+    fn test_click_is_not_speech() {
+        // Щелчок кнопки записи (10 мс) в начале не сдвигает начало речи
+        let mut input = tone(0.3, 0.0);
+        input.extend(tone(0.01, 0.9));
+        input.extend(tone(0.69, 0.0));
+        input.extend(tone(0.5, 0.1));
+
+        let (_, kept) = run(&TrimParams { leading_margin_ms: 0, ..TrimParams::default() }, &input);
+        assert_eq!(kept, Some((RATE as u64, input.len() as u64)));
+    }
+
+    #[test]
+    fn test_silent_take_is_kept() {
+        let input = tone(1.0, 0.0001);
+        let (output, kept) = run(&TrimParams::default(), &input);
+        assert_eq!(kept, None);
+        assert_eq!(output, input);
+    }
+}
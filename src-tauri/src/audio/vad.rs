@@ -0,0 +1,219 @@
+/// Длительность кадра анализа
+const FRAME_MS: u32 = 20;
+/// Кадры тише этого уровня никогда не считаются речью
+const MIN_SPEECH_DBFS: f32 = -55.0;
+/// Доля смен знака, выше которой кадр считается шумом (шипение, вентилятор)
+const MAX_SPEECH_ZCR: f32 = 0.35;
+/// Сколько подряд речевых кадров нужно, чтобы начать речь (отсекает щелчки)
+const MIN_SPEECH_MS: u32 = 60;
+/// Скорость подстройки оценки шума (на кадр) в тишине
+const NOISE_ADAPT_RATE: f32 = 0.05;
+/// Нижняя граница уровня в dBFS
+const MIN_DBFS: f32 = -120.0;
+
+/// Параметры детектора речи.
+#[derive(Debug, Clone, Copy)]
+pub struct VadConfig {
+    /// На сколько dB кадр должен быть громче оценки шума, чтобы считаться речью
+    pub threshold_db: f32,
+    /// Сколько миллисекунд тишины завершают речь
+    pub trailing_silence_ms: u32,
+}
+
+/// Переход между речью и тишиной.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VadEvent {
+    SpeechStart,
+    SpeechEnd,
+}
+
+/// Детектор речи по энергии и частоте смены знака (zero-crossing rate).
+/// 
+/// Сигнал (моно) режется на кадры по 20 мс. Кадр считается речевым, если он
+/// громче адаптивной оценки шума на `threshold_db` и его ZCR не похож на шум.
+/// Речь начинается после ~60 мс речевых кадров подряд и заканчивается после
+/// `trailing_silence_ms` без речи. Оценка шума подстраивается только в тишине.
+pub struct VoiceActivityDetector {
+    config: VadConfig,
+    frame_len: usize,
+    frame: Vec<f32>,
+    noise_dbfs: Option<f32>,
+    in_speech: bool,
+    speech_frames: u32,
+    silence_frames: u32,
+    min_speech_frames: u32,
+    trailing_silence_frames: u32,
+}
+
+impl VoiceActivityDetector {
+    /// Параметры:
+    /// * `sample_rate` - частота дискретизации моно сигнала
+    /// * `config` - пороги детектора
+    pub fn new(sample_rate: u32, config: VadConfig) -> Self {
+        let frame_len = (sample_rate as usize * FRAME_MS as usize / 1000).max(1);
+        Self {
+            config,
+            frame_len,
+            frame: Vec::with_capacity(frame_len),
+            noise_dbfs: None,
+            in_speech: false,
+            speech_frames: 0,
+            silence_frames: 0,
+            min_speech_frames: MIN_SPEECH_MS.div_ceil(FRAME_MS),
+            trailing_silence_frames: config.trailing_silence_ms.div_ceil(FRAME_MS).max(1),
+        }
+    }
+
+    /// Считает, что речь уже идёт (запись начата вручную): тишина дольше
+    /// `trailing_silence_ms` с этого момента завершит её.
+    pub fn begin_speech(&mut self) {
+        self.in_speech = true;
+        self.silence_frames = 0;
+    }
+
+    /// Анализирует сэмплы и возвращает переход, если он произошёл.
+    /// 
+    /// Если в блоке несколько переходов, возвращается последний.
+    pub fn process(&mut self, samples: &[f32]) -> Option<VadEvent> {
+        let mut event = None;
+        for &s in samples {
+            self.frame.push(s);
+            if self.frame.len() == self.frame_len {
+                event = self.process_frame().or(event);
+                self.frame.clear();
+            }
+        }
+        event
+    }
+
+    fn process_frame(&mut self) -> Option<VadEvent> {
+        let energy = frame_dbfs(&self.frame);
+        let zcr = zero_crossing_rate(&self.frame);
+        let noise = *self.noise_dbfs.get_or_insert(energy);
+
+        let voiced = energy > noise + self.config.threshold_db
+            && energy > MIN_SPEECH_DBFS
+            && zcr <= MAX_SPEECH_ZCR;
+
+        if !voiced {
+            // Вниз оценка шума идёт сразу, вверх - медленно
+            let adapted = if energy < noise { energy } else { noise + (energy - noise) * NOISE_ADAPT_RATE };
+            self.noise_dbfs = Some(adapted);
+        }
+
+        if self.in_speech {
+            if voiced {
+                self.silence_frames = 0;
+                return None;
+            }
+            self.silence_frames += 1;
+            if self.silence_frames >= self.trailing_silence_frames {
+                self.in_speech = false;
+                self.speech_frames = 0;
+                self.silence_frames = 0;
+                return Some(VadEvent::SpeechEnd);
+            }
+            return None;
+        }
+
+        self.speech_frames = if voiced { self.speech_frames + 1 } else { 0 };
+        if self.speech_frames >= self.min_speech_frames {
+            self.in_speech = true;
+            self.silence_frames = 0;
+            return Some(VadEvent::SpeechStart);
+        }
+        None
+    }
+}
+
+/// Уровень кадра (RMS) в dBFS.
+fn frame_dbfs(frame: &[f32]) -> f32 {
+    if frame.is_empty() {
+        return MIN_DBFS;
+    }
+    let rms = (frame.iter().map(|&s| s * s).sum::<f32>() / frame.len() as f32).sqrt();
+    if rms <= 0.0 {
+        return MIN_DBFS;
+    }
+    (20.0 * rms.log10()).max(MIN_DBFS)
+}
+
+/// Доля соседних сэмплов с разным знаком.
+fn zero_crossing_rate(frame: &[f32]) -> f32 {
+    if frame.len() < 2 {
+        return 0.0;
+    }
+    let crossings = frame.windows(2).filter(|w| (w[0] >= 0.0) != (w[1] >= 0.0)).count();
+    crossings as f32 / (frame.len() - 1) as f32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RATE: u32 = 16000;
+
+    fn config() -> VadConfig {
+        VadConfig { threshold_db: 12.0, trailing_silence_ms: 300 }
+    }
+
+    fn tone(ms: u32, amplitude: f32) -> Vec<f32> {
+        let len = (RATE * ms / 1000) as usize;
+        (0..len)
+            .map(|i| amplitude * (2.0 * std::f32::consts::PI * 220.0 * i as f32 / RATE as f32).sin())
+            .collect()
+    }
+
+    /// Детерминированный "белый" шум (высокий ZCR)
+    fn noise(ms: u32, amplitude: f32) -> Vec<f32> {
+        let len = (RATE * ms / 1000) as usize;
+        let mut state = 12345u32;
+        (0..len)
+            .map(|_| {
+                state = state.wrapping_mul(1_103_515_245).wrapping_add(12345);
+                amplitude * ((state >> 16) as f32 / 32768.0 - 1.0)
+            })
+            .collect()
+    }
+
+    fn events(vad: &mut VoiceActivityDetector, samples: &[f32]) -> Vec<VadEvent> {
+        samples.chunks(160).filter_map(|c| vad.process(c)).collect()
+    }
+
+    #[test]
+    fn test_speech_start_and_end() {
+        let mut vad = VoiceActivityDetector::new(RATE, config());
+        assert!(events(&mut vad, &noise(500, 0.001)).is_empty());
+
+        assert_eq!(events(&mut vad, &tone(400, 0.3)), vec![VadEvent::SpeechStart]);
+
+        // Короткая пауза внутри фразы не завершает речь
+        assert!(events(&mut vad, &noise(200, 0.001)).is_empty());
+        assert!(events(&mut vad, &tone(200, 0.3)).is_empty());
+
+        assert_eq!(events(&mut vad, &noise(400, 0.001)), vec![VadEvent::SpeechEnd]);
+    }
+
+    #[test]
+    fn test_click_is_not_speech() {
+        let mut vad = VoiceActivityDetector::new(RATE, config());
+        events(&mut vad, &noise(300, 0.001));
+        let mut signal = tone(20, 0.5);
+        signal.extend(noise(300, 0.001));
+        assert!(events(&mut vad, &signal).is_empty());
+    }
+
+    #[test]
+    fn test_loud_hiss_is_not_speech() {
+        let mut vad = VoiceActivityDetector::new(RATE, config());
+        events(&mut vad, &noise(300, 0.001));
+        assert!(events(&mut vad, &noise(500, 0.3)).is_empty());
+    }
+
+    #[test]
+    fn test_manual_start_ends_on_silence() {
+        let mut vad = VoiceActivityDetector::new(RATE, config());
+        vad.begin_speech();
+        assert_eq!(events(&mut vad, &noise(400, 0.001)), vec![VadEvent::SpeechEnd]);
+    }
+}
@@ -1,99 +1,241 @@
-use std::sync::{Arc, Mutex, RwLock};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
 use tokio::sync::mpsc::Receiver;
 use hound;
 use crate::types::AudioCapture;
-use crate::audio::processor::process_audio;
+use crate::audio::loudness::LoudnessMeter;
+use crate::audio::processor::{process_take, ProcessingReport, PROCESS_CHUNK_FRAMES};
+use crate::audio::quality::QualityAnalyzer;
+use crate::audio::recording::{Take, TakeJob};
+use crate::audio::resample::{Resampler, TARGET_SAMPLE_RATE};
+use crate::audio::state::finish_job;
+use serde::Serialize;
 use crate::utils::cache::SharedAudioCache;
 use tauri::{AppHandle, Emitter};
 
+type WavFileWriter = hound::WavWriter<std::io::BufWriter<std::fs::File>>;
+
+/// Файлы обработанной дорожки (payload события 'tracks-processing-finished').
+#[derive(Debug, Clone, Serialize)]
+pub struct ProcessedTrack {
+    /// Метка дорожки ("Me", "Them")
+    pub label: String,
+    pub pre_path: String,
+    pub post_path: String,
+    /// Что обработка сделала с дорожкой (обрезка тишины)
+    #[serde(flatten)]
+    pub report: ProcessingReport,
+}
+
 /// Background worker для обработки аудио в отдельном потоке.
 /// 
-/// Принимает сэмплы из mpsc канала, сохраняет их в pre-processed WAV,
-/// применяет process_audio для фильтрации/усиления, сохраняет post-processed WAV
-/// и эмитит событие 'processing-finished' с путями к файлам.
+/// Принимает дубли из mpsc канала, сохраняет их в pre-processed WAV,
+/// применяет process_take для фильтрации/усиления, сохраняет post-processed WAV
+/// (уже 16 кГц моно - формат распознавания) и эмитит событие 'processing-finished'
+/// с путями к файлам и `ProcessingReport` (где в pre WAV лежит обрезанный post WAV,
+/// громкость и качество записи). Отчёт сохраняется рядом с post WAV (.json).
+/// Когда очередь опустела, сессия записи переходит из Queued в Idle.
+/// 
+/// Многодорожечный дубль (запись звонка) обрабатывается по дорожкам, и вместо
+/// 'processing-finished' эмитится 'tracks-processing-finished' со списком
+/// `ProcessedTrack`. Дорожка, которую не удалось обработать, пропускается.
+/// 
+/// Дубль может лежать в памяти или во временном файле (длинная запись) -
+/// он читается блоками, поэтому обработка не зависит от длительности записи.
 /// 
 /// Параметры:
-/// * `rx` - mpsc receiver для получения сэмплов
-/// * `capture` - Arc на состояние AudioCapture для чтения sample_rate/channels
+/// * `rx` - mpsc receiver для получения дублей
+/// * `capture` - Arc на состояние AudioCapture с параметрами обработки
 /// * `cache` - Arc на AudioCache для генерации путей к временным WAV файлам
 /// * `app` - AppHandle для отправки событий во frontend
 pub async fn run(
-    mut rx: Receiver<Vec<f32>>, 
+    mut rx: Receiver<TakeJob>, 
     capture: Arc<Mutex<AudioCapture>>, 
     cache: SharedAudioCache,
     app: AppHandle
 ) {
     log::info!("Audio worker started");
 
-    while let Some(samples) = rx.recv().await {
-        let pre_path = cache.generate_wav_path("pre");
-        let post_path = cache.generate_wav_path("post");
-
-        // Read sample rate and channels from capture
-        let (sample_rate, channels) = {
-            let cap = capture.lock().unwrap();
-            (cap.sample_rate, cap.channels)
-        };
-
-        // Save pre-processing WAV
-        if let Err(e) = write_wav_f32_path(&pre_path, &samples, sample_rate, channels) {
-            log::error!("Failed to write pre WAV: {}", e);
-        } else {
-            log::info!("Wrote pre WAV: {:?}", pre_path);
-        }
+    while let Some(job) = rx.recv().await {
+        process_job(job, &capture, &cache, &app).await;
+        // Последний дубль очереди обработан: сессия переходит в Idle, если новый не начат
+        finish_job(&capture, Some(&app));
+    }
 
-        // Prepare output buffer for processed data
-        let out_buf: Arc<RwLock<Vec<f32>>> = Arc::new(RwLock::new(Vec::new()));
+    log::info!("Audio worker exiting");
+}
 
-        // run processor in blocking thread
-        let capture_clone = capture.clone();
-        let samples_clone = samples.clone();
-        let out_clone = out_buf.clone();
+/// Обрабатывает один дубль и эмитит 'processing-finished'
+/// или 'tracks-processing-finished'.
+async fn process_job(
+    job: TakeJob,
+    capture: &Arc<Mutex<AudioCapture>>,
+    cache: &SharedAudioCache,
+    app: &AppHandle,
+) {
+    let capture_clone = capture.clone();
+    let cache_clone = cache.clone();
+    let multitrack = job.is_multitrack();
 
-        let _ = tokio::task::spawn_blocking(move || {
-            process_audio(&samples_clone, &out_clone, capture_clone);
-        }).await;
+    // run processor in blocking thread
+    let result = tokio::task::spawn_blocking(move || {
+        job.tracks
+            .into_iter()
+            .filter_map(|track| {
+                let label = track.label.unwrap_or_default();
+                match process_take_to_files(track.take, &capture_clone, &cache_clone) {
+                    Ok((pre_path, post_path, report)) => Some(ProcessedTrack {
+                        label,
+                        pre_path: pre_path.to_string_lossy().to_string(),
+                        post_path: post_path.to_string_lossy().to_string(),
+                        report,
+                    }),
+                    Err(e) => {
+                        log::error!("Failed to process track {:?}: {}", label, e);
+                        None
+                    }
+                }
+            })
+            .collect::<Vec<_>>()
+    })
+    .await
+    .map_err(|e| format!("Processing task failed: {}", e));
 
-        // Read processed samples
-        let processed = match out_buf.read() {
-            Ok(v) => v.clone(),
-            Err(e) => {
-                log::error!("Failed to read processed buffer: {}", e);
-                Vec::new()
-            }
-        };
+    let mut tracks = match result {
+        Ok(tracks) if !tracks.is_empty() => tracks,
+        Ok(_) => return,
+        Err(e) => {
+            log::error!("Failed to process take: {}", e);
+            return;
+        }
+    };
 
-        // Save post-processing WAV
-        if let Err(e) = write_wav_f32_path(&post_path, &processed, sample_rate, channels) {
-            log::error!("Failed to write post WAV: {}", e);
-        } else {
-            log::info!("Wrote post WAV: {:?}", post_path);
+    if multitrack {
+        log::info!("Emitting tracks-processing-finished event with {} tracks", tracks.len());
+        if let Err(e) = app.emit("tracks-processing-finished", &tracks) {
+            log::error!("Failed to emit tracks-processing-finished event: {}", e);
         }
+        return;
+    }
+
+    // Emit event to front-end with paths
+    let track = tracks.swap_remove(0);
+    let payload = (track.pre_path, track.post_path, track.report);
+    log::info!("Emitting processing-finished event with payload: {:?}", payload);
+    
+    if let Err(e) = app.emit("processing-finished", payload) {
+        log::error!("Failed to emit processing-finished event: {}", e);
+    } else {
+        log::info!("Successfully emitted processing-finished event");
+    }
+}
+
+/// Сохраняет pre WAV, обрабатывает дубль и сохраняет post WAV.
+/// 
+/// Pre WAV хранит дубль как записан, post WAV ресэмплируется с ограничением полосы
+/// до `TARGET_SAMPLE_RATE` (см. `Resampler`) - распознаванию ресэмплинг уже не нужен.
+/// Тишина в начале и конце post WAV может быть обрезана: смещение в `ProcessingReport`.
+/// Дубли моно (каналы сводятся при захвате).
+/// 
+/// Вместе с pre WAV считаются `QualityReport`, громкость и истинный пик дубля
+/// как записан: они попадают в отчёт обработки, предупреждения о качестве
+/// пишутся в лог. От стадии нормализации в отчёте остаётся только усиление.
+/// 
+/// Ошибка записи pre WAV только логируется, ошибка обработки или записи post WAV
+/// прерывает обработку дубля. Временный файл дубля удаляется вместе с `take`.
+/// 
+/// Параметры:
+/// * `take` - записанный дубль
+/// * `capture` - состояние AudioCapture с параметрами обработки
+/// * `cache` - кэш для путей к WAV файлам
+fn process_take_to_files(
+    take: Take,
+    capture: &Arc<Mutex<AudioCapture>>,
+    cache: &SharedAudioCache,
+) -> Result<(PathBuf, PathBuf, ProcessingReport), String> {
+    let pre_path = cache.generate_wav_path("pre");
+    let post_path = cache.generate_wav_path("post");
+
+    log::info!(
+        "Processing take: {:.1}s, {} samples ({})",
+        take.duration_seconds(),
+        take.len(),
+        if take.is_spilled() { "spilled to disk" } else { "in memory" }
+    );
 
-        // Emit event to front-end with paths
-        let payload = (pre_path.to_string_lossy().to_string(), post_path.to_string_lossy().to_string());
-        log::info!("Emitting processing-finished event with payload: {:?}", payload);
-        
-        if let Err(e) = app.emit("processing-finished", payload) {
-            log::error!("Failed to emit processing-finished event: {}", e);
-        } else {
-            log::info!("Successfully emitted processing-finished event");
+    // Save pre-processing WAV and analyze the take as recorded
+    let mut analyzer = QualityAnalyzer::new(take.sample_rate);
+    let mut meter = LoudnessMeter::new(take.sample_rate);
+    let mut pre_writer = create_wav_writer(&pre_path, take.sample_rate, take.channels);
+    let read_result = take.for_each_chunk(PROCESS_CHUNK_FRAMES, |chunk| {
+        analyzer.add(chunk);
+        meter.add(chunk);
+        if let Ok(writer) = &mut pre_writer {
+            if let Err(e) = write_wav_chunk(writer, chunk) {
+                pre_writer = Err(e);
+            }
         }
+        Ok(())
+    });
+    let pre_result = read_result
+        .and(pre_writer)
+        .and_then(|writer| writer.finalize().map_err(|e| e.to_string()));
+    if let Err(e) = pre_result {
+        log::error!("Failed to write pre WAV: {}", e);
+    } else {
+        log::info!("Wrote pre WAV: {:?}", pre_path);
+    }
+    let quality = analyzer.finish();
+    for warning in &quality.warnings {
+        log::warn!("Take quality: {}", warning);
     }
 
-    log::info!("Audio worker exiting");
+    // Process, resample and save post-processing WAV
+    let mut post_writer = create_wav_writer(&post_path, TARGET_SAMPLE_RATE, take.channels)
+        .map_err(|e| format!("Failed to create post WAV: {}", e))?;
+    let mut resampler = Resampler::new(take.sample_rate, TARGET_SAMPLE_RATE);
+    let mut resampled = Vec::with_capacity(PROCESS_CHUNK_FRAMES);
+    let mut report = process_take(&take, capture, |chunk| {
+        resampled.clear();
+        resampler.process(chunk, &mut resampled);
+        write_wav_chunk(&mut post_writer, &resampled)
+    })
+    .map_err(|e| format!("Failed to process audio: {}", e))?;
+    resampled.clear();
+    resampler.flush(&mut resampled);
+    write_wav_chunk(&mut post_writer, &resampled)
+        .map_err(|e| format!("Failed to write post WAV: {}", e))?;
+    post_writer.finalize()
+        .map_err(|e| format!("Failed to write post WAV: {}", e))?;
+    log::info!("Wrote post WAV: {:?}", post_path);
+
+    let gain_db = report.loudness.map_or(0.0, |stage| stage.gain_db);
+    report.loudness = Some(meter.report(gain_db));
+    report.quality = Some(quality);
+    if let Err(e) = save_report(&post_path, &report) {
+        log::error!("Failed to save processing report: {}", e);
+    }
+
+    Ok((pre_path, post_path, report))
 }
 
-/// Записывает f32 сэмплы в WAV файл с 16-битным PCM форматом.
+/// Сохраняет отчёт обработки рядом с post WAV (тот же путь с расширением .json).
 /// 
-/// Конвертирует f32 [-1.0, 1.0] в i16 с ограничением диапазона.
+/// Параметры:
+/// * `post_path` - путь к обработанному WAV файлу
+/// * `report` - отчёт обработки с качеством дубля
+fn save_report(post_path: &Path, report: &ProcessingReport) -> Result<(), String> {
+    let content = serde_json::to_string_pretty(report).map_err(|e| e.to_string())?;
+    std::fs::write(post_path.with_extension("json"), content).map_err(|e| e.to_string())
+}
+
+/// Создаёт WAV файл с 16-битным PCM форматом.
 /// 
 /// Параметры:
 /// * `path` - путь к выходному WAV файлу
-/// * `samples` - срез аудиосэмплов (f32)
 /// * `sample_rate` - частота дискретизации
 /// * `channels` - количество каналов
-fn write_wav_f32_path(path: &std::path::Path, samples: &[f32], sample_rate: u32, channels: u16) -> Result<(), String> {
+fn create_wav_writer(path: &Path, sample_rate: u32, channels: u16) -> Result<WavFileWriter, String> {
     let spec = hound::WavSpec {
         channels,
         sample_rate,
@@ -101,13 +243,20 @@ fn write_wav_f32_path(path: &std::path::Path, samples: &[f32], sample_rate: u32,
         sample_format: hound::SampleFormat::Int,
     };
 
-    let mut writer = hound::WavWriter::create(path, spec).map_err(|e| e.to_string())?;
+    hound::WavWriter::create(path, spec).map_err(|e| e.to_string())
+}
 
+/// Дописывает блок f32 сэмплов в WAV файл.
+/// 
+/// Конвертирует f32 [-1.0, 1.0] в i16 с ограничением диапазона.
+/// 
+/// Параметры:
+/// * `writer` - открытый WAV writer
+/// * `samples` - срез аудиосэмплов (f32)
+fn write_wav_chunk(writer: &mut WavFileWriter, samples: &[f32]) -> Result<(), String> {
     for &s in samples.iter() {
         let v = (s.clamp(-1.0, 1.0) * i16::MAX as f32) as i16;
         writer.write_sample(v).map_err(|e| e.to_string())?;
     }
-
-    writer.finalize().map_err(|e| e.to_string())?;
     Ok(())
 }
@@ -1,7 +1,11 @@
-use tauri::State;
+use tauri::{AppHandle, State};
 use std::sync::{Arc, Mutex};
-use crate::types::AudioCapture;
-use crate::audio::capture::start_audio_capture_with_stream;
+use crate::types::{AudioCapture, RecordingStatus};
+use crate::audio::devices::{DeviceConfig, StreamPreferences};
+use crate::audio::recording::TakeJob;
+use crate::utils::config;
+use crate::audio::session::SharedRecordingSession;
+use crate::audio::state::{finish_job, set_state, SessionState};
 use tokio::sync::mpsc::Sender;
 
 /// Возвращает текущий статус записи.
@@ -11,130 +15,214 @@ use tokio::sync::mpsc::Sender;
 #[tauri::command]
 pub fn get_recording_status(
     state: State<'_, Arc<Mutex<AudioCapture>>>,
-) -> Result<bool, String> {
+) -> Result<RecordingStatus, String> {
     let capture = state.lock()
         .map_err(|_| "Failed to lock state".to_string())?;
-    let is_recording = *capture.is_recording.lock()
-        .map_err(|_| "Failed to lock is_recording".to_string())?;
-    Ok(is_recording)
+    Ok(capture.status())
+}
+
+/// Ставит текущую запись на паузу.
+/// 
+/// Stream остаётся открытым, сэмплы до `resume_recording` в дубль не попадают.
+/// Время паузы не учитывается в ограничении длительности записи.
+/// 
+/// Параметры:
+/// * `state` - глобальное состояние AudioCapture
+/// * `app` - AppHandle для события 'recording-state'
+#[tauri::command]
+pub fn pause_recording(
+    state: State<'_, Arc<Mutex<AudioCapture>>>,
+    app: AppHandle,
+) -> Result<RecordingStatus, String> {
+    set_state(&state, Some(&app), AudioCapture::pause)?;
+    let capture = state.lock()
+        .map_err(|_| "Failed to lock state".to_string())?;
+    log::info!("Recording paused at {:.1}s", capture.elapsed_without_pauses().as_secs_f64());
+    Ok(capture.status())
+}
+
+/// Продолжает запись после паузы в тот же дубль.
+/// 
+/// Параметры:
+/// * `state` - глобальное состояние AudioCapture
+/// * `app` - AppHandle для события 'recording-state'
+#[tauri::command]
+pub fn resume_recording(
+    state: State<'_, Arc<Mutex<AudioCapture>>>,
+    app: AppHandle,
+) -> Result<RecordingStatus, String> {
+    set_state(&state, Some(&app), AudioCapture::resume)?;
+    let capture = state.lock()
+        .map_err(|_| "Failed to lock state".to_string())?;
+    log::info!("Recording resumed at {:.1}s", capture.elapsed_without_pauses().as_secs_f64());
+    Ok(capture.status())
 }
 
 /// Запускает запись аудио с микрофона.
 /// 
-/// Инициализирует аудиозахват через cpal stream, который продолжает работать пока is_recording == true.
-/// Stream забывается через mem::forget чтобы callback продолжал работу до явной остановки.
-/// Авто-стоп происходит через 30 секунд внутри audio callback (см. capture.rs).
+/// Инициализирует аудиозахват через cpal stream, которым владеет RecordingSession.
+/// Stream живёт до stop_recording, авто-стопа или выхода из приложения.
+/// Авто-стоп по `AudioSettings::max_record_seconds` происходит в потоке сессии (см. session.rs).
 /// 
 /// Параметры:
-/// * `state` - глобальное состояние AudioCapture с буфером и флагами записи
-/// * `device` - имя аудиоустройства для захвата
+/// * `state` - глобальное состояние AudioCapture с флагами записи
+/// * `session` - сессия записи, владеющая cpal stream
+/// * `device` - ID аудиоустройства для захвата (`InputDevice::id`)
+/// * `preferences` - желаемые частота, количество каналов и формат (необязательно)
+/// * `app` - AppHandle для событий сессии ('audio-overrun')
+/// 
+/// Если задан `AudioSettings::call_track_device`, второй источник пишется
+/// одновременно отдельной дорожкой (запись звонка, см. audio/tracks.rs).
+/// 
+/// Выбранное устройство запоминается в настройках (`AudioSettings::input_device`).
+/// Возвращает конфигурацию, ближайшую к желаемой из поддерживаемых устройством.
 #[tauri::command]
 pub fn start_recording(
     state: State<'_, Arc<Mutex<AudioCapture>>>,
+    session: State<'_, SharedRecordingSession>,
     device: String,
-) -> Result<String, String> {
-    let stream = start_audio_capture_with_stream(state, device)?;
+    preferences: Option<StreamPreferences>,
+    app: AppHandle,
+) -> Result<DeviceConfig, String> {
+    let config = session
+        .lock()
+        .map_err(|_| "Failed to lock recording session".to_string())?
+        .start(state.inner().clone(), device.clone(), preferences.unwrap_or_default(), app.clone())?;
     
-    log::info!("Audio capture started successfully.");
+    log::info!("Audio capture started successfully: {:?}", config);
+    remember_input_device(&state, &app, device);
+    Ok(config)
+}
+
+/// Сохраняет устройство, с которого началась запись, как выбранное по умолчанию.
+fn remember_input_device(state: &Arc<Mutex<AudioCapture>>, app: &AppHandle, device: String) {
+    let settings = match state.lock() {
+        Ok(mut capture) if capture.settings.input_device.as_ref() != Some(&device) => {
+            capture.settings.input_device = Some(device);
+            capture.settings.clone()
+        }
+        _ => return,
+    };
 
-    std::mem::forget(stream);
-    Ok("Recording started".to_string())
+    if let Err(e) = config::save_audio_settings(app, &settings) {
+        log::warn!("Failed to remember input device: {}", e);
+    }
 }
 
-/// Останавливает запись аудио и отправляет захваченные сэмплы на обработку.
+/// Останавливает запись аудио и отправляет захваченный дубль на обработку.
 /// 
-/// Извлекает сэмплы из буфера, валидирует их и отправляет в фоновый worker через mpsc канал.
+/// Закрывает stream, забирает записанный дубль, валидирует его и отправляет
+/// в фоновый worker через mpsc канал. Если включён pre-roll, микрофон
+/// снова открывается в режиме ожидания следующей записи.
+/// 
+/// Если дубль уже завершился авто-стопом, он в очереди обработки, и остановка
+/// просто подтверждает это.
 /// 
 /// Параметры:
-/// * `state` - глобальное состояние AudioCapture
-/// * `processing_sender` - канал для отправки сэмплов в background worker
+/// * `state` - глобальное состояние AudioCapture с настройками
+/// * `session` - сессия записи, владеющая cpal stream
+/// * `processing_sender` - канал для отправки дублей в background worker
+/// * `app` - AppHandle для событий stream pre-roll
 #[tauri::command]
 pub fn stop_recording(
     state: State<'_, Arc<Mutex<AudioCapture>>>,
-    processing_sender: State<'_, Sender<Vec<f32>>>,
+    session: State<'_, SharedRecordingSession>,
+    processing_sender: State<'_, Sender<TakeJob>>,
+    app: AppHandle,
 ) -> Result<(), String> {
-    stop_recording_inner(state.inner().clone(), processing_sender.inner().clone())
-}
+    let result = stop_recording_inner(state.inner(), session.inner(), processing_sender.inner().clone(), &app);
 
-/// Внутренняя функция остановки записи: координирует извлечение, валидацию и постановку в очередь.
-/// 
-/// Параметры:
-/// * `state_arc` - Arc на состояние AudioCapture
-/// * `sender` - mpsc канал для отправки сэмплов
-fn stop_recording_inner(
-    state_arc: Arc<Mutex<AudioCapture>>,
-    sender: Sender<Vec<f32>>,
-) -> Result<(), String> {
-    let samples = extract_audio_samples(&state_arc)?;
-    validate_samples(&samples)?;
-    queue_for_processing(sender, samples)
+    if let Ok(mut session) = session.lock() {
+        if let Err(e) = session.start_standby(state.inner().clone(), app) {
+            log::warn!("Failed to restart pre-roll capture: {}", e);
+        }
+    }
+    result
 }
 
-/// Останавливает запись и извлекает аудио сэмплы из буфера.
+/// Внутренняя функция остановки записи: координирует закрытие stream, извлечение,
+/// валидацию и постановку в очередь.
 /// 
-/// Устанавливает is_recording в false, очищает start_time и забирает буфер через mem::take.
+/// Дубль возвращает поток сессии после того как stream закрыт и кольцевой буфер
+/// опустошён, поэтому callback не может дописать сэмплы после извлечения.
+/// Сессия переходит из Finalizing в Queued, а если дубль не ушёл в очередь - в Idle.
 /// 
 /// Параметры:
-/// * `state_arc` - ссылка на Arc с состоянием AudioCapture
-fn extract_audio_samples(state_arc: &Arc<Mutex<AudioCapture>>) -> Result<Vec<f32>, String> {
-    let mut capture = state_arc
+/// * `state` - глобальное состояние AudioCapture
+/// * `session` - сессия записи, владеющая cpal stream
+/// * `sender` - mpsc канал для отправки дублей
+/// * `app` - AppHandle для события 'recording-state'
+fn stop_recording_inner(
+    state: &Arc<Mutex<AudioCapture>>,
+    session: &SharedRecordingSession,
+    sender: Sender<TakeJob>,
+    app: &AppHandle,
+) -> Result<(), String> {
+    let stopped = session
         .lock()
-        .map_err(|_| "Failed to acquire lock on AudioCapture state".to_string())?;
+        .map_err(|_| "Failed to lock recording session".to_string())?
+        .stop();
 
-    // Проверяем, что запись активна
-    let is_recording = *capture
-        .is_recording
-        .lock()
-        .map_err(|_| "Failed to lock is_recording flag".to_string())?;
-    
-    if !is_recording {
-        return Err("Recording is not in progress".to_string());
-    }
+    let job = match stopped {
+        Some(job) => job.and_then(|job| {
+            log::info!("Recording stopped, extracted {} track(s) ({:.1}s)", job.tracks.len(), job.duration_seconds());
+            validate_take(&job)?;
+            Ok(job)
+        }),
+        None => {
+            // Авто-стоп опередил остановку: дубль уже в очереди
+            let queued = state.lock().is_ok_and(|cap| cap.session_state == SessionState::Queued);
+            return if queued {
+                log::info!("Take was already queued by auto-stop");
+                Ok(())
+            } else {
+                Err("Recording is not in progress".to_string())
+            };
+        }
+    };
 
-    // Останавливаем запись
-    *capture
-        .is_recording
-        .lock()
-        .map_err(|_| "Failed to lock is_recording flag".to_string())? = false;
-    capture.start_time = None;
-
-    // Извлекаем сэмплы из буфера (без клонирования)
-    let samples = capture
-        .buffer
-        .lock()
-        .map_err(|_| "Failed to lock audio buffer".to_string())
-        .map(|mut buf| std::mem::take(&mut *buf))?;
-    
-    log::info!("Recording stopped, extracted {} samples", samples.len());
-    Ok(samples)
+    let job = match job {
+        Ok(job) => job,
+        Err(e) => {
+            set_state(state, Some(app), |cap| cap.transition(SessionState::Idle))?;
+            return Err(e);
+        }
+    };
+    // Дубль учитывается в очереди до отправки (см. `AudioCapture::queue_take`)
+    set_state(state, Some(app), AudioCapture::queue_take)?;
+    let queued = queue_for_processing(sender, job);
+    if queued.is_err() {
+        finish_job(state, Some(app));
+    }
+    queued
 }
 
-/// Проверяет что буфер содержит данные.
+/// Проверяет что дубль содержит данные.
 /// 
-/// Возвращает ошибку если массив сэмплов пуст.
+/// Возвращает ошибку если основная дорожка дубля пуста.
 /// 
 /// Параметры:
-/// * `samples` - срез аудиосэмплов для проверки
-fn validate_samples(samples: &[f32]) -> Result<(), String> {
-    if samples.is_empty() {
+/// * `job` - записанный дубль для проверки
+fn validate_take(job: &TakeJob) -> Result<(), String> {
+    if job.is_empty() {
         log::warn!("Recording stopped but buffer is empty");
         return Err("No audio data recorded".to_string());
     }
     Ok(())
 }
 
-/// Отправляет сэмплы в очередь обработки через mpsc канал.
+/// Отправляет дубль в очередь обработки через mpsc канал.
 /// 
 /// Использует try_send для неблокирующей отправки. Если канал полон, возвращает ошибку.
 /// 
 /// Параметры:
 /// * `sender` - mpsc sender для передачи данных в background worker
-/// * `samples` - вектор аудиосэмплов (f32) для обработки
-fn queue_for_processing(sender: Sender<Vec<f32>>, samples: Vec<f32>) -> Result<(), String> {
-    log::info!("Queueing {} samples for background processing", samples.len());
+/// * `job` - записанный дубль (одна или несколько дорожек) для обработки
+fn queue_for_processing(sender: Sender<TakeJob>, job: TakeJob) -> Result<(), String> {
+    log::info!("Queueing {:.1}s take ({} track(s)) for background processing", job.duration_seconds(), job.tracks.len());
     
     sender
-        .try_send(samples)
+        .try_send(job)
         .map_err(|e| format!("Failed to queue processing: {}", e))?;
     
     log::info!("Audio processing queued successfully");
@@ -1,27 +1,21 @@
-use cpal::{default_host, traits::{DeviceTrait, HostTrait}};
-use serde::Serialize;
-use pipe_trait::Pipe;
+use std::sync::{Arc, Mutex};
+use tauri::State;
 
-#[derive(Serialize)]
-pub struct InputDevice {
-    pub name: String,
-}
+pub use crate::audio::devices::{DeviceCapabilities, InputDevice};
+use crate::audio::devices::{device_capabilities, find_input_device, list_input_devices};
+use crate::audio::loopback::{find_loopback_device, is_loopback_id};
+use crate::types::AudioCapture;
 
 /// Возвращает список всех доступных аудио входных устройств.
 /// 
 /// Использует cpal для получения списка устройств от системного хоста.
 /// Фильтрует устройства, которые не могут предоставить имя.
+/// Источники звука системы (monitor / loopback) идут в конце списка с `is_loopback`.
+/// Об изменениях списка frontend узнаёт из события 'devices-changed'.
 #[tauri::command]
 pub fn get_input_device_names() -> Result<Vec<InputDevice>, String> {
 	log::debug!("Retrieving input devices");
-	let host = default_host();
-	log::debug!("Using host: {:?}", host.id());
-
-	host.input_devices()
-        .map_err(|e| format!("Failed to retrieve input devices: {}", e))?
-        .filter_map(|device| device.name().ok().map(|name| InputDevice { name }))
-        .collect::<Vec<_>>()
-        .pipe(Ok)
+	list_input_devices()
 }
 
 /// Возвращает системное аудио входное устройство по умолчанию.
@@ -30,14 +24,57 @@ pub fn get_input_device_names() -> Result<Vec<InputDevice>, String> {
 #[tauri::command]
 pub async fn get_default_input_device_name() -> Result<InputDevice, String> {
 	log::debug!("Retrieving input devices");
-	let host = default_host();
-	log::debug!("Using host: {:?}", host.id());
+	default_input_device()
+}
+
+/// Возвращает поддерживаемые устройством частоты, количества каналов и форматы.
+/// 
+/// Параметры:
+/// * `device` - ID устройства (`InputDevice::id`)
+#[tauri::command]
+pub async fn get_device_capabilities(device: String) -> Result<DeviceCapabilities, String> {
+	log::debug!("Retrieving capabilities of {}", device);
+	device_capabilities(&device)
+}
 
-	let device = host.default_input_device().ok_or_else(|| {
-		format!("Device error: No default input device found")
-	})?;
+/// Возвращает устройство, выбранное пользователем в прошлый раз.
+/// 
+/// Сохранённый ID ищется с нечётким сравнением имён (см. `find_input_device`),
+/// поэтому выбор переживает перезагрузку. Если устройство не найдено,
+/// возвращается устройство по умолчанию.
+/// 
+/// Параметры:
+/// * `state` - глобальное состояние AudioCapture с настройками
+#[tauri::command]
+pub async fn get_preferred_input_device(
+	state: State<'_, Arc<Mutex<AudioCapture>>>,
+) -> Result<InputDevice, String> {
+	let preferred = state
+		.lock()
+		.map_err(|_| "Failed to lock audio state".to_string())?
+		.settings
+		.input_device
+		.clone();
 
-	let device_name = device.name().map_err(|e| format!("Failed to get device name: {}", e))?;
+	if let Some(id) = preferred {
+		if is_loopback_id(&id) {
+			match find_loopback_device(&id) {
+				Some(device) => return Ok(device),
+				None => log::warn!("Preferred loopback source {:?} is unavailable", id),
+			}
+		} else {
+			match find_input_device(&id) {
+				Ok((_, device)) => return Ok(device),
+				Err(e) => log::warn!("Preferred input device {:?} is unavailable: {}", id, e),
+			}
+		}
+	}
+	default_input_device()
+}
 
-	Ok(InputDevice { name: device_name })
-}
\ No newline at end of file
+fn default_input_device() -> Result<InputDevice, String> {
+	list_input_devices()?
+		.into_iter()
+		.find(|device| device.is_default)
+		.ok_or_else(|| "Device error: No default input device found".to_string())
+}
@@ -1,6 +1,7 @@
 pub mod audio;
 pub mod device;
 pub mod recognition;
+pub mod settings;
 pub mod system;
 pub mod setup;
 
@@ -8,5 +9,6 @@ pub mod setup;
 pub use audio::*;
 pub use device::*;
 pub use recognition::*;
+pub use settings::*;
 pub use setup::*;
 
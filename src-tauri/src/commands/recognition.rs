@@ -1,7 +1,10 @@
 use std::path::Path;
-use tauri::{AppHandle, Emitter};
-use serde::Serialize;
+use std::sync::{Arc, Mutex};
+use tauri::{AppHandle, Emitter, State};
+use serde::{Deserialize, Serialize};
+use crate::recognition::transcript::{merge_tracks, Segment};
 use crate::recognition::whisper;
+use crate::types::AudioCapture;
 
 #[derive(Debug, Clone, Serialize)]
 pub struct RecognitionResult {
@@ -15,13 +18,16 @@ pub struct RecognitionResult {
 /// 
 /// Параметры:
 /// * `audio_path` - путь к обработанному WAV файлу (16kHz, mono)
+/// * `state` - глобальное состояние AudioCapture (язык распознавания из настроек)
 /// * `app` - AppHandle для отправки событий во frontend
 #[tauri::command]
 pub async fn recognize_audio(
     audio_path: String,
+    state: State<'_, Arc<Mutex<AudioCapture>>>,
     app: AppHandle,
 ) -> Result<String, String> {
     log::info!("Starting Whisper recognition for: {}", audio_path);
+    let language = recognition_language(&state)?;
     
     let path = Path::new(&audio_path);
     
@@ -33,9 +39,7 @@ pub async fn recognize_audio(
     // Запускаем распознавание в отдельном потоке, чтобы не блокировать
     let path_owned = path.to_path_buf();
     let full_text = tokio::task::spawn_blocking(move || {
-        // По умолчанию используем русский язык
-        // TODO: сделать язык настраиваемым через параметры
-        whisper::recognize(&path_owned, "ru")
+        whisper::recognize(&path_owned, &language)
     })
     .await
     .map_err(|e| format!("Recognition task failed: {}", e))??;
@@ -57,6 +61,83 @@ pub async fn recognize_audio(
     Ok(full_text)
 }
 
+/// Обработанная дорожка многодорожечного дубля (`ProcessedTrack` worker'а).
+#[derive(Debug, Clone, Deserialize)]
+pub struct TrackAudio {
+    /// Метка дорожки ("Me", "Them")
+    pub label: String,
+    /// Путь к обработанному WAV файлу дорожки
+    pub audio_path: String,
+    /// Сколько миллисекунд тишины обрезано в начале дорожки (`TrimOffsets::start_ms`)
+    #[serde(default)]
+    pub offset_ms: i64,
+}
+
+/// Распознаёт дорожки записи звонка и сводит их в расшифровку диалога.
+/// 
+/// Каждая дорожка распознаётся отдельно, фрагменты упорядочиваются по времени
+/// и подписываются меткой дорожки: `Me: ...` / `Them: ...` (см. `merge_tracks`).
+/// Дорожки обрезаются по-разному, поэтому время фрагментов сначала сдвигается
+/// на `offset_ms` - ко времени от начала дубля.
+/// После распознавания эмитит 'recognition-completed' с расшифровкой и путём
+/// к первой дорожке.
+/// 
+/// Параметры:
+/// * `tracks` - дорожки из события 'tracks-processing-finished'
+/// * `state` - глобальное состояние AudioCapture (язык распознавания из настроек)
+/// * `app` - AppHandle для отправки событий во frontend
+#[tauri::command]
+pub async fn recognize_tracks(
+    tracks: Vec<TrackAudio>,
+    state: State<'_, Arc<Mutex<AudioCapture>>>,
+    app: AppHandle,
+) -> Result<String, String> {
+    log::info!("Starting Whisper recognition for {} tracks", tracks.len());
+    let language = recognition_language(&state)?;
+    let audio_path = tracks
+        .first()
+        .map(|track| track.audio_path.clone())
+        .ok_or_else(|| "No tracks to recognize".to_string())?;
+    
+    let transcript = tokio::task::spawn_blocking(move || {
+        let recognized = tracks
+            .into_iter()
+            .map(|track| {
+                let segments: Vec<Segment> = whisper::recognize_segments(Path::new(&track.audio_path), &language)?
+                    .into_iter()
+                    .map(|segment| Segment {
+                        start_ms: segment.start_ms + track.offset_ms,
+                        end_ms: segment.end_ms + track.offset_ms,
+                        ..segment
+                    })
+                    .collect();
+                log::info!("Track {}: {} segments", track.label, segments.len());
+                Ok((track.label, segments))
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+        Ok::<_, String>(merge_tracks(&recognized))
+    })
+    .await
+    .map_err(|e| format!("Recognition task failed: {}", e))??;
+    
+    log::info!("Transcript completed. Text length: {}", transcript.len());
+    
+    let result = RecognitionResult {
+        text: transcript.clone(),
+        audio_path,
+    };
+    app.emit("recognition-completed", &result)
+        .map_err(|e| format!("Failed to emit recognition-completed event: {}", e))?;
+    
+    Ok(transcript)
+}
+
+/// Язык распознавания из настроек (`AudioSettings::recognition_language`).
+fn recognition_language(state: &State<'_, Arc<Mutex<AudioCapture>>>) -> Result<String, String> {
+    let capture = state.lock().map_err(|_| "Failed to lock audio state".to_string())?;
+    Ok(capture.settings.recognition_language.clone())
+}
+
 /// Инициализирует модель Whisper
 /// 
 /// Параметры:
@@ -0,0 +1,144 @@
+use std::sync::{Arc, Mutex};
+use tauri::{AppHandle, State};
+
+use crate::audio::pipeline::StageConfig;
+use crate::audio::session::SharedRecordingSession;
+use crate::types::AudioCapture;
+use crate::utils::config::{self, AudioSettings};
+
+/// Максимальная длительность pre-roll: буфер держится в памяти постоянно
+const MAX_PRE_ROLL_SECONDS: u32 = 10;
+
+/// Возвращает текущие настройки записи.
+#[tauri::command]
+pub fn get_audio_settings(state: State<'_, Arc<Mutex<AudioCapture>>>) -> Result<AudioSettings, String> {
+    let capture = state.lock().map_err(|_| "Failed to lock audio state".to_string())?;
+    Ok(capture.settings.clone())
+}
+
+/// Обновляет и сохраняет настройки записи.
+/// 
+/// Новые значения применяются со следующей записи: текущая запись
+/// продолжается с настройками, прочитанными при старте.
+/// Stream pre-roll переоткрывается с новыми настройками, а при выключении
+/// `pre_roll_enabled` микрофон сразу закрывается.
+/// 
+/// Параметры:
+/// * `state` - глобальное состояние AudioCapture
+/// * `session` - сессия записи, владеющая stream pre-roll
+/// * `app` - AppHandle для пути к директории конфигурации
+/// * `settings` - новые настройки
+#[tauri::command]
+pub fn update_audio_settings(
+    state: State<'_, Arc<Mutex<AudioCapture>>>,
+    session: State<'_, SharedRecordingSession>,
+    app: AppHandle,
+    settings: AudioSettings,
+) -> Result<AudioSettings, String> {
+    validate_audio_settings(&settings)?;
+
+    config::save_audio_settings(&app, &settings).map_err(|e| e.to_string())?;
+    state
+        .lock()
+        .map_err(|_| "Failed to lock audio state".to_string())?
+        .settings = settings.clone();
+
+    log::info!("Audio settings updated: {:?}", settings);
+
+    let mut session = session.lock().map_err(|_| "Failed to lock recording session".to_string())?;
+    session.stop_standby(state.inner());
+    if let Err(e) = session.start_standby(state.inner().clone(), app) {
+        log::warn!("Failed to start pre-roll capture: {}", e);
+    }
+    Ok(settings)
+}
+
+/// Возвращает цепочку обработки профиля.
+/// 
+/// Параметры:
+/// * `state` - глобальное состояние AudioCapture
+/// * `profile` - имя профиля (None - выбранный профиль)
+#[tauri::command]
+pub fn get_processing_chain(
+    state: State<'_, Arc<Mutex<AudioCapture>>>,
+    profile: Option<String>,
+) -> Result<Vec<StageConfig>, String> {
+    let capture = state.lock().map_err(|_| "Failed to lock audio state".to_string())?;
+    let name = profile.unwrap_or_else(|| capture.settings.processing_profile.clone());
+    capture.settings.processing_profiles
+        .get(&name)
+        .map(|profile| profile.stages.clone())
+        .ok_or_else(|| format!("Unknown processing profile: {}", name))
+}
+
+/// Заменяет цепочку обработки профиля и сохраняет настройки.
+/// 
+/// Новая цепочка применяется к следующему обработанному дублю, в том числе
+/// уже стоящему в очереди. Профиля с таким именем может не быть - тогда он создаётся.
+/// 
+/// Параметры:
+/// * `state` - глобальное состояние AudioCapture
+/// * `app` - AppHandle для пути к директории конфигурации
+/// * `profile` - имя профиля
+/// * `stages` - стадии по порядку
+#[tauri::command]
+pub fn update_processing_chain(
+    state: State<'_, Arc<Mutex<AudioCapture>>>,
+    app: AppHandle,
+    profile: String,
+    stages: Vec<StageConfig>,
+) -> Result<Vec<StageConfig>, String> {
+    if profile.trim().is_empty() {
+        return Err("Processing profile name must not be empty".to_string());
+    }
+    validate_stages(&stages)?;
+
+    let settings = {
+        let mut capture = state.lock().map_err(|_| "Failed to lock audio state".to_string())?;
+        let mut settings = capture.settings.clone();
+        settings.processing_profiles.entry(profile.clone()).or_default().stages = stages.clone();
+        config::save_audio_settings(&app, &settings).map_err(|e| e.to_string())?;
+        capture.settings = settings.clone();
+        settings
+    };
+
+    log::info!("Processing chain of profile {:?} updated: {:?}", profile, settings.processing_profiles[&profile].stages);
+    Ok(stages)
+}
+
+/// Проверяет параметры каждой стадии цепочки.
+fn validate_stages(stages: &[StageConfig]) -> Result<(), String> {
+    stages.iter().try_for_each(StageConfig::validate)
+}
+
+/// Проверяет, что настройки записи допустимы.
+fn validate_audio_settings(settings: &AudioSettings) -> Result<(), String> {
+    if settings.max_record_seconds == Some(0) {
+        return Err("Max recording duration must be greater than zero".to_string());
+    }
+    if settings.spill_after_seconds == 0 {
+        return Err("Spill threshold must be at least one second".to_string());
+    }
+    if !(-120.0..=0.0).contains(&settings.noise_floor_dbfs) {
+        return Err("Noise floor must be between -120 and 0 dBFS".to_string());
+    }
+    if !(1..=MAX_PRE_ROLL_SECONDS).contains(&settings.pre_roll_seconds) {
+        return Err(format!("Pre-roll must be between 1 and {} seconds", MAX_PRE_ROLL_SECONDS));
+    }
+    if !(200..=10_000).contains(&settings.vad_trailing_silence_ms) {
+        return Err("Trailing silence must be between 200 and 10000 ms".to_string());
+    }
+    if !(3.0..=40.0).contains(&settings.vad_threshold_db) {
+        return Err("Speech threshold must be between 3 and 40 dB".to_string());
+    }
+    if settings.recognition_language.trim().is_empty() {
+        return Err("Recognition language must not be empty".to_string());
+    }
+    if !settings.processing_profiles.contains_key(&settings.processing_profile) {
+        return Err(format!("Unknown processing profile: {}", settings.processing_profile));
+    }
+    for (name, profile) in &settings.processing_profiles {
+        validate_stages(&profile.stages).map_err(|e| format!("Profile {}: {}", name, e))?;
+    }
+    Ok(())
+}
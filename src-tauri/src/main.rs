@@ -10,13 +10,17 @@ mod utils;
 use std::sync::{Arc, Mutex};
 use dotenv::dotenv;
 use crate::types::AudioCapture;
-use crate::commands::device::{get_default_input_device_name, get_input_device_names};
-use crate::commands::audio::{start_recording, stop_recording, get_recording_status};
-use crate::commands::recognition::{recognize_audio, init_whisper};
+use crate::audio::recording::TakeJob;
+use crate::audio::session::RecordingSession;
+use crate::commands::device::{get_default_input_device_name, get_input_device_names, get_preferred_input_device, get_device_capabilities};
+use crate::commands::audio::{start_recording, stop_recording, pause_recording, resume_recording, get_recording_status};
+use crate::commands::recognition::{recognize_audio, recognize_tracks, init_whisper};
+use crate::commands::settings::{get_audio_settings, get_processing_chain, update_audio_settings, update_processing_chain};
 use crate::commands::setup::{initialize_app, get_setup_status, download_model, get_available_models};
 use crate::recognition::models::ModelSize;
 use crate::recognition::whisper;
 use crate::utils::cache::AudioCache;
+use crate::utils::config;
 use crate::utils::setup as setup_utils;
 use tokio::sync::mpsc;
 use std::time::Instant;
@@ -30,25 +34,41 @@ async fn main() {
     env_logger::init();
     
     let capture = Arc::new(Mutex::new(AudioCapture::default()));
-    
+
     // Создаём кэш для временных WAV файлов
     let cache = Arc::new(AudioCache::new().expect("Failed to create audio cache"));
 
     // Создаём канал для очереди задач обработки
-    let (tx, rx) = mpsc::channel::<Vec<f32>>(4);
+    let (tx, rx) = mpsc::channel::<TakeJob>(4);
+
+    // Сессия записи владеет cpal stream и закрывает его при остановке/выходе.
+    // Длинные записи сбрасываются во временные файлы кэша,
+    // дубли режима hands-free сессия сама ставит в очередь обработки
+    let session = Arc::new(Mutex::new(RecordingSession::new(cache.clone(), tx.clone())));
+    let session_for_setup = session.clone();
 
     // Собираем и запускаем приложение Tauri
     tauri::Builder::default()
         .manage(capture.clone())
+        .manage(session.clone())
         .manage(cache.clone())
         .manage(tx)
         .invoke_handler(tauri::generate_handler![
             get_default_input_device_name,
             get_input_device_names,
+            get_preferred_input_device,
+            get_device_capabilities,
             start_recording,
             stop_recording,
+            pause_recording,
+            resume_recording,
             get_recording_status,
+            get_audio_settings,
+            update_audio_settings,
+            get_processing_chain,
+            update_processing_chain,
             recognize_audio,
+            recognize_tracks,
             init_whisper,
             initialize_app,
             get_setup_status,
@@ -56,6 +76,21 @@ async fn main() {
             get_available_models
         ])
         .setup(move |app| {
+            // Загружаем сохранённые настройки записи
+            if let Ok(mut cap) = capture.lock() {
+                cap.settings = config::load_audio_settings(app.handle());
+            }
+
+            // Открываем микрофон заранее, если включён pre-roll
+            if let Ok(mut session) = session_for_setup.lock() {
+                if let Err(e) = session.start_standby(capture.clone(), app.handle().clone()) {
+                    log::warn!("Failed to start pre-roll capture: {}", e);
+                }
+            }
+
+            // Следим за подключением/отключением аудиоустройств
+            crate::audio::devices::spawn_device_watcher(capture.clone(), app.handle().clone());
+
             // Запускаем воркер обработки в фоне, передаём rx, capture и cache
             let handle = app.handle().clone();
             let capture_for_worker = capture.clone();
@@ -100,6 +135,16 @@ async fn main() {
             });
             Ok(())
         })
-        .run(tauri::generate_context!())
-        .expect("Error while running Tauri application");
+        .build(tauri::generate_context!())
+        .expect("Error while building Tauri application")
+        .run(move |_app, event| {
+            if let tauri::RunEvent::Exit = event {
+                // Освобождаем устройство, если запись или pre-roll ещё идёт
+                if let Ok(mut session) = session.lock() {
+                    if session.stop().is_some() {
+                        log::info!("Recording session closed on exit");
+                    }
+                }
+            }
+        });
 }
@@ -1,3 +1,4 @@
 pub mod whisper;
 pub mod models;
 pub mod postprocess;
+pub mod transcript;
@@ -0,0 +1,86 @@
+use crate::recognition::postprocess;
+
+/// Фрагмент распознанной речи с временем относительно начала дорожки.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Segment {
+    pub start_ms: i64,
+    pub end_ms: i64,
+    pub text: String,
+}
+
+/// Сводит распознанные дорожки в расшифровку диалога.
+/// 
+/// Дорожки одного дубля начинаются одновременно, поэтому фрагменты всех
+/// дорожек сортируются по времени начала. Подряд идущие фрагменты одной
+/// дорожки объединяются в одну реплику `<метка>: <текст>`, реплики
+/// разделяются переводом строки. Текст реплики проходит постобработку,
+/// реплики, от которых ничего не осталось, пропускаются.
+/// 
+/// Точность выравнивания: дорожки начинают и останавливают запись по общему
+/// флагу, который каждая замечает за интервал опроса (~10 мс), поэтому
+/// старт и каждая пауза дают расхождение до ~10 мс. Переоткрытие stream
+/// дорожки звонка заполняется тишиной, а переоткрытие или замена устройства
+/// основного источника - нет: его реплики после сбоя идут раньше на время
+/// переоткрытия (до ~1 с). На порядок реплик это влияет, только если они
+/// ближе друг к другу, чем эта ошибка.
+/// 
+/// Параметры:
+/// * `tracks` - пары (метка дорожки, фрагменты дорожки)
+pub fn merge_tracks(tracks: &[(String, Vec<Segment>)]) -> String {
+    let mut segments: Vec<(&str, &Segment)> = tracks
+        .iter()
+        .flat_map(|(label, segments)| segments.iter().map(move |segment| (label.as_str(), segment)))
+        .filter(|(_, segment)| !segment.text.trim().is_empty())
+        .collect();
+    // Стабильная сортировка: при равном времени порядок дорожек сохраняется
+    segments.sort_by_key(|(_, segment)| (segment.start_ms, segment.end_ms));
+
+    let mut turns: Vec<(&str, String)> = Vec::new();
+    for (label, segment) in segments {
+        match turns.last_mut() {
+            Some((last_label, text)) if *last_label == label => {
+                text.push(' ');
+                text.push_str(segment.text.trim());
+            }
+            _ => turns.push((label, segment.text.trim().to_string())),
+        }
+    }
+
+    turns
+        .into_iter()
+        .map(|(label, text)| (label, postprocess::process_text(&text)))
+        .filter(|(_, text)| !text.is_empty())
+        .map(|(label, text)| format!("{}: {}", label, text))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn segment(start_ms: i64, text: &str) -> Segment {
+        Segment { start_ms, end_ms: start_ms + 1000, text: text.to_string() }
+    }
+
+    #[test]
+    fn test_merge_interleaves_by_time() {
+        let tracks = vec![
+            ("Me".to_string(), vec![segment(0, "привет"), segment(1200, "как дела?"), segment(5000, "отлично")]),
+            ("Them".to_string(), vec![segment(2500, "хорошо, а у тебя?")]),
+        ];
+        assert_eq!(
+            merge_tracks(&tracks),
+            "Me: Привет как дела?\nThem: Хорошо, а у тебя?\nMe: Отлично"
+        );
+    }
+
+    #[test]
+    fn test_merge_skips_empty_segments() {
+        let tracks = vec![
+            ("Me".to_string(), vec![segment(0, "  ")]),
+            ("Them".to_string(), vec![segment(100, "да")]),
+        ];
+        assert_eq!(merge_tracks(&tracks), "Them: Да");
+    }
+}
@@ -1,7 +1,9 @@
 use std::path::Path;
 use whisper_rs::{FullParams, SamplingStrategy};
 use crate::recognition::models::{get_model, initialize_model, ModelSize};
+use crate::audio::resample::{resample, TARGET_SAMPLE_RATE};
 use crate::recognition::postprocess;
+use crate::recognition::transcript::Segment;
 
 /// Инициализирует Whisper с заданной моделью
 pub fn init(model_size: ModelSize) -> Result<(), String> {
@@ -14,6 +16,35 @@ pub fn init(model_size: ModelSize) -> Result<(), String> {
 /// * `audio_path` - путь к WAV файлу (должен быть 16kHz, mono, 16-bit)
 /// * `language` - язык распознавания ("ru", "en", "auto" для автоопределения)
 pub fn recognize(audio_path: &Path, language: &str) -> Result<String, String> {
+    let segments = recognize_segments(audio_path, language)?;
+    let full_text = segments
+        .iter()
+        .map(|segment| segment.text.as_str())
+        .collect::<Vec<_>>()
+        .join(" ");
+    let full_text = full_text.trim().to_string();
+    
+    log::info!("Recognition completed. Text length: {}", full_text.len());
+    
+    // Постобработка текста
+    let processed_text = postprocess::process_text(&full_text);
+    if processed_text.trim().is_empty() && !full_text.trim().is_empty() {
+        log::info!(
+            "Postprocess removed all text. Raw result was: {}",
+            full_text
+        );
+    }
+    
+    Ok(processed_text)
+}
+
+/// Распознает речь из WAV файла и возвращает фрагменты с временем начала и конца
+/// (без постобработки текста).
+/// 
+/// Параметры:
+/// * `audio_path` - путь к WAV файлу (должен быть 16kHz, mono, 16-bit)
+/// * `language` - язык распознавания ("ru", "en", "auto" для автоопределения)
+pub fn recognize_segments(audio_path: &Path, language: &str) -> Result<Vec<Segment>, String> {
     log::info!("Recognizing audio from: {:?}, language: {}", audio_path, language);
     
     // Проверяем файл
@@ -25,7 +56,7 @@ pub fn recognize(audio_path: &Path, language: &str) -> Result<String, String> {
     log::info!("Loading audio samples from file...");
     let audio_data = load_audio_samples(audio_path)?;
     log::info!("Audio loaded: {} samples", audio_data.len());
-    let audio_data = pad_audio_min_duration(audio_data, 16000, 1.1);
+    let audio_data = pad_audio_min_duration(audio_data, TARGET_SAMPLE_RATE, 1.1);
     
     // Получаем модель
     log::info!("Getting Whisper model lock...");
@@ -76,33 +107,23 @@ pub fn recognize(audio_path: &Path, language: &str) -> Result<String, String> {
         .map_err(|e| format!("Whisper recognition failed: {}", e))?;
     log::info!("Whisper recognition finished, extracting segments...");
     
-    // Собираем результат
+    // Собираем результат (время Whisper - в сотых долях секунды)
     let num_segments = state.full_n_segments()
         .map_err(|e| format!("Failed to get segment count: {}", e))?;
     
-    let mut full_text = String::new();
+    let mut segments = Vec::with_capacity(num_segments.max(0) as usize);
     
     for i in 0..num_segments {
-        let segment = state.full_get_segment_text(i)
+        let text = state.full_get_segment_text(i)
             .map_err(|e| format!("Failed to get segment {}: {}", i, e))?;
-        full_text.push_str(&segment);
-        full_text.push(' ');
+        let start = state.full_get_segment_t0(i)
+            .map_err(|e| format!("Failed to get segment {} start: {}", i, e))?;
+        let end = state.full_get_segment_t1(i)
+            .map_err(|e| format!("Failed to get segment {} end: {}", i, e))?;
+        segments.push(Segment { start_ms: start * 10, end_ms: end * 10, text });
     }
     
-    let full_text = full_text.trim().to_string();
-    
-    log::info!("Recognition completed. Text length: {}", full_text.len());
-    
-    // Постобработка текста
-    let processed_text = postprocess::process_text(&full_text);
-    if processed_text.trim().is_empty() && !full_text.trim().is_empty() {
-        log::info!(
-            "Postprocess removed all text. Raw result was: {}",
-            full_text
-        );
-    }
-    
-    Ok(processed_text)
+    Ok(segments)
 }
 
 /// Загружает аудио данные из WAV файла и конвертирует в формат для Whisper
@@ -114,12 +135,13 @@ fn load_audio_samples(path: &Path) -> Result<Vec<f32>, String> {
     let spec = reader.spec();
     log::debug!("WAV spec: {:?}", spec);
     
-    // Whisper требует 16kHz mono
-    let needs_resample = spec.sample_rate != 16000;
+    // Whisper требует 16kHz mono. Записи приложения worker уже ресэмплировал,
+    // ресэмплинг здесь нужен только для сторонних файлов
+    let needs_resample = spec.sample_rate != TARGET_SAMPLE_RATE;
     if needs_resample {
         log::warn!(
-            "Audio sample rate is {} Hz, resampling to 16000 Hz for Whisper.",
-            spec.sample_rate
+            "Audio sample rate is {} Hz, resampling to {} Hz for Whisper.",
+            spec.sample_rate, TARGET_SAMPLE_RATE
         );
     }
     
@@ -131,47 +153,42 @@ fn load_audio_samples(path: &Path) -> Result<Vec<f32>, String> {
         );
     }
     
-    // Читаем семплы и нормализуем в диапазон [-1.0, 1.0]
-    let samples: Result<Vec<f32>, _> = match spec.sample_format {
+    // Читаем семплы и нормализуем в диапазон [-1.0, 1.0].
+    // Записи приложения уже сведены в моно при захвате (см. audio::downmix).
+    // Для сторонних многоканальных файлов берем только первый канал прямо при чтении,
+    // чтобы длинные многоканальные записи не загружались в память целиком
+    let step = spec.channels.max(1) as usize;
+    let mono_samples: Vec<f32> = match spec.sample_format {
         hound::SampleFormat::Float => {
             reader.samples::<f32>()
+                .step_by(step)
                 .map(|s| s.map_err(|e| format!("Sample read error: {}", e)))
-                .collect()
+                .collect::<Result<_, _>>()?
         }
         hound::SampleFormat::Int => {
             match spec.bits_per_sample {
                 16 => {
                     reader.samples::<i16>()
+                        .step_by(step)
                         .map(|s| s.map(|s| s as f32 / 32768.0)
                             .map_err(|e| format!("Sample read error: {}", e)))
-                        .collect()
+                        .collect::<Result<_, _>>()?
                 }
                 32 => {
                     reader.samples::<i32>()
+                        .step_by(step)
                         .map(|s| s.map(|s| s as f32 / 2147483648.0)
                             .map_err(|e| format!("Sample read error: {}", e)))
-                        .collect()
+                        .collect::<Result<_, _>>()?
                 }
-                _ => Err(format!("Unsupported bits per sample: {}", spec.bits_per_sample)),
+                _ => return Err(format!("Unsupported bits per sample: {}", spec.bits_per_sample)),
             }
         }
     };
     
-    let samples = samples?;
-    
-    // Если стерео, берем только первый канал
-    let mono_samples = if spec.channels > 1 {
-        samples.iter()
-            .step_by(spec.channels as usize)
-            .copied()
-            .collect()
-    } else {
-        samples
-    };
-    
     let resampled = if needs_resample {
-        log::info!("Resampling from {} Hz to 16000 Hz...", spec.sample_rate);
-        let result = resample_linear(&mono_samples, spec.sample_rate, 16000);
+        log::info!("Resampling from {} Hz to {} Hz...", spec.sample_rate, TARGET_SAMPLE_RATE);
+        let result = resample(&mono_samples, spec.sample_rate, TARGET_SAMPLE_RATE);
         log::info!("Resampling completed: {} -> {} samples", mono_samples.len(), result.len());
         result
     } else {
@@ -183,32 +200,6 @@ fn load_audio_samples(path: &Path) -> Result<Vec<f32>, String> {
     Ok(resampled)
 }
 
-/// Простая линейная ресэмплизация до целевой частоты
-fn resample_linear(input: &[f32], src_rate: u32, dst_rate: u32) -> Vec<f32> {
-    if input.is_empty() || src_rate == dst_rate {
-        return input.to_vec();
-    }
-
-    let src_rate = src_rate as f64;
-    let dst_rate = dst_rate as f64;
-    let ratio = src_rate / dst_rate;
-    let out_len = ((input.len() as f64) / ratio).ceil() as usize;
-    let mut output = Vec::with_capacity(out_len);
-
-    for i in 0..out_len {
-        let src_pos = (i as f64) * ratio;
-        let idx = src_pos.floor() as usize;
-        let frac = src_pos - (idx as f64);
-
-        let s0 = input.get(idx).copied().unwrap_or(0.0);
-        let s1 = input.get(idx + 1).copied().unwrap_or(s0);
-
-        output.push(s0 + (s1 - s0) * (frac as f32));
-    }
-
-    output
-}
-
 /// Дополняет аудио тишиной до минимальной длительности
 fn pad_audio_min_duration(mut input: Vec<f32>, sample_rate: u32, min_seconds: f32) -> Vec<f32> {
     if input.is_empty() {
@@ -1,4 +1,8 @@
-use std::{sync::{Arc, Mutex}, time::Instant};
+use std::{sync::{atomic::{AtomicBool, AtomicU64, Ordering}, Arc}, time::{Duration, Instant}};
+use serde::Serialize;
+use crate::audio::capture::StreamErrorSlot;
+use crate::audio::state::{SessionState, StateChange};
+use crate::utils::config::AudioSettings;
 
 /// Структура для управления аудиозахватом
 /// 
@@ -6,32 +10,48 @@ use std::{sync::{Arc, Mutex}, time::Instant};
 /// - Частота дискретизации: 48000 Hz (выше 44100, улучшает детализацию)
 /// - Каналы: 1 (монo - стандарт для речи, предотвращает синхронизацию)
 /// - Формат: WAV без потерь (F32/I16)
-/// - Предотвращение клиппинга (target_rms = 0.12)
-/// - Адаптивная подстройка шума (noise_gate = 15% RMS)
+/// - Обработка дубля цепочкой стадий профиля: noise gate (15% RMS), АРУ (-18 dBFS),
+///   limiter (см. audio/pipeline.rs)
 #[allow(dead_code)]
 pub struct AudioCapture {
-    pub is_recording: Arc<Mutex<bool>>,       // Флаг записи
-    pub buffer: Arc<Mutex<Vec<f32>>>,        // Буфер для сэмплов
+    pub session_state: SessionState,         // Состояние сессии записи (переходы только через методы ниже)
+    pub pending_jobs: usize,                 // Дубли в очереди обработки: отправлены worker, но ещё не обработаны
+    pub is_recording: Arc<AtomicBool>,       // Идёт запись дубля (в том числе на паузе), повторяет session_state для потоков без мьютекса
+    pub is_capturing: Arc<AtomicBool>,       // Stream открыт и пишет в кольцевой буфер (читается из real-time callback)
+    pub overrun_samples: Arc<AtomicU64>,     // Сэмплы, потерянные из-за переполнения кольцевого буфера
+    pub device_lost: Arc<AtomicBool>,        // Активное устройство отключено (error callback / device watcher)
+    pub source_ended: Arc<AtomicBool>,       // Источник закончился (конец файла), новых сэмплов не будет
+    pub stream_error: StreamErrorSlot,       // Последняя ошибка stream, кроме отключения устройства (error callback)
+    pub is_paused: Arc<AtomicBool>,          // Запись на паузе: сэмплы из кольцевого буфера отбрасываются
+    pub paused_since: Option<Instant>,       // Начало текущей паузы
+    pub paused_total: Duration,              // Суммарная длительность завершённых пауз текущей записи
+    pub active_device: Option<String>,       // Имя устройства, с которого идёт запись
     pub sample_rate: u32,                    // Частота дискретизации (рекомендуется 48000 Hz для качества)
     pub channels: u16,                       // Количество каналов (1 = mono, оптимально для речи)
     pub start_time: Option<Instant>,         // Время старта записи
     pub volume_level: f32,                   // Последний уровень громкости
-    // Перенесённые свойства процессора
-    pub gain: f32,              // Адаптивный коэффициент усиления (целевой RMS: 0.12)
-    pub noise_threshold: f32,   // Адаптивный порог шума (15% от входного RMS)
     pub rms_input: f32,                      // RMS входного сигнала
     pub rms_output: f32,                     // RMS выходного сигнала после обработки
     pub buffer_duration_seconds: usize,
-    // Рекомендуемые параметры обработки:
-    pub peak_prevention_threshold: f32,      // Предотвращение клиппинга (обычно 0.95)
-    pub soft_noise_gate_factor: f32,         // Мягкий noise gate (оставляет % от тихих сэмплов)
+    // Параметры обработки - в профилях обработки (settings.processing_profiles)
+    pub settings: AudioSettings,             // Пользовательские настройки записи (длительность, сброс на диск)
 }
 
 impl Default for AudioCapture {
     fn default() -> Self {
         Self {
-            is_recording: Arc::new(Mutex::new(false)),
-            buffer: Arc::new(Mutex::new(Vec::new())),
+            session_state: SessionState::Idle,
+            pending_jobs: 0,
+            is_recording: Arc::new(AtomicBool::new(false)),
+            is_capturing: Arc::new(AtomicBool::new(false)),
+            overrun_samples: Arc::new(AtomicU64::new(0)),
+            device_lost: Arc::new(AtomicBool::new(false)),
+            source_ended: Arc::new(AtomicBool::new(false)),
+            stream_error: StreamErrorSlot::default(),
+            is_paused: Arc::new(AtomicBool::new(false)),
+            paused_since: None,
+            paused_total: Duration::ZERO,
+            active_device: None,
             // Использование 48000 Hz вместо 44100 для лучшей детализации речи
             // Whisper будет ресэмплировать до 16000 Hz без потери качества
             sample_rate: 48000,
@@ -39,17 +59,200 @@ impl Default for AudioCapture {
             channels: 1,
             start_time: None,
             volume_level: 1.0,
-            // Адаптивный gain (целевой RMS: 0.12 для оптимального распознавания)
-            gain: 1.0,
-            // Адаптивный noise threshold (вычисляется как 15% от входного RMS)
-            noise_threshold: 0.0,
             buffer_duration_seconds: 10,
-            // Предотвращение клиппинга: если амплитуда > 0.95, снижаем gain
-            peak_prevention_threshold: 0.95,
-            // Мягкий noise gate: оставляем 20% от очень тихих сэмплов для сохранения деталей
-            soft_noise_gate_factor: 0.2,
             rms_input: 0.0,
             rms_output: 0.0,
+            settings: AudioSettings::default(),
         }
     }
 }
+
+
+/// Статус записи для `get_recording_status`.
+#[derive(Debug, Clone, Serialize)]
+pub struct RecordingStatus {
+    /// Состояние сессии записи
+    pub state: SessionState,
+    /// Открыт ли stream (запись идёт или на паузе)
+    pub is_recording: bool,
+    /// Запись на паузе
+    pub is_paused: bool,
+    /// Устройство, с которого идёт запись
+    pub device: Option<String>,
+    /// Длительность записанного без учёта пауз, в секундах
+    pub elapsed_seconds: f64,
+    /// Ограничение длительности записи (None = без ограничения)
+    pub max_record_seconds: Option<u32>,
+    /// Сэмплов потеряно из-за переполнения кольцевого буфера
+    pub dropped_samples: u64,
+}
+
+impl AudioCapture {
+    /// Переход состояния без побочных действий (Finalizing -> Queued / Idle, Arming -> Idle и т.п.).
+    pub fn transition(&mut self, next: SessionState) -> Result<StateChange, String> {
+        let change = self.session_state.transition(next)?;
+        self.session_state = next;
+        Ok(change)
+    }
+
+    /// Дубль уходит в очередь обработки: Finalizing -> Queued.
+    /// 
+    /// Счётчик очереди растёт под тем же локом, что и переход, поэтому worker,
+    /// закончивший предыдущий дубль, не переведёт сессию в Idle, пока этот
+    /// дубль ещё не отправлен.
+    pub fn queue_take(&mut self) -> Result<StateChange, String> {
+        let change = self.transition(SessionState::Queued)?;
+        self.pending_jobs += 1;
+        Ok(change)
+    }
+
+    /// Дубль покинул очередь (обработан или не отправлен).
+    /// 
+    /// Когда очередь опустела, а новый дубль не начат, сессия переходит
+    /// из Queued в Idle - возвращает этот переход.
+    pub fn finish_job(&mut self) -> Option<StateChange> {
+        self.pending_jobs = self.pending_jobs.saturating_sub(1);
+        if self.pending_jobs > 0 || self.session_state != SessionState::Queued {
+            return None;
+        }
+        self.transition(SessionState::Idle).ok()
+    }
+
+    /// Запись запрошена: stream открывается (или уже открыт в режиме pre-roll).
+    pub fn arm(&mut self) -> Result<StateChange, String> {
+        self.transition(SessionState::Arming)
+    }
+
+    /// Отмечает начало записи дубля: сбрасывает счётчик потерь и паузу.
+    /// 
+    /// Stream к этому моменту уже открыт (сразу или в режиме pre-roll).
+    pub fn begin_recording(&mut self) -> Result<StateChange, String> {
+        let change = self.transition(SessionState::Recording)?;
+        self.overrun_samples.store(0, Ordering::Relaxed);
+        self.reset_pause();
+        self.start_time = Some(Instant::now());
+        self.is_recording.store(true, Ordering::Release);
+        Ok(change)
+    }
+
+    /// Ставит текущую запись на паузу.
+    /// 
+    /// Stream остаётся открытым, но поток сессии отбрасывает сэмплы до `resume`,
+    /// поэтому пауза не попадает в дубль и не расходует лимит длительности.
+    pub fn pause(&mut self) -> Result<StateChange, String> {
+        match self.session_state {
+            SessionState::Recording => {}
+            SessionState::Paused => return Err("Recording is already paused".to_string()),
+            _ => return Err("Recording is not in progress".to_string()),
+        }
+        let change = self.transition(SessionState::Paused)?;
+        self.is_paused.store(true, Ordering::Release);
+        self.paused_since = Some(Instant::now());
+        Ok(change)
+    }
+
+    /// Продолжает запись после паузы в тот же дубль.
+    pub fn resume(&mut self) -> Result<StateChange, String> {
+        match self.session_state {
+            SessionState::Paused => {}
+            SessionState::Recording => return Err("Recording is not paused".to_string()),
+            _ => return Err("Recording is not in progress".to_string()),
+        }
+        let change = self.transition(SessionState::Recording)?;
+        self.is_paused.store(false, Ordering::Release);
+        if let Some(since) = self.paused_since.take() {
+            self.paused_total += since.elapsed();
+        }
+        Ok(change)
+    }
+
+    /// Дубль закончен (остановка, авто-стоп, тишина): новые сэмплы в него не попадут.
+    pub fn finalize(&mut self) -> Result<StateChange, String> {
+        let change = self.transition(SessionState::Finalizing)?;
+        self.is_recording.store(false, Ordering::Release);
+        self.start_time = None;
+        self.reset_pause();
+        Ok(change)
+    }
+
+    /// Сбрасывает состояние паузы (начало и конец записи).
+    pub fn reset_pause(&mut self) {
+        self.is_paused.store(false, Ordering::Release);
+        self.paused_since = None;
+        self.paused_total = Duration::ZERO;
+    }
+
+    /// Длительность записи без учёта пауз.
+    pub fn elapsed_without_pauses(&self) -> Duration {
+        let Some(start) = self.start_time else {
+            return Duration::ZERO;
+        };
+        let paused = self.paused_total + self.paused_since.map_or(Duration::ZERO, |since| since.elapsed());
+        start.elapsed().saturating_sub(paused)
+    }
+
+    /// Текущий статус записи.
+    pub fn status(&self) -> RecordingStatus {
+        RecordingStatus {
+            state: self.session_state,
+            is_recording: self.session_state.is_recording(),
+            is_paused: self.session_state == SessionState::Paused,
+            device: self.active_device.clone(),
+            elapsed_seconds: self.elapsed_without_pauses().as_secs_f64(),
+            max_record_seconds: self.settings.max_record_seconds,
+            dropped_samples: self.overrun_samples.load(Ordering::Relaxed),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pause_requires_active_recording() {
+        let mut capture = AudioCapture::default();
+        assert!(capture.pause().is_err());
+
+        capture.arm().unwrap();
+        capture.begin_recording().unwrap();
+        capture.pause().unwrap();
+        assert!(capture.pause().is_err());
+        assert!(capture.status().is_paused);
+
+        capture.resume().unwrap();
+        assert!(capture.resume().is_err());
+        assert!(!capture.status().is_paused);
+    }
+
+    #[test]
+    fn test_paused_time_is_excluded() {
+        let mut capture = AudioCapture::default();
+        capture.arm().unwrap();
+        capture.begin_recording().unwrap();
+        capture.start_time = Some(Instant::now() - Duration::from_secs(10));
+        capture.pause().unwrap();
+        capture.paused_since = Some(Instant::now() - Duration::from_secs(4));
+
+        let elapsed = capture.elapsed_without_pauses().as_secs_f64();
+        assert!((elapsed - 6.0).abs() < 0.5, "elapsed {}", elapsed);
+
+        capture.resume().unwrap();
+        let elapsed = capture.elapsed_without_pauses().as_secs_f64();
+        assert!((elapsed - 6.0).abs() < 0.5, "elapsed {}", elapsed);
+    }
+
+    #[test]
+    fn test_finalize_clears_recording_flags() {
+        let mut capture = AudioCapture::default();
+        capture.arm().unwrap();
+        capture.begin_recording().unwrap();
+        capture.pause().unwrap();
+
+        capture.finalize().unwrap();
+        assert!(!capture.is_recording.load(Ordering::Acquire));
+        assert!(!capture.is_paused.load(Ordering::Acquire));
+        assert_eq!(capture.status().state, SessionState::Finalizing);
+        assert!(capture.finalize().is_err());
+    }
+}
@@ -1 +1,186 @@
 // Конфигурация
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager};
+use crate::audio::agc::AgcParams;
+use crate::audio::biquad::{DcBlockParams, HighPassParams};
+use crate::audio::denoise::DenoiseParams;
+use crate::audio::gate::GateParams;
+use crate::audio::limiter::LimiterParams;
+use crate::audio::loudness::LoudnessParams;
+use crate::audio::pipeline::StageConfig;
+use crate::audio::trim::TrimParams;
+use crate::audio::downmix::ChannelStrategy;
+use crate::utils::error::AppError;
+
+const AUDIO_SETTINGS_FILE: &str = "audio_settings.json";
+/// Профиль обработки, который выбран по умолчанию
+pub const DEFAULT_PROCESSING_PROFILE: &str = "default";
+
+/// Профиль обработки дубля в worker: цепочка стадий DSP.
+/// 
+/// Профили именованные, чтобы переключаться между условиями записи
+/// (тихая комната, open space) без перенастройки каждой стадии.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ProcessingProfile {
+    /// Стадии обработки по порядку
+    pub stages: Vec<StageConfig>,
+}
+
+impl Default for ProcessingProfile {
+    fn default() -> Self {
+        Self {
+            stages: vec![
+                StageConfig::DcBlock(DcBlockParams::default()),
+                StageConfig::HighPass(HighPassParams::default()),
+                StageConfig::NoiseGate(GateParams::default()),
+                StageConfig::Trim(TrimParams::default()),
+                StageConfig::Agc(AgcParams::default()),
+                StageConfig::Loudness(LoudnessParams::default()),
+                StageConfig::Limiter(LimiterParams::default()),
+            ],
+        }
+    }
+}
+
+/// Профили обработки по умолчанию: обычная обработка и шумное помещение
+/// (спектральное вычитание шума вместо noise gate). Смещение и гул убираются
+/// первыми, до всех оценок уровня. Тишина обрезается после подавления шума:
+/// шумоподавитель запоминает шум по началу записи. Нормализация громкости
+/// идёт после АРУ и доводит до цели уже выровненный сигнал; громкость дубля
+/// как записан для отчёта измеряет worker.
+fn default_processing_profiles() -> BTreeMap<String, ProcessingProfile> {
+    let noisy = ProcessingProfile {
+        stages: vec![
+            StageConfig::DcBlock(DcBlockParams::default()),
+            StageConfig::HighPass(HighPassParams::default()),
+            StageConfig::Denoise(DenoiseParams::default()),
+            StageConfig::Trim(TrimParams::default()),
+            StageConfig::Agc(AgcParams::default()),
+            StageConfig::Loudness(LoudnessParams::default()),
+            StageConfig::Limiter(LimiterParams::default()),
+        ],
+    };
+    BTreeMap::from([
+        (DEFAULT_PROCESSING_PROFILE.to_string(), ProcessingProfile::default()),
+        ("noisy_room".to_string(), noisy),
+    ])
+}
+
+/// Пользовательские настройки записи, сохраняются между запусками.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AudioSettings {
+    /// Максимальная длительность записи в секундах (None = без ограничения)
+    pub max_record_seconds: Option<u32>,
+    /// После скольких секунд запись сбрасывается из памяти во временный файл
+    pub spill_after_seconds: u32,
+    /// ID выбранного входного устройства (`InputDevice::id`)
+    pub input_device: Option<String>,
+    /// При отключении устройства во время записи переключаться на устройство по умолчанию
+    pub device_failover: bool,
+    /// Как сводить многоканальный вход в моно
+    pub channel_strategy: ChannelStrategy,
+    /// Уровень (RMS, dBFS), ниже которого вход считается тишиной в событиях 'audio-level'
+    pub noise_floor_dbfs: f32,
+    /// Держать микрофон открытым между записями, чтобы не терять начало фразы.
+    /// Выключено по умолчанию: пока настройка включена, микрофон слушает постоянно
+    pub pre_roll_enabled: bool,
+    /// Сколько секунд до нажатия записи добавляется в начало дубля
+    pub pre_roll_seconds: u32,
+    /// Режим hands-free: запись начинается с началом речи и заканчивается после тишины
+    pub hands_free: bool,
+    /// Сколько миллисекунд тишины завершают дубль в режиме hands-free
+    pub vad_trailing_silence_ms: u32,
+    /// На сколько dB речь должна быть громче фонового шума
+    pub vad_threshold_db: f32,
+    /// Запись звонка: ID второго источника (обычно звук системы), который пишется
+    /// отдельной дорожкой "Them" одновременно с микрофоном. None - выключено
+    pub call_track_device: Option<String>,
+    /// Имя профиля обработки, который применяет worker
+    pub processing_profile: String,
+    /// Профили обработки по имени
+    pub processing_profiles: BTreeMap<String, ProcessingProfile>,
+    /// Язык распознавания ("ru", "en", "auto" для автоопределения)
+    pub recognition_language: String,
+}
+
+impl Default for AudioSettings {
+    fn default() -> Self {
+        Self {
+            max_record_seconds: Some(30),
+            spill_after_seconds: 30,
+            input_device: None,
+            device_failover: true,
+            channel_strategy: ChannelStrategy::Average,
+            noise_floor_dbfs: -60.0,
+            pre_roll_enabled: false,
+            pre_roll_seconds: 2,
+            hands_free: false,
+            vad_trailing_silence_ms: 1000,
+            vad_threshold_db: 12.0,
+            call_track_device: None,
+            processing_profile: DEFAULT_PROCESSING_PROFILE.to_string(),
+            processing_profiles: default_processing_profiles(),
+            recognition_language: "ru".to_string(),
+        }
+    }
+}
+
+impl AudioSettings {
+    /// Выбранный профиль обработки (если его нет - профиль по умолчанию).
+    pub fn active_processing(&self) -> ProcessingProfile {
+        self.processing_profiles
+            .get(&self.processing_profile)
+            .cloned()
+            .unwrap_or_default()
+    }
+}
+
+/// Путь к файлу настроек в директории конфигурации приложения
+fn audio_settings_path(app_handle: &AppHandle) -> Result<PathBuf, AppError> {
+    let config_dir = app_handle
+        .path()
+        .app_config_dir()
+        .map_err(|e| AppError::IoError(format!("Failed to get app config dir: {}", e)))?;
+    Ok(config_dir.join(AUDIO_SETTINGS_FILE))
+}
+
+/// Загружает настройки записи. Если файла нет или он повреждён, возвращает значения по умолчанию.
+pub fn load_audio_settings(app_handle: &AppHandle) -> AudioSettings {
+    let path = match audio_settings_path(app_handle) {
+        Ok(path) => path,
+        Err(e) => {
+            log::warn!("Using default audio settings: {}", e);
+            return AudioSettings::default();
+        }
+    };
+
+    match fs::read_to_string(&path) {
+        Ok(content) => serde_json::from_str(&content).unwrap_or_else(|e| {
+            log::warn!("Invalid audio settings file {:?}, using defaults: {}", path, e);
+            AudioSettings::default()
+        }),
+        Err(_) => AudioSettings::default(),
+    }
+}
+
+/// Сохраняет настройки записи в директорию конфигурации приложения
+pub fn save_audio_settings(app_handle: &AppHandle, settings: &AudioSettings) -> Result<(), AppError> {
+    let path = audio_settings_path(app_handle)?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)
+            .map_err(|e| AppError::IoError(format!("Failed to create config dir: {}", e)))?;
+    }
+
+    let content = serde_json::to_string_pretty(settings)
+        .map_err(|e| AppError::IoError(format!("Failed to serialize audio settings: {}", e)))?;
+    fs::write(&path, content)
+        .map_err(|e| AppError::IoError(format!("Failed to write audio settings: {}", e)))?;
+
+    log::info!("Audio settings saved to {:?}", path);
+    Ok(())
+}
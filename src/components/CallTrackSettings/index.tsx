@@ -0,0 +1,66 @@
+import React, { useEffect, useState } from 'react'
+import { invoke } from '@tauri-apps/api/core'
+import { useAudioStore, type AudioSettings } from '../../stores/audioStore'
+
+export function CallTrackSettings(): React.ReactElement {
+  const { isRecording, availableDevices, selectedDevice } = useAudioStore()
+  const [settings, setSettings] = useState<AudioSettings | null>(null)
+
+  // Загружаем сохранённые настройки при монтировании
+  useEffect(() => {
+    invoke<AudioSettings>('get_audio_settings')
+      .then(setSettings)
+      .catch((err) => console.error('Failed to load audio settings:', err))
+  }, [])
+
+  const handleChange = async (event: React.ChangeEvent<HTMLSelectElement>) => {
+    if (!settings) return
+    const value = event.target.value
+    try {
+      // Берём актуальные настройки: выбранное устройство сохраняется при старте записи
+      const latest = await invoke<AudioSettings>('get_audio_settings')
+      const updated: AudioSettings = { ...latest, call_track_device: value === '' ? null : value }
+      setSettings(await invoke<AudioSettings>('update_audio_settings', { settings: updated }))
+    } catch (err) {
+      console.error('Failed to update audio settings:', err)
+    }
+  }
+
+  // Собеседника обычно слышно через звук системы, поэтому loopback источники идут первыми
+  const candidates = availableDevices
+    .filter(d => d.id !== selectedDevice?.id)
+    .sort((a, b) => Number(b.is_loopback) - Number(a.is_loopback))
+  const current = settings?.call_track_device ?? ''
+
+  return (
+    <div className="flex flex-col gap-2">
+      <label className="text-sm font-medium text-gray-700">
+        Запись звонка (дорожка собеседника)
+      </label>
+      <select
+        value={current}
+        onChange={handleChange}
+        disabled={!settings || isRecording}
+        className="px-3 py-2 border border-gray-300 rounded-lg bg-white text-gray-900
+                   disabled:bg-gray-100 disabled:cursor-not-allowed
+                   focus:ring-2 focus:ring-blue-500 focus:border-transparent
+                   transition-colors"
+      >
+        <option value="">Выключено</option>
+        {current !== '' && !candidates.some(d => d.id === current) && (
+          <option value={current}>{current} (недоступно)</option>
+        )}
+        {candidates.map((device) => (
+          <option key={device.id} value={device.id}>
+            {device.name}{device.is_loopback ? ' (звук системы)' : ''}
+          </option>
+        ))}
+      </select>
+      {current !== '' && (
+        <p className="text-xs text-gray-500">
+          Расшифровка делится на реплики «Me» (микрофон) и «Them» (собеседник)
+        </p>
+      )}
+    </div>
+  )
+}
@@ -0,0 +1,73 @@
+import React, { useEffect, useState } from 'react'
+import { invoke } from '@tauri-apps/api/core'
+import { useAudioStore, type AudioSettings, type ChannelStrategy } from '../../stores/audioStore'
+
+// Значение <option> для стратегии: 'average', 'loudest' или 'channel:N'
+function strategyToValue(strategy: ChannelStrategy): string {
+  return strategy.mode === 'channel' ? `channel:${strategy.index}` : strategy.mode
+}
+
+function valueToStrategy(value: string): ChannelStrategy {
+  if (value.startsWith('channel:')) {
+    return { mode: 'channel', index: Number(value.slice('channel:'.length)) }
+  }
+  return value === 'loudest' ? { mode: 'loudest' } : { mode: 'average' }
+}
+
+export function ChannelStrategySelector(): React.ReactElement {
+  const { isRecording, selectedDevice } = useAudioStore()
+  const [settings, setSettings] = useState<AudioSettings | null>(null)
+
+  // Загружаем сохранённые настройки при монтировании
+  useEffect(() => {
+    invoke<AudioSettings>('get_audio_settings')
+      .then(setSettings)
+      .catch((err) => console.error('Failed to load audio settings:', err))
+  }, [])
+
+  // Отдельные каналы предлагаем по конфигурации выбранного устройства (минимум стерео)
+  const channelCount = Math.max(selectedDevice?.default_config?.channels ?? 2, 2)
+  const current = settings ? strategyToValue(settings.channel_strategy) : 'average'
+  const channelValues = Array.from({ length: channelCount }, (_, i) => `channel:${i}`)
+  if (current.startsWith('channel:') && !channelValues.includes(current)) {
+    channelValues.push(current)
+  }
+
+  const handleStrategyChange = async (event: React.ChangeEvent<HTMLSelectElement>) => {
+    if (!settings) return
+    const value = event.target.value
+    try {
+      // Берём актуальные настройки: выбранное устройство сохраняется при старте записи
+      const latest = await invoke<AudioSettings>('get_audio_settings')
+      const updated: AudioSettings = { ...latest, channel_strategy: valueToStrategy(value) }
+      setSettings(await invoke<AudioSettings>('update_audio_settings', { settings: updated }))
+    } catch (err) {
+      console.error('Failed to update audio settings:', err)
+    }
+  }
+
+  return (
+    <div className="flex flex-col gap-2">
+      <label className="text-sm font-medium text-gray-700">
+        Каналы микрофона
+      </label>
+      <select
+        value={current}
+        onChange={handleStrategyChange}
+        disabled={!settings || isRecording}
+        className="px-3 py-2 border border-gray-300 rounded-lg bg-white text-gray-900
+                   disabled:bg-gray-100 disabled:cursor-not-allowed
+                   focus:ring-2 focus:ring-blue-500 focus:border-transparent
+                   transition-colors"
+      >
+        <option value="average">Среднее всех каналов</option>
+        <option value="loudest">Самый громкий канал</option>
+        {channelValues.map((value) => (
+          <option key={value} value={value}>
+            Только канал {Number(value.slice('channel:'.length)) + 1}
+          </option>
+        ))}
+      </select>
+    </div>
+  )
+}
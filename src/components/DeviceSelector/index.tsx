@@ -1,5 +1,6 @@
 import React, { useEffect } from 'react'
 import { invoke } from '@tauri-apps/api/core'
+import { listen } from '@tauri-apps/api/event'
 import { useAudioStore, type InputDevice } from '../../stores/audioStore'
 
 export function DeviceSelector(): React.ReactElement {
@@ -18,10 +19,10 @@ export function DeviceSelector(): React.ReactElement {
         const devices = await invoke<InputDevice[]>('get_input_device_names')
         setAvailableDevices(devices)
         
-        // Если устройство не выбрано, загружаем дефолтное
+        // Если устройство не выбрано, загружаем сохранённое (или дефолтное)
         if (!selectedDevice && devices.length > 0) {
-          const defaultDevice = await invoke<InputDevice>('get_default_input_device_name')
-          setSelectedDevice(defaultDevice)
+          const preferredDevice = await invoke<InputDevice>('get_preferred_input_device')
+          setSelectedDevice(preferredDevice)
         }
       } catch (err) {
         console.error('Failed to load audio devices:', err)
@@ -31,21 +32,54 @@ export function DeviceSelector(): React.ReactElement {
     loadDevices()
   }, [])
 
+  // Обновляем список при подключении/отключении устройств
+  useEffect(() => {
+    const unlisten = listen<InputDevice[]>('devices-changed', async (event) => {
+      const devices = event.payload
+      setAvailableDevices(devices)
+
+      // Выбранное устройство отключено - переключаемся на дефолтное
+      const current = useAudioStore.getState().selectedDevice
+      if (current && !devices.some(d => d.id === current.id)) {
+        try {
+          const defaultDevice = await invoke<InputDevice>('get_default_input_device_name')
+          setSelectedDevice(defaultDevice)
+        } catch (err) {
+          console.error('Failed to load default audio device:', err)
+          setSelectedDevice(devices[0] ?? null)
+        }
+      }
+    })
+
+    return () => {
+      unlisten.then((fn) => fn()).catch(console.error)
+    }
+  }, [])
+
   const handleDeviceChange = (event: React.ChangeEvent<HTMLSelectElement>) => {
-    const deviceName = event.target.value
-    const device = availableDevices.find(d => d.name === deviceName)
+    const deviceId = event.target.value
+    const device = availableDevices.find(d => d.id === deviceId)
     if (device) {
       setSelectedDevice(device)
     }
   }
 
+  // Источники звука системы (звонки, видео) показываем отдельной группой
+  const microphones = availableDevices.filter(d => !d.is_loopback)
+  const loopbacks = availableDevices.filter(d => d.is_loopback)
+  const renderOption = (device: InputDevice) => (
+    <option key={device.id} value={device.id}>
+      {device.name}{device.is_default ? ' (по умолчанию)' : ''}
+    </option>
+  )
+
   return (
     <div className="flex flex-col gap-2">
       <label className="text-sm font-medium text-gray-700">
         Устройство ввода
       </label>
       <select
-        value={selectedDevice?.name || ''}
+        value={selectedDevice?.id || ''}
         onChange={handleDeviceChange}
         disabled={isRecording}
         className="px-3 py-2 border border-gray-300 rounded-lg bg-white text-gray-900 
@@ -56,11 +90,16 @@ export function DeviceSelector(): React.ReactElement {
         {availableDevices.length === 0 ? (
           <option value="">Загрузка устройств...</option>
         ) : (
-          availableDevices.map((device) => (
-            <option key={device.name} value={device.name}>
-              {device.name}
-            </option>
-          ))
+          <>
+            <optgroup label="Микрофоны">
+              {microphones.map(renderOption)}
+            </optgroup>
+            {loopbacks.length > 0 && (
+              <optgroup label="Звук системы">
+                {loopbacks.map(renderOption)}
+              </optgroup>
+            )}
+          </>
         )}
       </select>
       {isRecording && (
@@ -68,6 +107,11 @@ export function DeviceSelector(): React.ReactElement {
           Остановите запись для смены устройства
         </p>
       )}
+      {selectedDevice?.is_loopback && (
+        <p className="text-xs text-gray-500">
+          Записывается звук, который воспроизводит компьютер
+        </p>
+      )}
     </div>
   )
 }
@@ -1,6 +1,7 @@
 import React, { useState, useEffect } from 'react'
 import { useRecord } from '../RecordButton/useRecord'
 import { useRecognitionStore } from '../../stores/recognitionStore'
+import { LevelMeter } from '../LevelMeter'
 
 export function FloatingWidget(): React.ReactElement {
   const { isRecording, startRecord, stopRecord } = useRecord()
@@ -70,8 +71,15 @@ export function FloatingWidget(): React.ReactElement {
         </div>
       </div>
 
+      {/* Уровень входного сигнала во время записи */}
+      {isRecording && (
+        <div className="absolute bottom-24 right-0 bg-white rounded-lg shadow-2xl p-3 w-48">
+          <LevelMeter />
+        </div>
+      )}
+
       {/* Развернутая панель с дополнительными действиями */}
-      {isExpanded && text && (
+      {isExpanded && !isRecording && text && (
         <div className="absolute bottom-24 right-0 bg-white rounded-lg shadow-2xl p-4 w-64">
           <div className="space-y-3">
             <div>
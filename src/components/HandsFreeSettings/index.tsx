@@ -0,0 +1,65 @@
+import React, { useEffect, useState } from 'react'
+import { invoke } from '@tauri-apps/api/core'
+import { useAudioStore, type AudioSettings } from '../../stores/audioStore'
+
+const SILENCE_OPTIONS: { label: string; ms: number }[] = [
+  { label: '0.5 секунды', ms: 500 },
+  { label: '1 секунда', ms: 1000 },
+  { label: '2 секунды', ms: 2000 },
+  { label: '3 секунды', ms: 3000 },
+]
+
+export function HandsFreeSettings(): React.ReactElement {
+  const { isRecording } = useAudioStore()
+  const [settings, setSettings] = useState<AudioSettings | null>(null)
+
+  // Загружаем сохранённые настройки при монтировании
+  useEffect(() => {
+    invoke<AudioSettings>('get_audio_settings')
+      .then(setSettings)
+      .catch((err) => console.error('Failed to load audio settings:', err))
+  }, [])
+
+  const update = async (patch: Partial<AudioSettings>) => {
+    if (!settings) return
+    try {
+      // Берём актуальные настройки: выбранное устройство сохраняется при старте записи
+      const current = await invoke<AudioSettings>('get_audio_settings')
+      const updated: AudioSettings = { ...current, ...patch }
+      setSettings(await invoke<AudioSettings>('update_audio_settings', { settings: updated }))
+    } catch (err) {
+      console.error('Failed to update audio settings:', err)
+    }
+  }
+
+  return (
+    <div className="flex flex-col gap-2">
+      <label className="flex items-center gap-2 text-sm font-medium text-gray-700">
+        <input
+          type="checkbox"
+          checked={settings?.hands_free ?? false}
+          onChange={(event) => update({ hands_free: event.target.checked })}
+          disabled={!settings || isRecording}
+        />
+        Запись по голосу (без кнопки)
+      </label>
+      {settings?.hands_free && (
+        <select
+          value={settings.vad_trailing_silence_ms}
+          onChange={(event) => update({ vad_trailing_silence_ms: Number(event.target.value) })}
+          disabled={isRecording}
+          className="px-3 py-2 border border-gray-300 rounded-lg bg-white text-gray-900
+                     disabled:bg-gray-100 disabled:cursor-not-allowed
+                     focus:ring-2 focus:ring-blue-500 focus:border-transparent
+                     transition-colors"
+        >
+          {SILENCE_OPTIONS.map((option) => (
+            <option key={option.ms} value={option.ms}>
+              Остановка после тишины: {option.label}
+            </option>
+          ))}
+        </select>
+      )}
+    </div>
+  )
+}
@@ -0,0 +1,31 @@
+import React from 'react'
+import { useAudioStore } from '../../stores/audioStore'
+
+// Нижняя граница шкалы в dBFS
+const METER_FLOOR_DBFS = -60
+
+export function LevelMeter(): React.ReactElement | null {
+  const { inputLevel } = useAudioStore()
+  if (!inputLevel) return null
+
+  const percent = Math.min(100, Math.max(0, (1 - inputLevel.peak_dbfs / METER_FLOOR_DBFS) * 100))
+  const barColor = inputLevel.clipping
+    ? 'bg-red-500'
+    : inputLevel.below_noise_floor
+      ? 'bg-gray-400'
+      : 'bg-green-500'
+
+  return (
+    <div className="flex flex-col gap-1 w-full">
+      <div className="h-1.5 w-full bg-gray-200 rounded-full overflow-hidden">
+        <div className={`h-full ${barColor} transition-all duration-75`} style={{ width: `${percent}%` }} />
+      </div>
+      {inputLevel.clipping && (
+        <p className="text-xs text-red-600">⚠ Перегрузка входа</p>
+      )}
+      {!inputLevel.clipping && inputLevel.below_noise_floor && (
+        <p className="text-xs text-amber-600">⚠ Микрофон молчит</p>
+      )}
+    </div>
+  )
+}
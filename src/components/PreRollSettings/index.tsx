@@ -0,0 +1,60 @@
+import React, { useEffect, useState } from 'react'
+import { invoke } from '@tauri-apps/api/core'
+import { useAudioStore, type AudioSettings } from '../../stores/audioStore'
+
+const PRE_ROLL_OPTIONS = [1, 2, 3, 5, 10]
+
+export function PreRollSettings(): React.ReactElement {
+  const { isRecording } = useAudioStore()
+  const [settings, setSettings] = useState<AudioSettings | null>(null)
+
+  // Загружаем сохранённые настройки при монтировании
+  useEffect(() => {
+    invoke<AudioSettings>('get_audio_settings')
+      .then(setSettings)
+      .catch((err) => console.error('Failed to load audio settings:', err))
+  }, [])
+
+  const update = async (patch: Partial<AudioSettings>) => {
+    if (!settings) return
+    try {
+      // Берём актуальные настройки: выбранное устройство сохраняется при старте записи
+      const current = await invoke<AudioSettings>('get_audio_settings')
+      const updated: AudioSettings = { ...current, ...patch }
+      setSettings(await invoke<AudioSettings>('update_audio_settings', { settings: updated }))
+    } catch (err) {
+      console.error('Failed to update audio settings:', err)
+    }
+  }
+
+  return (
+    <div className="flex flex-col gap-2">
+      <label className="flex items-center gap-2 text-sm font-medium text-gray-700">
+        <input
+          type="checkbox"
+          checked={settings?.pre_roll_enabled ?? false}
+          onChange={(event) => update({ pre_roll_enabled: event.target.checked })}
+          disabled={!settings || isRecording}
+        />
+        Не терять начало фразы (микрофон всегда включён)
+      </label>
+      {settings?.pre_roll_enabled && (
+        <select
+          value={settings.pre_roll_seconds}
+          onChange={(event) => update({ pre_roll_seconds: Number(event.target.value) })}
+          disabled={isRecording}
+          className="px-3 py-2 border border-gray-300 rounded-lg bg-white text-gray-900
+                     disabled:bg-gray-100 disabled:cursor-not-allowed
+                     focus:ring-2 focus:ring-blue-500 focus:border-transparent
+                     transition-colors"
+        >
+          {PRE_ROLL_OPTIONS.map((seconds) => (
+            <option key={seconds} value={seconds}>
+              {seconds} с до начала записи
+            </option>
+          ))}
+        </select>
+      )}
+    </div>
+  )
+}
@@ -0,0 +1,150 @@
+import React, { useEffect, useState } from 'react'
+import { invoke } from '@tauri-apps/api/core'
+import { type AudioSettings, type StageConfig } from '../../stores/audioStore'
+
+const PROFILE_LABELS: Record<string, string> = {
+  default: 'Обычный',
+  noisy_room: 'Шумное помещение',
+}
+
+// Стадии подавления шума; в цепочке стоит не больше одной из них
+type Suppression = 'off' | 'noise_gate' | 'denoise'
+
+const SUPPRESSION_OPTIONS: { label: string; value: Suppression; stage?: StageConfig }[] = [
+  { label: 'Выключено', value: 'off' },
+  {
+    label: 'Мягкий noise gate',
+    value: 'noise_gate',
+    stage: { stage: 'noise_gate', threshold_ratio: 0.15, max_threshold: 0.01, factor: 0.2 },
+  },
+  {
+    label: 'Спектральное вычитание шума',
+    value: 'denoise',
+    stage: { stage: 'denoise', profile_ms: 300, reduction_db: 20 },
+  },
+]
+
+const isSuppression = (stage: StageConfig) => stage.stage === 'noise_gate' || stage.stage === 'denoise'
+
+// Целевая громкость дубля после нормализации, LUFS
+const TARGET_LEVELS = [-24, -21, -18, -15, -12]
+
+const selectClassName = `px-3 py-2 border border-gray-300 rounded-lg bg-white text-gray-900
+                   disabled:bg-gray-100 disabled:cursor-not-allowed
+                   focus:ring-2 focus:ring-blue-500 focus:border-transparent
+                   transition-colors`
+
+export function ProcessingProfileSettings(): React.ReactElement {
+  const [settings, setSettings] = useState<AudioSettings | null>(null)
+
+  // Загружаем сохранённые настройки при монтировании
+  useEffect(() => {
+    invoke<AudioSettings>('get_audio_settings')
+      .then(setSettings)
+      .catch((err) => console.error('Failed to load audio settings:', err))
+  }, [])
+
+  // Профиль применяется при обработке дубля, поэтому менять его можно и во время записи
+  const update = async (change: (current: AudioSettings) => AudioSettings) => {
+    if (!settings) return
+    try {
+      // Берём актуальные настройки: выбранное устройство сохраняется при старте записи
+      const current = await invoke<AudioSettings>('get_audio_settings')
+      setSettings(await invoke<AudioSettings>('update_audio_settings', { settings: change(current) }))
+    } catch (err) {
+      console.error('Failed to update audio settings:', err)
+    }
+  }
+
+  const selectProfile = (name: string) => update((current) => ({ ...current, processing_profile: name }))
+
+  // Меняет цепочку стадий выбранного профиля
+  const updateChain = async (change: (stages: StageConfig[]) => StageConfig[]) => {
+    if (!settings) return
+    const profile = settings.processing_profile
+    try {
+      const current = await invoke<StageConfig[]>('get_processing_chain', { profile })
+      const stages = await invoke<StageConfig[]>('update_processing_chain', { profile, stages: change(current) })
+      setSettings({
+        ...settings,
+        processing_profiles: { ...settings.processing_profiles, [profile]: { stages } },
+      })
+    } catch (err) {
+      console.error('Failed to update processing chain:', err)
+    }
+  }
+
+  // Подавление шума стоит сразу после фильтров (смещение и гул убираются до оценки шума)
+  const setSuppression = (mode: Suppression) =>
+    updateChain((stages) => {
+      const rest = stages.filter((stage) => !isSuppression(stage))
+      const option = SUPPRESSION_OPTIONS.find((item) => item.value === mode)
+      if (!option?.stage) return rest
+      const position = rest.filter((stage) => stage.stage === 'dc_block' || stage.stage === 'high_pass').length
+      return [...rest.slice(0, position), option.stage, ...rest.slice(position)]
+    })
+
+  const setTargetLevel = (target_lufs: number) =>
+    updateChain((stages) =>
+      stages.map((stage) => (stage.stage === 'loudness' ? { ...stage, target_lufs } : stage)),
+    )
+
+  const stages = settings?.processing_profiles[settings.processing_profile]?.stages
+  const suppression = stages?.find(isSuppression)
+  const loudness = stages?.find((stage) => stage.stage === 'loudness')
+
+  return (
+    <div className="flex flex-col gap-2">
+      <label className="text-sm font-medium text-gray-700">
+        Профиль обработки
+      </label>
+      <select
+        value={settings?.processing_profile ?? ''}
+        onChange={(event) => selectProfile(event.target.value)}
+        disabled={!settings}
+        className={selectClassName}
+      >
+        {Object.keys(settings?.processing_profiles ?? {}).map((name) => (
+          <option key={name} value={name}>
+            {PROFILE_LABELS[name] ?? name}
+          </option>
+        ))}
+      </select>
+      {stages && (
+        <select
+          value={suppression?.stage ?? 'off'}
+          onChange={(event) => setSuppression(event.target.value as Suppression)}
+          className={selectClassName}
+        >
+          {SUPPRESSION_OPTIONS.map((option) => (
+            <option key={option.value} value={option.value}>
+              Подавление шума: {option.label}
+            </option>
+          ))}
+        </select>
+      )}
+      {loudness?.stage === 'loudness' && (
+        <select
+          value={loudness.target_lufs}
+          onChange={(event) => setTargetLevel(Number(event.target.value))}
+          className={selectClassName}
+        >
+          {!TARGET_LEVELS.includes(loudness.target_lufs) && (
+            <option value={loudness.target_lufs}>Громкость речи: {loudness.target_lufs} LUFS</option>
+          )}
+          {TARGET_LEVELS.map((level) => (
+            <option key={level} value={level}>
+              Громкость речи: {level} LUFS{level === -18 ? ' (рекомендуется)' : ''}
+            </option>
+          ))}
+        </select>
+      )}
+      {suppression?.stage === 'denoise' && (
+        <p className="text-xs text-gray-500">
+          Шум запоминается по первым {suppression.profile_ms} мс записи: сделайте паузу перед речью
+          или включите pre-roll
+        </p>
+      )}
+    </div>
+  )
+}
@@ -1,13 +1,15 @@
 import React from 'react'
 import { useRecord } from './useRecord'
+import { useAudioStore } from '../../stores/audioStore'
 
 export function RecordButton(): React.ReactElement {
-  const { isRecording, startRecord, stopRecord } = useRecord()
+  const { isRecording, isPaused, startRecord, stopRecord, togglePause } = useRecord()
+  const { droppedSamples, deviceNotice } = useAudioStore()
 
   return (
     <div className="relative inline-block">
       {/* Пульсирующие круги при записи */}
-      {isRecording && (
+      {isRecording && !isPaused && (
         <>
           <span className="absolute inset-0 rounded-lg bg-red-500 opacity-75 animate-ping" />
           <span className="absolute inset-0 rounded-lg bg-red-500 opacity-50 animate-pulse" />
@@ -35,6 +37,24 @@ export function RecordButton(): React.ReactElement {
           )}
         </span>
       </button>
+      {isRecording && (
+        <button
+          onClick={togglePause}
+          className="relative ml-2 px-4 py-3 rounded-lg font-semibold bg-gray-200 hover:bg-gray-300 text-gray-800 transition-colors"
+        >
+          {isPaused ? '▶ Продолжить' : '⏸ Пауза'}
+        </button>
+      )}
+      {droppedSamples > 0 && (
+        <p className="absolute left-0 right-0 top-full mt-2 text-xs text-amber-600 text-center whitespace-nowrap">
+          ⚠ Потеряно сэмплов: {droppedSamples} (перегрузка аудиопотока)
+        </p>
+      )}
+      {deviceNotice && (
+        <p className={`absolute left-0 right-0 ${droppedSamples > 0 ? 'top-full mt-6' : 'top-full mt-2'} text-xs text-amber-600 text-center whitespace-nowrap`}>
+          ⚠ {deviceNotice}
+        </p>
+      )}
     </div>
   )
 }
@@ -2,14 +2,16 @@
 import { useState, useEffect } from 'react'
 import { invoke } from '@tauri-apps/api/core'
 import { listen } from '@tauri-apps/api/event'
-import { useRecognitionStore } from '../../stores/recognitionStore'
-import { useAudioStore } from '../../stores/audioStore'
+import { useRecognitionStore, type ProcessingReport } from '../../stores/recognitionStore'
+import { useAudioStore, type DeviceConfig, type InputDevice, type LevelReport, type RecordingStatus, type StateChange } from '../../stores/audioStore'
 import { useRecognition } from '../../hooks/useRecognition'
 
 interface UseRecordReturn {
   isRecording: boolean
+  isPaused: boolean
   startRecord: () => Promise<void>
   stopRecord: () => Promise<void>
+  togglePause: () => Promise<void>
 }
 
 interface RecognitionResult {
@@ -17,34 +19,53 @@ interface RecognitionResult {
   audio_path: string
 }
 
+interface OverrunReport {
+  dropped_samples: number
+}
+
+interface HandsFreeReport {
+  recording: boolean
+}
+
+// Дорожка записи звонка после обработки (audio/worker.rs ProcessedTrack)
+interface ProcessedTrack extends ProcessingReport {
+  label: string | null
+  pre_path: string
+  post_path: string
+}
+
+interface DeviceLostReport {
+  lost_device: string
+  switched_to: string | null
+  error: string | null
+}
+
+// Ошибка stream во время захвата (audio/session.rs RecordingErrorReport)
+interface RecordingErrorReport {
+  kind: 'device_lost' | 'backend' | 'buffer_overrun'
+  device: string
+  track: string | null
+  message: string
+  recovered: boolean
+}
+
 let listenersRefCount = 0
 let unlistenProcessingRef: Promise<() => void> | null = null
+let unlistenTracksProcessingRef: Promise<() => void> | null = null
 let unlistenRecognitionRef: Promise<() => void> | null = null
+let unlistenOverrunRef: Promise<() => void> | null = null
+let unlistenDeviceLostRef: Promise<() => void> | null = null
+let unlistenLevelRef: Promise<() => void> | null = null
+let unlistenHandsFreeRef: Promise<() => void> | null = null
+let unlistenRecordingErrorRef: Promise<() => void> | null = null
+let unlistenStateRef: Promise<() => void> | null = null
 
 export function useRecord(): UseRecordReturn {
   const [isRecording, setIsRecording] = useState(false)
-  const { recognize } = useRecognition()
-  const { setWavPaths, setIsProcessing, setText, setLastResultEmpty } = useRecognitionStore()
-  const { selectedDevice } = useAudioStore()
-
-  // Проверяем статус записи каждую секунду для отслеживания авто-стопа
-  useEffect(() => {
-    if (!isRecording) return
-
-    const checkStatus = setInterval(async () => {
-      try {
-        const status = await invoke<boolean>('get_recording_status')
-        if (!status && isRecording) {
-          console.log('[useRecord] Auto-stop detected, updating UI')
-          setIsRecording(false)
-        }
-      } catch (err) {
-        console.error('Failed to check recording status:', err)
-      }
-    }, 1000)
-
-    return () => clearInterval(checkStatus)
-  }, [isRecording])
+  const [isPaused, setIsPaused] = useState(false)
+  const { recognize, recognizeTracks } = useRecognition()
+  const { setWavPaths, setProcessingReport, setIsProcessing, setText, setLastResultEmpty } = useRecognitionStore()
+  const { selectedDevice, setDroppedSamples, setDeviceNotice, setInputLevel } = useAudioStore()
 
   // Подписываемся на события один раз глобально
   useEffect(() => {
@@ -54,16 +75,35 @@ export function useRecord(): UseRecordReturn {
       console.log('[useRecord] Setting up event listeners')
       let isProcessing = false
 
-      unlistenProcessingRef = listen<[string, string]>('processing-finished', async (event) => {
+      // Состояние сессии ведёт Rust: авто-стоп, тишина hands-free и ошибки приходят отсюда
+      unlistenStateRef = listen<StateChange>('recording-state', (event) => {
+        const { from, to } = event.payload
+        console.log(`[useRecord] recording-state: ${from} -> ${to}`)
+        setIsRecording(to === 'arming' || to === 'recording' || to === 'paused')
+        setIsPaused(to === 'paused')
+        if (to === 'finalizing') {
+          setInputLevel(null)
+        }
+        if (to === 'queued') {
+          setIsProcessing(true)
+        }
+        // Дубль не ушёл в обработку (пустой или не записался) - processing-finished не придёт
+        if (from === 'finalizing' && to === 'idle') {
+          setIsProcessing(false)
+        }
+      })
+
+      unlistenProcessingRef = listen<[string, string, ProcessingReport]>('processing-finished', async (event) => {
         if (isProcessing) return
 
         isProcessing = true
         console.log('[useRecord] processing-finished event received:', event.payload)
-        const [prePath, postPath] = event.payload
-        console.log('Processing finished:', { prePath, postPath })
+        const [prePath, postPath, report] = event.payload
+        console.log('Processing finished:', { prePath, postPath, report })
 
-        // Сохраняем пути в store
+        // Сохраняем пути и смещение обрезанного дубля в store
         setWavPaths(prePath, postPath)
+        setProcessingReport(report)
         setIsProcessing(false)
 
         // Автоматически запускаем распознавание на post-обработанном файле
@@ -77,6 +117,30 @@ export function useRecord(): UseRecordReturn {
         }
       })
 
+      // Запись звонка: каждая дорожка распознаётся отдельно и сводится в диалог
+      unlistenTracksProcessingRef = listen<ProcessedTrack[]>('tracks-processing-finished', async (event) => {
+        if (isProcessing || event.payload.length === 0) return
+
+        isProcessing = true
+        console.log('[useRecord] tracks-processing-finished event received:', event.payload)
+        const [first] = event.payload
+        setWavPaths(first.pre_path, first.post_path)
+        setProcessingReport({ trim: first.trim, loudness: first.loudness, quality: first.quality })
+        setIsProcessing(false)
+
+        try {
+          await recognizeTracks(event.payload.map((track, index) => ({
+            label: track.label ?? `Track ${index + 1}`,
+            audio_path: track.post_path,
+            offset_ms: track.trim?.start_ms ?? 0
+          })))
+        } catch (err) {
+          console.error('Auto-recognition failed:', err)
+        } finally {
+          isProcessing = false
+        }
+      })
+
       // Подписываемся на событие recognition-completed для получения результата
       unlistenRecognitionRef = listen<RecognitionResult>('recognition-completed', (event) => {
         console.log('[useRecord] recognition-completed event received:', event.payload)
@@ -93,6 +157,69 @@ export function useRecord(): UseRecordReturn {
           setLastResultEmpty(false)
         }
       })
+
+      // Потери сэмплов из-за перегрузки аудиопотока
+      unlistenOverrunRef = listen<OverrunReport>('audio-overrun', (event) => {
+        console.warn('[useRecord] audio-overrun:', event.payload)
+        setDroppedSamples(event.payload.dropped_samples)
+      })
+
+      // Уровень входного сигнала (~20 раз в секунду во время записи)
+      unlistenLevelRef = listen<LevelReport>('audio-level', (event) => {
+        setInputLevel(event.payload)
+      })
+
+      // Режим hands-free: дубль начат или завершён детектором речи
+      unlistenHandsFreeRef = listen<HandsFreeReport>('hands-free-recording', (event) => {
+        console.log('[useRecord] hands-free-recording:', event.payload)
+        if (event.payload.recording) {
+          setIsRecording(true)
+          setIsProcessing(true)
+          setDroppedSamples(0)
+          setDeviceNotice(null)
+        } else {
+          // Дубль уже в очереди обработки, ждём processing-finished
+          setIsRecording(false)
+          setInputLevel(null)
+        }
+      })
+
+      // Устройство отключилось во время записи
+      unlistenDeviceLostRef = listen<DeviceLostReport>('recording-device-lost', (event) => {
+        console.warn('[useRecord] recording-device-lost:', event.payload)
+        const { lost_device, switched_to } = event.payload
+        if (switched_to) {
+          setDeviceNotice(`Устройство «${lost_device}» отключено, запись продолжается на «${switched_to}»`)
+          return
+        }
+
+        // Записанное поток сессии сам отправил на обработку - дальше ведёт recording-state
+        setDeviceNotice(`Устройство «${lost_device}» отключено, запись остановлена`)
+      })
+
+      // Ошибка stream: сбой драйвера или переполнение буфера
+      unlistenRecordingErrorRef = listen<RecordingErrorReport>('recording-error', (event) => {
+        console.warn('[useRecord] recording-error:', event.payload)
+        const { kind, device, track, recovered } = event.payload
+        // Переполнение показывает счётчик потерь
+        if (kind === 'buffer_overrun') return
+        // Дорожка звонка: основная запись идёт дальше, пропадает только эта дорожка
+        if (track) {
+          setDeviceNotice(recovered
+            ? `Сбой источника дорожки «${track}» («${device}»), запись продолжается`
+            : `Источник дорожки «${track}» («${device}») недоступен, дорожка остановлена`)
+          return
+        }
+        // Отключение основного устройства показывает recording-device-lost
+        if (kind !== 'backend') return
+        if (recovered) {
+          setDeviceNotice(`Сбой звукового драйвера на «${device}», запись продолжается`)
+          return
+        }
+
+        // Записанное поток сессии сам отправил на обработку - дальше ведёт recording-state
+        setDeviceNotice(`Сбой звукового драйвера на «${device}», запись остановлена`)
+      })
     }
 
     return () => {
@@ -100,28 +227,46 @@ export function useRecord(): UseRecordReturn {
       if (listenersRefCount === 0) {
         console.log('[useRecord] Cleaning up event listeners')
         unlistenProcessingRef?.then((fn) => fn()).catch(console.error)
+        unlistenTracksProcessingRef?.then((fn) => fn()).catch(console.error)
         unlistenRecognitionRef?.then((fn) => fn()).catch(console.error)
+        unlistenOverrunRef?.then((fn) => fn()).catch(console.error)
+        unlistenDeviceLostRef?.then((fn) => fn()).catch(console.error)
+        unlistenLevelRef?.then((fn) => fn()).catch(console.error)
+        unlistenHandsFreeRef?.then((fn) => fn()).catch(console.error)
+        unlistenRecordingErrorRef?.then((fn) => fn()).catch(console.error)
+        unlistenStateRef?.then((fn) => fn()).catch(console.error)
         unlistenProcessingRef = null
+        unlistenTracksProcessingRef = null
         unlistenRecognitionRef = null
+        unlistenOverrunRef = null
+        unlistenDeviceLostRef = null
+        unlistenLevelRef = null
+        unlistenHandsFreeRef = null
+        unlistenRecordingErrorRef = null
+        unlistenStateRef = null
       }
     }
   }, [])
 
   const startRecord = async () => {
     setIsRecording(true)
+    setIsPaused(false)
     setIsProcessing(true)
+    setDroppedSamples(0)
+    setDeviceNotice(null)
+    setInputLevel(null)
     try {
       // Используем выбранное устройство или получаем дефолтное
-      let deviceName: string
+      let deviceId: string
       if (selectedDevice) {
-        deviceName = selectedDevice.name
+        deviceId = selectedDevice.id
       } else {
-        const deviceInfo = await invoke<{ name: string }>('get_default_input_device_name')
-        deviceName = deviceInfo.name
+        const deviceInfo = await invoke<InputDevice>('get_preferred_input_device')
+        deviceId = deviceInfo.id
       }
       
-      await invoke('start_recording', { device: deviceName })
-      console.log('Recording started with device:', deviceName)
+      const config = await invoke<DeviceConfig>('start_recording', { device: deviceId })
+      console.log('Recording started with device:', deviceId, config)
     } catch (err) {
       console.error('Failed to start recording:', err)
       setIsRecording(false)
@@ -131,6 +276,8 @@ export function useRecord(): UseRecordReturn {
 
   const stopRecord = async () => {
     setIsRecording(false)
+    setIsPaused(false)
+    setInputLevel(null)
     try {
       await invoke('stop_recording')
       console.log('Recording stopped')
@@ -140,5 +287,15 @@ export function useRecord(): UseRecordReturn {
     }
   }
 
-  return { isRecording, startRecord, stopRecord }
+  // Пауза оставляет запись в том же дубле
+  const togglePause = async () => {
+    try {
+      const status = await invoke<RecordingStatus>(isPaused ? 'resume_recording' : 'pause_recording')
+      setIsPaused(status.is_paused)
+    } catch (err) {
+      console.error('Failed to toggle pause:', err)
+    }
+  }
+
+  return { isRecording, isPaused, startRecord, stopRecord, togglePause }
 }
@@ -0,0 +1,63 @@
+import React, { useEffect, useState } from 'react'
+import { invoke } from '@tauri-apps/api/core'
+import { useAudioStore, type AudioSettings } from '../../stores/audioStore'
+
+const LIMIT_OPTIONS: { label: string; seconds: number | null }[] = [
+  { label: '30 секунд', seconds: 30 },
+  { label: '1 минута', seconds: 60 },
+  { label: '5 минут', seconds: 300 },
+  { label: '15 минут', seconds: 900 },
+  { label: '60 минут', seconds: 3600 },
+  { label: 'Без ограничения', seconds: null },
+]
+
+export function RecordingLimitSelector(): React.ReactElement {
+  const { isRecording } = useAudioStore()
+  const [settings, setSettings] = useState<AudioSettings | null>(null)
+
+  // Загружаем сохранённые настройки при монтировании
+  useEffect(() => {
+    invoke<AudioSettings>('get_audio_settings')
+      .then(setSettings)
+      .catch((err) => console.error('Failed to load audio settings:', err))
+  }, [])
+
+  const handleLimitChange = async (event: React.ChangeEvent<HTMLSelectElement>) => {
+    if (!settings) return
+    const value = event.target.value
+    try {
+      // Берём актуальные настройки: выбранное устройство сохраняется при старте записи
+      const current = await invoke<AudioSettings>('get_audio_settings')
+      const updated: AudioSettings = {
+        ...current,
+        max_record_seconds: value === '' ? null : Number(value),
+      }
+      setSettings(await invoke<AudioSettings>('update_audio_settings', { settings: updated }))
+    } catch (err) {
+      console.error('Failed to update audio settings:', err)
+    }
+  }
+
+  return (
+    <div className="flex flex-col gap-2">
+      <label className="text-sm font-medium text-gray-700">
+        Максимальная длительность записи
+      </label>
+      <select
+        value={settings?.max_record_seconds?.toString() ?? ''}
+        onChange={handleLimitChange}
+        disabled={!settings || isRecording}
+        className="px-3 py-2 border border-gray-300 rounded-lg bg-white text-gray-900
+                   disabled:bg-gray-100 disabled:cursor-not-allowed
+                   focus:ring-2 focus:ring-blue-500 focus:border-transparent
+                   transition-colors"
+      >
+        {LIMIT_OPTIONS.map((option) => (
+          <option key={option.label} value={option.seconds?.toString() ?? ''}>
+            {option.label}
+          </option>
+        ))}
+      </select>
+    </div>
+  )
+}
@@ -1,5 +1,11 @@
 import React, { useState } from 'react'
 import { DeviceSelector } from '../DeviceSelector'
+import { RecordingLimitSelector } from '../RecordingLimitSelector'
+import { ChannelStrategySelector } from '../ChannelStrategySelector'
+import { PreRollSettings } from '../PreRollSettings'
+import { HandsFreeSettings } from '../HandsFreeSettings'
+import { CallTrackSettings } from '../CallTrackSettings'
+import { ProcessingProfileSettings } from '../ProcessingProfileSettings'
 
 interface SettingsPanelProps {}
 
@@ -20,6 +26,12 @@ export function SettingsPanel({}: SettingsPanelProps): React.ReactElement {
       {isOpen && (
         <div className="space-y-4">
           <DeviceSelector />
+          <RecordingLimitSelector />
+          <ChannelStrategySelector />
+          <PreRollSettings />
+          <HandsFreeSettings />
+          <CallTrackSettings />
+          <ProcessingProfileSettings />
           <div className="flex items-center justify-between">
             <label className="text-gray-700">Язык</label>
             <select className="px-3 py-2 border border-gray-300 rounded-lg">
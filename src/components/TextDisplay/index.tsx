@@ -2,7 +2,7 @@ import React, { useState } from 'react'
 import { useRecognitionStore } from '../../stores/recognitionStore'
 
 export function TextDisplay(): React.ReactElement {
-  const { text, isProcessing, isRecognizing, lastResultEmpty } = useRecognitionStore()
+  const { text, isProcessing, isRecognizing, lastResultEmpty, processingReport } = useRecognitionStore()
   const [copied, setCopied] = useState(false)
 
   const isLoading = isProcessing || isRecognizing
@@ -46,6 +46,12 @@ export function TextDisplay(): React.ReactElement {
       {!text && !isLoading && !lastResultEmpty && (
         <p className="text-gray-400">Распознанный текст появится здесь</p>
       )}
+      {/* Предупреждения о качестве записи: почему распознавание могло ошибиться */}
+      {!isLoading && processingReport?.quality?.warnings.map((warning) => (
+        <p key={warning} className="mt-2 text-xs text-amber-600">
+          ⚠ {warning}
+        </p>
+      ))}
     </div>
   )
 }
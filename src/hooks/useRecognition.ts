@@ -9,6 +9,15 @@ interface UseRecognitionReturn {
   isLoading: boolean
   error: Error | null
   recognize: (audioPath: string) => Promise<void>
+  recognizeTracks: (tracks: TrackAudio[]) => Promise<void>
+}
+
+// Дорожка записи звонка (commands/recognition.rs TrackAudio)
+export interface TrackAudio {
+  label: string
+  audio_path: string
+  // Сколько миллисекунд тишины обрезано в начале дорожки
+  offset_ms: number
 }
 
 export function useRecognition(): UseRecognitionReturn {
@@ -16,13 +25,13 @@ export function useRecognition(): UseRecognitionReturn {
   const [error, setError] = useState<Error | null>(null)
   const { text, setText, setIsRecognizing, setLastResultEmpty } = useRecognitionStore()
 
-  // @ts-ignore
-  const recognize = async (audioPath: string) => {
+  // Общий ход распознавания: одна запись или дорожки звонка
+  const runRecognition = async (run: () => Promise<string>) => {
     setIsLoading(true)
     setIsRecognizing(true)
     setError(null)
     try {
-      const result = await invoke<string>('recognize_audio', { audioPath })
+      const result = await run()
       const trimmed = result.trim()
       if (trimmed.length === 0) {
         setText('')
@@ -42,5 +51,15 @@ export function useRecognition(): UseRecognitionReturn {
     }
   }
 
-  return { text, setText, isLoading, error, recognize }
+  // @ts-ignore
+  const recognize = async (audioPath: string) => {
+    await runRecognition(() => invoke<string>('recognize_audio', { audioPath }))
+  }
+
+  // Каждая дорожка распознаётся отдельно и сводится в диалог «Me» / «Them»
+  const recognizeTracks = async (tracks: TrackAudio[]) => {
+    await runRecognition(() => invoke<string>('recognize_tracks', { tracks }))
+  }
+
+  return { text, setText, isLoading, error, recognize, recognizeTracks }
 }
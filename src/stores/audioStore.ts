@@ -1,7 +1,167 @@
 import { create } from 'zustand'
 
+// Конфигурация устройства по умолчанию
+export interface DeviceConfig {
+  sample_rate: number
+  channels: number
+  sample_format: string
+}
+
+// Поддерживаемый устройством диапазон конфигураций
+export interface ConfigRange {
+  channels: number
+  min_sample_rate: number
+  max_sample_rate: number
+  sample_format: string
+}
+
+// Возможности устройства (get_device_capabilities)
+export interface DeviceCapabilities {
+  device: InputDevice
+  sample_rates: number[]
+  channels: number[]
+  sample_formats: string[]
+  configs: ConfigRange[]
+}
+
+// Желаемые параметры stream для start_recording
+export interface StreamPreferences {
+  sample_rate?: number
+  channels?: number
+  sample_format?: string
+}
+
+// Входное устройство (audio/devices.rs InputDevice)
 export interface InputDevice {
+  id: string
   name: string
+  host_api: string
+  default_config: DeviceConfig | null
+  is_default: boolean
+  // Звук системы (monitor / loopback), а не микрофон
+  is_loopback: boolean
+}
+
+// Состояние сессии записи (audio/state.rs SessionState)
+export type SessionState = 'idle' | 'arming' | 'recording' | 'paused' | 'finalizing' | 'queued'
+
+// Переход состояния (событие 'recording-state', audio/state.rs StateChange)
+export interface StateChange {
+  from: SessionState
+  to: SessionState
+}
+
+// Статус записи (get_recording_status, types.rs RecordingStatus)
+export interface RecordingStatus {
+  state: SessionState
+  is_recording: boolean
+  is_paused: boolean
+  device: string | null
+  elapsed_seconds: number
+  max_record_seconds: number | null
+  dropped_samples: number
+}
+
+// Уровень входного сигнала (событие 'audio-level', audio/level.rs LevelReport)
+export interface LevelReport {
+  rms: number
+  peak: number
+  rms_dbfs: number
+  peak_dbfs: number
+  clipping: boolean
+  below_noise_floor: boolean
+}
+
+// Сведение многоканального входа в моно (audio/downmix.rs ChannelStrategy)
+export type ChannelStrategy =
+  | { mode: 'average' }
+  | { mode: 'channel'; index: number }
+  | { mode: 'loudest' }
+
+// Параметры удаления постоянной составляющей (audio/biquad.rs DcBlockParams)
+export interface DcBlockParams {
+  cutoff_hz: number
+}
+
+// Параметры фильтра высоких частот (audio/biquad.rs HighPassParams)
+export interface HighPassParams {
+  cutoff_hz: number
+  q: number
+}
+
+// Параметры мягкого noise gate (audio/processor.rs GateParams)
+export interface GateParams {
+  threshold_ratio: number
+  max_threshold: number
+  factor: number
+}
+
+// Параметры спектрального вычитания шума (audio/denoise.rs DenoiseParams)
+export interface DenoiseParams {
+  profile_ms: number
+  reduction_db: number
+}
+
+// Параметры обрезки тишины (audio/trim.rs TrimParams)
+export interface TrimParams {
+  threshold_db: number
+  leading_margin_ms: number
+  trailing_margin_ms: number
+}
+
+// Параметры АРУ (audio/agc.rs AgcParams)
+export interface AgcParams {
+  target_dbfs: number
+  attack_ms: number
+  release_ms: number
+  max_gain_db: number
+}
+
+// Параметры нормализации громкости (audio/loudness.rs LoudnessParams)
+export interface LoudnessParams {
+  target_lufs: number
+  max_gain_db: number
+}
+
+// Параметры limiter (audio/limiter.rs LimiterParams)
+export interface LimiterParams {
+  threshold: number
+  lookahead_ms: number
+}
+
+// Стадия цепочки обработки (audio/pipeline.rs StageConfig)
+export type StageConfig =
+  | ({ stage: 'dc_block' } & DcBlockParams)
+  | ({ stage: 'high_pass' } & HighPassParams)
+  | ({ stage: 'noise_gate' } & GateParams)
+  | ({ stage: 'denoise' } & DenoiseParams)
+  | ({ stage: 'trim' } & TrimParams)
+  | ({ stage: 'agc' } & AgcParams)
+  | ({ stage: 'loudness' } & LoudnessParams)
+  | ({ stage: 'limiter' } & LimiterParams)
+
+// Профиль обработки дубля (utils/config.rs ProcessingProfile)
+export interface ProcessingProfile {
+  stages: StageConfig[]
+}
+
+// Настройки записи (utils/config.rs AudioSettings)
+export interface AudioSettings {
+  max_record_seconds: number | null
+  spill_after_seconds: number
+  input_device: string | null
+  device_failover: boolean
+  channel_strategy: ChannelStrategy
+  noise_floor_dbfs: number
+  pre_roll_enabled: boolean
+  pre_roll_seconds: number
+  hands_free: boolean
+  vad_trailing_silence_ms: number
+  vad_threshold_db: number
+  call_track_device: string | null
+  processing_profile: string
+  processing_profiles: Record<string, ProcessingProfile>
+  recognition_language: string
 }
 
 interface AudioStore {
@@ -13,6 +173,12 @@ interface AudioStore {
   setSelectedDevice: (device: InputDevice | null) => void
   availableDevices: InputDevice[]
   setAvailableDevices: (devices: InputDevice[]) => void
+  droppedSamples: number
+  setDroppedSamples: (count: number) => void
+  deviceNotice: string | null
+  setDeviceNotice: (notice: string | null) => void
+  inputLevel: LevelReport | null
+  setInputLevel: (level: LevelReport | null) => void
 }
 
 export const useAudioStore = create<AudioStore>((set) => ({
@@ -24,4 +190,10 @@ export const useAudioStore = create<AudioStore>((set) => ({
   setSelectedDevice: (device) => set({ selectedDevice: device }),
   availableDevices: [],
   setAvailableDevices: (devices) => set({ availableDevices: devices }),
+  droppedSamples: 0,
+  setDroppedSamples: (count) => set({ droppedSamples: count }),
+  deviceNotice: null,
+  setDeviceNotice: (notice) => set({ deviceNotice: notice }),
+  inputLevel: null,
+  setInputLevel: (level) => set({ inputLevel: level }),
 }))
@@ -1,5 +1,37 @@
 import { create } from 'zustand'
 
+// Часть дубля, оставшаяся после обрезки тишины (audio/trim.rs TrimOffsets)
+export interface TrimOffsets {
+  start_ms: number
+  end_ms: number
+}
+
+// Громкость дубля как записан и усиление нормализации (audio/loudness.rs LoudnessReport)
+export interface LoudnessReport {
+  integrated_lufs: number | null
+  true_peak_dbtp: number | null
+  gain_db: number
+}
+
+// Качество дубля как записан (audio/quality.rs QualityReport)
+export interface QualityReport {
+  duration_seconds: number
+  peak_dbfs: number
+  rms_dbfs: number
+  clipping_percent: number
+  snr_db: number | null
+  noise_floor_dbfs: number
+  speech_ratio: number
+  warnings: string[]
+}
+
+// Что обработка сделала с дублем (audio/processor.rs ProcessingReport)
+export interface ProcessingReport {
+  trim: TrimOffsets | null
+  loudness: LoudnessReport | null
+  quality: QualityReport | null
+}
+
 interface RecognitionStore {
   text: string
   setText: (text: string) => void
@@ -12,6 +44,9 @@ interface RecognitionStore {
   preWavPath: string | null
   postWavPath: string | null
   setWavPaths: (pre: string | null, post: string | null) => void
+  // Время в post WAV + trim.start_ms = время в pre WAV
+  processingReport: ProcessingReport | null
+  setProcessingReport: (report: ProcessingReport | null) => void
   history: string[]
   addToHistory: (item: string) => void
 }
@@ -28,6 +63,8 @@ export const useRecognitionStore = create<RecognitionStore>((set) => ({
   preWavPath: null,
   postWavPath: null,
   setWavPaths: (pre, post) => set({ preWavPath: pre, postWavPath: post }),
+  processingReport: null,
+  setProcessingReport: (report) => set({ processingReport: report }),
   history: [],
   addToHistory: (item) =>
     set((state) => ({